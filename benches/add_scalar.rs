@@ -5,7 +5,8 @@ use halo2_proofs::poly::kzg::strategy::AccumulatorStrategy as KZGAccumulatorStra
 use halo2curves::bn256::{Bn256, Fq, Fr};
 use std::marker::PhantomData;
 // use mycrate::fibonacci;
-use halo2_examples::add1::example1::MyCircuit;
+use halo2_examples::add1::example1::{expected_output, MyCircuit};
+use halo2_examples::add1::example2::{min_k, MyCircuit as WideCircuit};
 use halo2_examples::proof::{create_proof, keygen, verify_proof};
 use halo2_proofs::dev::MockProver;
 use halo2_proofs::poly::commitment::{CommitmentScheme, ParamsProver, Prover, Verifier};
@@ -66,9 +67,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         rng,
         &ipa_small_params,
         &ipa_small_pk,
-        ipa_small_circuit.clone(),
-        small_nrows,
-        small_k,
+        &[ipa_small_circuit.clone()],
+        &[&[&[ipa_small_out]]],
     );
 
     // Generate Big IPA proof
@@ -84,9 +84,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         rng,
         &ipa_big_params,
         &ipa_big_pk,
-        ipa_big_circuit.clone(),
-        big_nrows,
-        big_k,
+        &[ipa_big_circuit.clone()],
+        &[&[&[ipa_big_out]]],
     );
 
     // Generate Small KZG proof
@@ -109,9 +108,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
             rng,
             &kzg_small_params,
             &kzg_small_pk,
-            kzg_small_circuit.clone(),
-            small_nrows,
-            small_k,
+            &[kzg_small_circuit.clone()],
+            &[&[&[kzg_small_out]]],
         );
 
     // Generate Big KZG proof
@@ -127,9 +125,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
             rng,
             &kzg_big_params,
             &kzg_big_pk,
-            kzg_big_circuit.clone(),
-            big_nrows,
-            big_k,
+            &[kzg_big_circuit.clone()],
+            &[&[&[kzg_big_out]]],
         );
 
     // Generate (small) KZG Verifier params
@@ -207,9 +204,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 black_box(rng),
                 black_box(&ipa_small_params),
                 black_box(&ipa_small_pk),
-                black_box(ipa_small_circuit.clone()),
-                black_box(small_nrows),
-                black_box(small_k),
+                &[black_box(ipa_small_circuit.clone())],
+                &[black_box(&[&[ipa_small_out][..]])],
             )
         })
     });
@@ -219,9 +215,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 black_box(rng),
                 black_box(&ipa_big_params),
                 black_box(&ipa_big_pk),
-                black_box(ipa_big_circuit.clone()),
-                black_box(big_nrows),
-                black_box(big_k),
+                &[black_box(ipa_big_circuit.clone())],
+                &[black_box(&[&[ipa_big_out][..]])],
             )
         })
     });
@@ -231,9 +226,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 black_box(rng),
                 black_box(&kzg_small_params),
                 black_box(&kzg_small_pk),
-                black_box(kzg_small_circuit.clone()),
-                black_box(small_nrows),
-                black_box(small_k),
+                &[black_box(kzg_small_circuit.clone())],
+                &[black_box(&[&[kzg_small_out][..]])],
             )
         })
     });
@@ -243,9 +237,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 black_box(rng),
                 black_box(&kzg_big_params),
                 black_box(&kzg_big_pk),
-                black_box(kzg_big_circuit.clone()),
-                black_box(big_nrows),
-                black_box(big_k),
+                &[black_box(kzg_big_circuit.clone())],
+                &[black_box(&[&[kzg_big_out][..]])],
             )
         })
     });
@@ -261,7 +254,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 black_box(ipa_small_verifier_params),
                 black_box(ipa_small_pk.get_vk()),
                 black_box(&ipa_small_proof[..]),
-                black_box(small_nrows),
+                &[black_box(&[&[ipa_small_out][..]])],
             );
         })
     });
@@ -277,7 +270,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 black_box(ipa_big_verifier_params),
                 black_box(ipa_big_pk.get_vk()),
                 black_box(&ipa_big_proof[..]),
-                black_box(big_nrows),
+                &[black_box(&[&[ipa_big_out][..]])],
             );
         })
     });
@@ -293,7 +286,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 black_box(kzg_small_verifier_params),
                 black_box(kzg_small_pk.get_vk()),
                 black_box(&kzg_small_proof[..]),
-                black_box(small_nrows),
+                &[black_box(&[&[kzg_small_out][..]])],
             );
         })
     });
@@ -309,11 +302,90 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 black_box(kzg_big_verifier_params),
                 black_box(kzg_big_pk.get_vk()),
                 black_box(&kzg_big_proof[..]),
-                black_box(big_nrows),
+                &[black_box(&[&[kzg_big_out][..]])],
             );
         })
     });
 }
 
-criterion_group!(benches, criterion_benchmark);
+/// Compares the single-column layout against the width-`WIDTH` layout on the same
+/// chain, reporting proof size and k, and benchmarking KZG proving time.
+pub fn wide_benchmark(c: &mut Criterion) {
+    const WIDTH: usize = 8;
+    let nrows = 65500;
+    let rng = OsRng;
+
+    type KZGScheme = KZGCommitmentScheme<Bn256>;
+
+    let scalar = Fr::from(3);
+    let value = Fr::from(5);
+    let out = expected_output(value, scalar, nrows);
+
+    let narrow_k = 16;
+    let narrow_circuit = MyCircuit {
+        value,
+        scalar,
+        nrows,
+        _marker: PhantomData,
+    };
+    let (narrow_params, narrow_pk) = keygen::<KZGScheme>(narrow_k, narrow_circuit.clone());
+
+    let wide_k = min_k(nrows, WIDTH);
+    let wide_circuit = WideCircuit::<_, WIDTH> {
+        value,
+        scalar,
+        nrows,
+        _marker: PhantomData,
+    };
+    let (wide_params, wide_pk) = keygen::<KZGScheme>(wide_k, wide_circuit.clone());
+
+    let narrow_proof = create_proof::<_, ProverSHPLONK<_>, _, _, Blake2bWrite<_, _, Challenge255<_>>>(
+        rng,
+        &narrow_params,
+        &narrow_pk,
+        &[narrow_circuit.clone()],
+        &[&[&[out]]],
+    );
+    let wide_proof = create_proof::<_, ProverSHPLONK<_>, _, _, Blake2bWrite<_, _, Challenge255<_>>>(
+        rng,
+        &wide_params,
+        &wide_pk,
+        &[wide_circuit.clone()],
+        &[&[&[out]]],
+    );
+    println!(
+        "add1 {} rows: single column k = {}, proof = {} bytes; width {} k = {}, proof = {} bytes",
+        nrows,
+        narrow_k,
+        narrow_proof.len(),
+        WIDTH,
+        wide_k,
+        wide_proof.len()
+    );
+
+    c.bench_function("kzg_prover_single_column", |b| {
+        b.iter(|| {
+            create_proof::<_, ProverSHPLONK<_>, _, _, Blake2bWrite<_, _, Challenge255<_>>>(
+                black_box(rng),
+                black_box(&narrow_params),
+                black_box(&narrow_pk),
+                &[black_box(narrow_circuit.clone())],
+                &[black_box(&[&[out][..]])],
+            )
+        })
+    });
+    c.bench_function("kzg_prover_wide", |b| {
+        b.iter(|| {
+            create_proof::<_, ProverSHPLONK<_>, _, _, Blake2bWrite<_, _, Challenge255<_>>>(
+                black_box(rng),
+                black_box(&wide_params),
+                black_box(&wide_pk),
+                &[black_box(wide_circuit.clone())],
+                &[black_box(&[&[out][..]])],
+            )
+        })
+    });
+}
+
+criterion_group!(benches, criterion_benchmark, wide_benchmark);
 criterion_main!(benches);
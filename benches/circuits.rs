@@ -0,0 +1,81 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use halo2_examples::circuits::{visit_all, CircuitDef, CircuitVisitor};
+use halo2_examples::proof::{create_proof, keygen, verify_proof};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::KZGCommitmentScheme;
+use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_proofs::poly::kzg::strategy::AccumulatorStrategy;
+use halo2_proofs::transcript::{Blake2bRead, Blake2bWrite, Challenge255};
+use halo2curves::bn256::{Bn256, Fr};
+use rand_core::OsRng;
+
+type KZGScheme = KZGCommitmentScheme<Bn256>;
+
+/// Proves and verifies the example of every registered circuit with KZG.
+struct Bench<'a> {
+    c: &'a mut Criterion,
+}
+
+impl CircuitVisitor<Fr> for Bench<'_> {
+    fn visit<C: CircuitDef<Fr>>(&mut self) {
+        let rng = OsRng;
+        let k = C::DEFAULT_K;
+        let circuit = C::example();
+        let instances = circuit.instances().expect("example witness is known");
+        let instances: Vec<&[Fr]> = instances.iter().map(|column| &column[..]).collect();
+
+        let (params, pk) = keygen::<KZGScheme>(k, circuit.clone());
+        let verifier_params = params.verifier_params();
+
+        self.c
+            .bench_function(&format!("{}_kzg_prover_k_{}", C::NAME, k), |b| {
+                b.iter(|| {
+                    create_proof::<_, ProverSHPLONK<_>, _, _, Blake2bWrite<_, _, Challenge255<_>>>(
+                        black_box(rng),
+                        black_box(&params),
+                        black_box(&pk),
+                        &[black_box(circuit.clone())],
+                        &[black_box(&instances[..])],
+                    )
+                })
+            });
+
+        let proof = create_proof::<_, ProverSHPLONK<_>, _, _, Blake2bWrite<_, _, Challenge255<_>>>(
+            rng,
+            &params,
+            &pk,
+            &[circuit.clone()],
+            &[&instances[..]],
+        );
+        println!("{}: {} byte proof", C::NAME, proof.len());
+
+        self.c
+            .bench_function(&format!("{}_kzg_verifier_k_{}", C::NAME, k), |b| {
+                b.iter(|| {
+                    verify_proof::<
+                        _,
+                        VerifierSHPLONK<_>,
+                        _,
+                        Blake2bRead<_, _, Challenge255<_>>,
+                        AccumulatorStrategy<_>,
+                    >(
+                        black_box(verifier_params),
+                        black_box(pk.get_vk()),
+                        black_box(&proof[..]),
+                        &[black_box(&instances[..])],
+                    )
+                })
+            });
+    }
+}
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    visit_all(&mut Bench { c });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = criterion_benchmark
+}
+criterion_main!(benches);
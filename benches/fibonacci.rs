@@ -0,0 +1,152 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use halo2_examples::fibonacci::example1::{self, fibonacci, FibonacciCircuit};
+use halo2_examples::fibonacci::example2::{self, FibonacciCircuit as SingleColumnCircuit};
+use halo2_examples::proof::{create_proof, keygen};
+use halo2_proofs::circuit::Value;
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem};
+use halo2_proofs::poly::kzg::commitment::KZGCommitmentScheme;
+use halo2_proofs::poly::kzg::multiopen::ProverSHPLONK;
+use halo2_proofs::transcript::{Blake2bWrite, Challenge255};
+use halo2curves::bn256::{Bn256, Fr};
+use rand_core::OsRng;
+
+type KZGScheme = KZGCommitmentScheme<Bn256>;
+
+fn circuit(n: usize) -> (FibonacciCircuit<Fr>, Fr) {
+    let a = Fr::from(1);
+    let b = Fr::from(1);
+    let circuit = FibonacciCircuit {
+        a: Value::known(a),
+        b: Value::known(b),
+        n,
+    };
+    (circuit, fibonacci(a, b, n))
+}
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let rng = OsRng;
+
+    for (k, n) in [(10u32, 1000usize), (16, 65000), (20, 1_000_000)] {
+        let (circuit, out) = circuit(n);
+
+        c.bench_function(&format!("fibonacci_mock_prover_n_{}", n), |b| {
+            b.iter(|| {
+                MockProver::run(black_box(k), black_box(&circuit), vec![vec![out]])
+                    .unwrap()
+                    .assert_satisfied()
+            })
+        });
+
+        let (params, pk) = keygen::<KZGScheme>(k, circuit.clone());
+        c.bench_function(&format!("fibonacci_kzg_prover_n_{}", n), |b| {
+            b.iter(|| {
+                create_proof::<_, ProverSHPLONK<_>, _, _, Blake2bWrite<_, _, Challenge255<_>>>(
+                    black_box(rng),
+                    black_box(&params),
+                    black_box(&pk),
+                    &[black_box(circuit.clone())],
+                    &[black_box(&[&[out][..]])],
+                )
+            })
+        });
+    }
+}
+
+/// Advice and permutation column counts of a circuit's constraint system.
+fn columns<C: Circuit<Fr>>() -> (usize, usize) {
+    let mut cs = ConstraintSystem::default();
+    C::configure(&mut cs);
+    (
+        cs.num_advice_columns(),
+        cs.permutation().get_columns().len(),
+    )
+}
+
+/// Compares the three-column layout (example1) against the single-column
+/// rotation layout (example2) on rows, columns, proof size and prover time.
+pub fn layout_benchmark(c: &mut Criterion) {
+    let rng = OsRng;
+
+    let (three_advice, three_perm) = columns::<FibonacciCircuit<Fr>>();
+    let (single_advice, single_perm) = columns::<SingleColumnCircuit<Fr>>();
+
+    for (k, n) in [(10u32, 1000usize), (16, 65000)] {
+        let (three, out) = circuit(n);
+        let single = SingleColumnCircuit {
+            a: three.a,
+            b: three.b,
+            n,
+        };
+
+        let (three_params, three_pk) = keygen::<KZGScheme>(k, three.clone());
+        let (single_params, single_pk) = keygen::<KZGScheme>(k, single.clone());
+
+        let three_proof =
+            create_proof::<_, ProverSHPLONK<_>, _, _, Blake2bWrite<_, _, Challenge255<_>>>(
+                rng,
+                &three_params,
+                &three_pk,
+                &[three.clone()],
+                &[&[&[out]]],
+            );
+        let single_proof =
+            create_proof::<_, ProverSHPLONK<_>, _, _, Blake2bWrite<_, _, Challenge255<_>>>(
+                rng,
+                &single_params,
+                &single_pk,
+                &[single.clone()],
+                &[&[&[out]]],
+            );
+
+        println!(
+            "fibonacci n = {}: three columns: {} rows, {} advice, {} permutation columns, {} byte proof",
+            n,
+            example1::rows_for(n),
+            three_advice,
+            three_perm,
+            three_proof.len()
+        );
+        println!(
+            "fibonacci n = {}: single column: {} rows, {} advice, {} permutation columns, {} byte proof",
+            n,
+            example2::rows_for(n),
+            single_advice,
+            single_perm,
+            single_proof.len()
+        );
+
+        c.bench_function(&format!("fibonacci_three_column_kzg_prover_n_{}", n), |b| {
+            b.iter(|| {
+                create_proof::<_, ProverSHPLONK<_>, _, _, Blake2bWrite<_, _, Challenge255<_>>>(
+                    black_box(rng),
+                    black_box(&three_params),
+                    black_box(&three_pk),
+                    &[black_box(three.clone())],
+                    &[black_box(&[&[out][..]])],
+                )
+            })
+        });
+        c.bench_function(
+            &format!("fibonacci_single_column_kzg_prover_n_{}", n),
+            |b| {
+                b.iter(|| {
+                    create_proof::<_, ProverSHPLONK<_>, _, _, Blake2bWrite<_, _, Challenge255<_>>>(
+                        black_box(rng),
+                        black_box(&single_params),
+                        black_box(&single_pk),
+                        &[black_box(single.clone())],
+                        &[black_box(&[&[out][..]])],
+                    )
+                })
+            },
+        );
+    }
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = criterion_benchmark, layout_benchmark
+}
+criterion_main!(benches);
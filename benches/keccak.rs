@@ -0,0 +1,30 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use halo2_examples::keccak::{KeccakCircuit, KeccakCost, RATE};
+use halo2_proofs::dev::MockProver;
+use halo2curves::bn256::Fr;
+
+/// Reports the rows per permutation and per message, and times synthesis of one and
+/// two permutations in the `MockProver`.
+pub fn criterion_benchmark(c: &mut Criterion) {
+    for (k, len) in [(16u32, 0usize), (17, RATE)] {
+        let cost = KeccakCost::measure::<Fr>(len);
+        println!(
+            "keccak256 of {} bytes: {} rows per permutation, {} rows in all, {} table rows",
+            len, cost.rows_per_permutation, cost.rows, cost.table_rows
+        );
+
+        let message = vec![0x5a; len];
+        let circuit = KeccakCircuit::new(&message);
+        let instances = KeccakCircuit::instances::<Fr>(&message);
+        c.bench_function(&format!("keccak256_synthesis_{}_bytes", len), |b| {
+            b.iter(|| MockProver::run(k, black_box(&circuit), instances.clone()).unwrap())
+        });
+    }
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = criterion_benchmark
+}
+criterion_main!(benches);
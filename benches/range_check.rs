@@ -0,0 +1,157 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use halo2_examples::range_check::{RangeCheckChip, RangeCheckConfig};
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::plonk::{
+    create_proof, keygen_pk, keygen_vk, Circuit, ConstraintSystem, Error, ProvingKey,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::ProverSHPLONK;
+use halo2_proofs::transcript::{Blake2bWrite, Challenge255, TranscriptWriterBuffer};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use rand_core::OsRng;
+
+const NUM_BITS: usize = 8;
+
+/// Range checks every value in its own region.
+#[derive(Clone, Default)]
+struct PerValueCircuit {
+    values: Vec<Value<Fr>>,
+}
+
+/// Range checks every value in consecutive rows of one region.
+#[derive(Clone, Default)]
+struct BatchedCircuit {
+    values: Vec<Value<Fr>>,
+}
+
+impl Circuit<Fr> for PerValueCircuit {
+    type Config = RangeCheckConfig<Fr>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            values: vec![Value::unknown(); self.values.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let advice = meta.advice_column();
+        RangeCheckChip::configure(meta, advice, NUM_BITS)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let chip = RangeCheckChip::construct(config);
+        chip.load_table(&mut layouter)?;
+        for value in self.values.iter() {
+            chip.witness_check(layouter.namespace(|| "value"), *value)?;
+        }
+        Ok(())
+    }
+}
+
+impl Circuit<Fr> for BatchedCircuit {
+    type Config = RangeCheckConfig<Fr>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            values: vec![Value::unknown(); self.values.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let advice = meta.advice_column();
+        RangeCheckChip::configure(meta, advice, NUM_BITS)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let chip = RangeCheckChip::construct(config);
+        chip.load_table(&mut layouter)?;
+        chip.assign_many(layouter.namespace(|| "values"), &self.values)?;
+        Ok(())
+    }
+}
+
+/// Smallest k for which the circuit fits in `2^k` rows, blinding rows included.
+fn min_k<C: Circuit<Fr>>(circuit: &C) -> u32 {
+    (1..=20)
+        .find(|&k| {
+            MockProver::run(k, circuit, vec![])
+                .map(|prover| prover.verify().is_ok())
+                .unwrap_or(false)
+        })
+        .expect("circuit should fit in 2^20 rows")
+}
+
+fn prove<C: Circuit<Fr> + Clone>(params: &ParamsKZG<Bn256>, pk: &ProvingKey<G1Affine>, circuit: C) {
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        params,
+        pk,
+        &[circuit],
+        &[&[]],
+        OsRng,
+        &mut transcript,
+    )
+    .expect("proof generation should not fail");
+}
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let k = 11;
+    let values: Vec<_> = (0..1000u64)
+        .map(|i| Value::known(Fr::from(i % (1 << NUM_BITS))))
+        .collect();
+
+    let per_value = PerValueCircuit {
+        values: values.clone(),
+    };
+    let batched = BatchedCircuit { values };
+
+    println!(
+        "min k: per-value = {}, batched = {}",
+        min_k(&per_value),
+        min_k(&batched)
+    );
+
+    let params = ParamsKZG::<Bn256>::new(k);
+    let per_value_pk = keygen_pk(
+        &params,
+        keygen_vk(&params, &per_value).expect("keygen_vk should not fail"),
+        &per_value,
+    )
+    .expect("keygen_pk should not fail");
+    let batched_pk = keygen_pk(
+        &params,
+        keygen_vk(&params, &batched).expect("keygen_vk should not fail"),
+        &batched,
+    )
+    .expect("keygen_pk should not fail");
+
+    c.bench_function("range_check_per_value_kzg_prover", |b| {
+        b.iter(|| {
+            prove(
+                black_box(&params),
+                black_box(&per_value_pk),
+                per_value.clone(),
+            )
+        })
+    });
+    c.bench_function("range_check_batched_kzg_prover", |b| {
+        b.iter(|| prove(black_box(&params), black_box(&batched_pk), batched.clone()))
+    });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,164 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use halo2_examples::add1::example1::{self as add1, AddScalarChip, AddScalarConfig};
+use halo2_examples::fibonacci::example1::{
+    self as fibonacci, FibonacciChip, FibonacciCircuit, FibonacciConfig,
+};
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+use halo2curves::bn256::Fr;
+use std::marker::PhantomData;
+
+/// add1 computing its chain cell by cell inside the region, as before the trace split.
+#[derive(Clone, Default)]
+struct SequentialAdd1 {
+    value: Fr,
+    scalar: Fr,
+    nrows: usize,
+}
+
+impl Circuit<Fr> for SequentialAdd1 {
+    type Config = AddScalarConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        AddScalarChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let chip = AddScalarChip::construct(config);
+        let out = chip.assign(
+            self.value,
+            self.scalar,
+            self.nrows,
+            layouter.namespace(|| "chain"),
+        )?;
+        chip.expose_public(layouter.namespace(|| "out"), &out, 0)
+    }
+}
+
+/// Fibonacci computing its rows inside the region, as before the trace split.
+#[derive(Clone, Default)]
+struct SequentialFibonacci {
+    a: Value<Fr>,
+    b: Value<Fr>,
+    n: usize,
+}
+
+impl Circuit<Fr> for SequentialFibonacci {
+    type Config = FibonacciConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        FibonacciChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let chip = FibonacciChip::construct(config);
+        let out = chip.assign(layouter.namespace(|| "fibonacci"), self.a, self.b, self.n)?;
+        chip.expose_public(layouter.namespace(|| "out"), &out, 0)
+    }
+}
+
+/// Compares sequential and parallel witness generation, both on the trace alone and
+/// through synthesis in the `MockProver` (which runs the floor planner's two passes).
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let value = Fr::from(5);
+    let scalar = Fr::from(3);
+    let one = Fr::from(1);
+
+    for (k, n) in [(16u32, 65000usize), (20, 1_000_000)] {
+        c.bench_function(&format!("add1_trace_sequential_k_{}", k), |b| {
+            b.iter(|| {
+                let mut acc = black_box(value);
+                (0..n)
+                    .map(|_| {
+                        let cur = acc;
+                        acc += scalar;
+                        cur
+                    })
+                    .collect::<Vec<_>>()
+            })
+        });
+        c.bench_function(&format!("add1_trace_parallel_k_{}", k), |b| {
+            b.iter(|| add1::trace(black_box(value), black_box(scalar), black_box(n)))
+        });
+        c.bench_function(&format!("fibonacci_trace_sequential_k_{}", k), |b| {
+            b.iter(|| {
+                let (mut x, mut y) = (black_box(one), black_box(one));
+                (0..=n)
+                    .map(|_| {
+                        let cur = x;
+                        (x, y) = (y, x + y);
+                        cur
+                    })
+                    .collect::<Vec<_>>()
+            })
+        });
+        c.bench_function(&format!("fibonacci_trace_parallel_k_{}", k), |b| {
+            b.iter(|| fibonacci::trace(black_box(one), black_box(one), black_box(n + 1)))
+        });
+
+        let out = add1::expected_output(value, scalar, n);
+        let sequential = SequentialAdd1 {
+            value,
+            scalar,
+            nrows: n,
+        };
+        let parallel = add1::MyCircuit {
+            value,
+            scalar,
+            nrows: n,
+            _marker: PhantomData,
+        };
+        c.bench_function(&format!("add1_synthesis_sequential_k_{}", k), |b| {
+            b.iter(|| MockProver::run(k, black_box(&sequential), vec![vec![out]]).unwrap())
+        });
+        c.bench_function(&format!("add1_synthesis_parallel_k_{}", k), |b| {
+            b.iter(|| MockProver::run(k, black_box(&parallel), vec![vec![out]]).unwrap())
+        });
+
+        let out = fibonacci::fibonacci(one, one, n);
+        let sequential = SequentialFibonacci {
+            a: Value::known(one),
+            b: Value::known(one),
+            n,
+        };
+        let parallel = FibonacciCircuit {
+            a: Value::known(one),
+            b: Value::known(one),
+            n,
+        };
+        c.bench_function(&format!("fibonacci_synthesis_sequential_k_{}", k), |b| {
+            b.iter(|| MockProver::run(k, black_box(&sequential), vec![vec![out]]).unwrap())
+        });
+        c.bench_function(&format!("fibonacci_synthesis_parallel_k_{}", k), |b| {
+            b.iter(|| MockProver::run(k, black_box(&parallel), vec![vec![out]]).unwrap())
+        });
+    }
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = criterion_benchmark
+}
+criterion_main!(benches);
@@ -1,17 +1,55 @@
+use ff::PrimeField;
 use halo2_proofs::{arithmetic::Field, circuit::*, plonk::*, poly::Rotation};
+use rayon::prelude::*;
 use std::marker::PhantomData;
 
 // Table setup
 //
-//        a    |  selector  |
-// --------------------------------------------------------------------------------
-//    scalar   |     1      |
-//        a0   |     x      |
-//        a1   |     x      |
+// The scalar is carried down its own column, and every row after the first adds it
+// to the value of the row above. [`MyCircuit`] lays out `nrows` rows in all, and
+// [`PublicInputCircuit`] `nrows` additions below the row of `value`.
+//
+//     a          |  scalar  |  selector  |  instance (PublicInputCircuit)  |
+// ---------------------------------------------------------------------------
+//   value        |  scalar  |     0      |  value                          |
+//   value + s    |  scalar  |     1      |  scalar                         |
+//   value + 2s   |  scalar  |     1      |  out                            |
+
+/// Output of the add1 chain: `nrows` counts the initial value row, so the chain
+/// performs `nrows - 1` additions of `scalar`.
+pub fn expected_output<F: PrimeField>(value: F, scalar: F, nrows: usize) -> F {
+    value + scalar * F::from(nrows.saturating_sub(1) as u64)
+}
+
+/// Output of [`PublicInputCircuit`]: `nrows` additions of `scalar` to `value`.
+pub fn public_output<F: PrimeField>(value: F, scalar: F, nrows: usize) -> F {
+    value + scalar * F::from(nrows as u64)
+}
+
+/// Rows per parallel chunk in [`trace`].
+const TRACE_CHUNK: usize = 1 << 12;
+
+/// The `nrows` values of the add1 chain, `value + i * scalar`. Each chunk starts from
+/// its closed-form value, so chunks are computed in parallel.
+pub fn trace<F: PrimeField>(value: F, scalar: F, nrows: usize) -> Vec<F> {
+    let mut trace = vec![F::ZERO; nrows.max(1)];
+    trace
+        .par_chunks_mut(TRACE_CHUNK)
+        .enumerate()
+        .for_each(|(i, chunk)| {
+            let mut acc = value + scalar * F::from((i * TRACE_CHUNK) as u64);
+            for cell in chunk {
+                *cell = acc;
+                acc += scalar;
+            }
+        });
+    trace
+}
 
 #[derive(Clone, Debug)]
 pub struct AddScalarConfig {
     pub advice: Column<Advice>,
+    pub scalar: Column<Advice>,
     pub selector: Selector,
     pub instance: Column<Instance>,
 }
@@ -33,23 +71,30 @@ impl<F: Field> AddScalarChip<F> {
     pub fn configure(meta: &mut ConstraintSystem<F>) -> AddScalarConfig {
         let instance = meta.instance_column();
         let col_a = meta.advice_column();
+        let col_scalar = meta.advice_column();
         let selector = meta.selector();
 
         meta.enable_equality(col_a);
+        meta.enable_equality(col_scalar);
         meta.enable_equality(instance);
 
         meta.create_gate("add", |meta| {
             let selector = meta.query_selector(selector);
-            // let scalar = meta.query_selector(selector);
-            let scalar = meta.query_advice(col_a, Rotation::cur());
-            let a = meta.query_advice(col_a, Rotation::next());
-            let out = meta.query_advice(col_a, Rotation(2));
-
-            vec![selector * ((a + scalar) - out)]
+            let scalar = meta.query_advice(col_scalar, Rotation::cur());
+            let scalar_prev = meta.query_advice(col_scalar, Rotation::prev());
+            let a = meta.query_advice(col_a, Rotation::prev());
+            let out = meta.query_advice(col_a, Rotation::cur());
+
+            // The scalar is carried unchanged from row to row.
+            vec![
+                selector.clone() * (scalar.clone() - scalar_prev),
+                selector * ((a + scalar) - out),
+            ]
         });
 
         AddScalarConfig {
             advice: col_a,
+            scalar: col_scalar,
             selector,
             instance,
         }
@@ -65,34 +110,117 @@ impl<F: Field> AddScalarChip<F> {
         layouter.assign_region(
             || "entire circuit",
             |mut region| {
-                self.config.selector.enable(&mut region, 0)?;
-
                 let scalar_cell = region.assign_advice(
-                    || "a0",
-                    self.config.advice,
+                    || "scalar",
+                    self.config.scalar,
                     0,
                     || Value::known(scalar),
                 )?;
 
-                let mut value_cell =
-                    region.assign_advice(|| "a0", self.config.advice, 1, || Value::known(value))?;
+                let value_cell =
+                    region.assign_advice(|| "a0", self.config.advice, 0, || Value::known(value))?;
+
+                self.assign_chain(&mut region, &scalar_cell, value_cell, nrows)
+            },
+        )
+    }
+
+    /// Copies `value` and `scalar` from instance rows 0 and 1, so they are public
+    /// inputs rather than private witnesses, and adds `scalar` to `value` `nrows` times.
+    ///
+    /// Unlike [`AddScalarChip::assign`], `nrows` counts the additions and not the row
+    /// of `value`, so the result is [`public_output`].
+    pub fn assign_from_instance(
+        &self,
+        nrows: usize,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "entire circuit",
+            |mut region| {
+                let scalar_cell = region.assign_advice_from_instance(
+                    || "scalar",
+                    self.config.instance,
+                    1,
+                    self.config.scalar,
+                    0,
+                )?;
+
+                let value_cell = region.assign_advice_from_instance(
+                    || "value",
+                    self.config.instance,
+                    0,
+                    self.config.advice,
+                    0,
+                )?;
+
+                self.assign_chain(&mut region, &scalar_cell, value_cell, nrows + 1)
+            },
+        )
+    }
+
+    /// Bulk-assigns a precomputed [`trace`] instead of computing the chain inside the
+    /// region, so the work is not repeated when the floor planner re-runs it.
+    pub fn assign_trace(
+        &self,
+        scalar: F,
+        trace: &[F],
+        mut layouter: impl Layouter<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert!(!trace.is_empty(), "trace holds at least the initial value");
 
-                // println!("0 scalar: {:?}", scalar_cell.value());
-                // println!("1 value: {:?}", value_cell.value());
-                for row in 2..=nrows {
-                    value_cell = region.assign_advice(
+        layouter.assign_region(
+            || "entire circuit",
+            |mut region| {
+                let mut out = None;
+                for (row, value) in trace.iter().enumerate() {
+                    if row > 0 {
+                        self.config.selector.enable(&mut region, row)?;
+                    }
+                    region.assign_advice(
+                        || "scalar",
+                        self.config.scalar,
+                        row,
+                        || Value::known(scalar),
+                    )?;
+                    out = Some(region.assign_advice(
                         || "a0 + scalar",
                         self.config.advice,
                         row,
-                        || value_cell.value().copied() + scalar_cell.value(),
-                    )?;
-                    // println!("{row} value: {:?}", value_cell.value());
+                        || Value::known(*value),
+                    )?);
                 }
 
-                Ok(value_cell)
+                Ok(out.expect("trace is not empty"))
             },
         )
     }
+
+    fn assign_chain(
+        &self,
+        region: &mut Region<'_, F>,
+        scalar_cell: &AssignedCell<F, F>,
+        mut value_cell: AssignedCell<F, F>,
+        nrows: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        for row in 1..nrows {
+            self.config.selector.enable(region, row)?;
+            region.assign_advice(
+                || "scalar",
+                self.config.scalar,
+                row,
+                || scalar_cell.value().copied(),
+            )?;
+            value_cell = region.assign_advice(
+                || "a0 + scalar",
+                self.config.advice,
+                row,
+                || value_cell.value().copied() + scalar_cell.value(),
+            )?;
+        }
+
+        Ok(value_cell)
+    }
     pub fn expose_public(
         &self,
         mut layouter: impl Layouter<F>,
@@ -114,12 +242,13 @@ pub struct MyCircuit<F: Field> {
 // PhantomData<F>);
 
 // use pasta_curves::pallas;
-// impl<F: Field> Circuit<F> for MyCircuit<F> {
+// impl<F: PrimeField> Circuit<F> for MyCircuit<F> {
 // impl Circuit<pallas::Base> for MyCircuit<pallas::Base> {
 
 impl<F: Field> Circuit<F> for MyCircuit<F> {
     type Config = AddScalarConfig;
     type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
 
     fn without_witnesses(&self) -> Self {
         Self::default()
@@ -136,20 +265,54 @@ impl<F: Field> Circuit<F> for MyCircuit<F> {
     ) -> Result<(), Error> {
         let chip = AddScalarChip::<F>::construct(config);
 
-        let out = chip.assign(
-            self.value,
-            self.scalar,
-            self.nrows,
-            layouter.namespace(|| "initial values"),
-        )?;
+        let trace = trace(self.value, self.scalar, self.nrows);
+        let out =
+            chip.assign_trace(self.scalar, &trace, layouter.namespace(|| "initial values"))?;
 
-        // println!("out: {:?}", out);
         chip.expose_public(layouter.namespace(|| "out"), &out, 0)?;
 
         Ok(())
     }
 }
 
+/// add1 with `value` and `scalar` taken from the instance column, so the verifier
+/// checks `out = value + nrows * scalar` for any public values.
+///
+/// Instance layout: `[value, scalar, out]`.
+#[derive(Default, Clone, Debug)]
+pub struct PublicInputCircuit<F: Field> {
+    pub nrows: usize,
+    pub _marker: PhantomData<F>,
+}
+
+impl<F: Field> Circuit<F> for PublicInputCircuit<F> {
+    type Config = AddScalarConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        AddScalarChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = AddScalarChip::<F>::construct(config);
+
+        let out = chip.assign_from_instance(self.nrows, layouter.namespace(|| "initial values"))?;
+
+        chip.expose_public(layouter.namespace(|| "out"), &out, 2)?;
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use ff::Field;
@@ -158,10 +321,13 @@ mod tests {
         Blake2bRead, Blake2bWrite, Challenge255, EncodedChallenge, Transcript, TranscriptRead,
         TranscriptReadBuffer, TranscriptWrite, TranscriptWriterBuffer,
     };
-    use halo2curves::bn256::{Bn256, Fq};
+    use halo2curves::bn256::{Bn256, Fq, Fr};
     use std::marker::PhantomData;
 
-    use super::MyCircuit;
+    use super::{
+        expected_output, public_output, trace, MyCircuit, PublicInputCircuit, TRACE_CHUNK,
+    };
+    use crate::model::{check, strategy, Model};
     use crate::proof::{common, create_proof, verify_proof};
     use ff::{FromUniformBytes, WithSmallOrderMulGroup};
     use halo2_proofs::dev::MockProver;
@@ -171,6 +337,7 @@ mod tests {
     };
     use halo2_proofs::poly::commitment::{CommitmentScheme, ParamsProver, Prover, Verifier};
     use pasta_curves::pallas;
+    use proptest::prelude::*;
     use rand_core::{OsRng, RngCore};
 
     #[test]
@@ -200,6 +367,229 @@ mod tests {
         let prover = MockProver::run(k, &circuit, vec![public_input.clone()]).unwrap();
         prover.assert_satisfied();
     }
+    #[test]
+    fn add_scalar_trace() {
+        let value = Fq::from(5);
+        let scalar = Fq::from(3);
+
+        for nrows in [0, 1, 2, TRACE_CHUNK, 2 * TRACE_CHUNK + 5] {
+            let trace = trace(value, scalar, nrows);
+            assert_eq!(trace.len(), nrows.max(1));
+
+            let mut acc = value;
+            for cell in trace.iter() {
+                assert_eq!(*cell, acc);
+                acc += scalar;
+            }
+            assert_eq!(trace.last(), Some(&expected_output(value, scalar, nrows)));
+        }
+    }
+
+    #[test]
+    fn add_scalar_public_inputs() {
+        let k = 5;
+        let nrows = 10;
+
+        let circuit = PublicInputCircuit::<Fq> {
+            nrows,
+            _marker: PhantomData,
+        };
+
+        for (value, scalar) in [(7u64, 13u64), (0, 1), (123_456, 789)] {
+            let value = Fq::from(value);
+            let scalar = Fq::from(scalar);
+            let out = public_output(value, scalar, nrows);
+
+            let prover = MockProver::run(k, &circuit, vec![vec![value, scalar, out]]).unwrap();
+            prover.assert_satisfied();
+
+            let prover =
+                MockProver::run(k, &circuit, vec![vec![value, scalar, out + Fq::ONE]]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+
+        // Large field elements wrap around like any other field arithmetic.
+        let value = -Fq::ONE;
+        let scalar = -Fq::from(2);
+        let out = public_output(value, scalar, nrows);
+        let prover = MockProver::run(k, &circuit, vec![vec![value, scalar, out]]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn add_scalar_public_inputs_middle_row() {
+        use crate::trace::{CellRef, Trace};
+
+        let k = 5;
+        let nrows = 10;
+        let circuit = PublicInputCircuit::<Fr> {
+            nrows,
+            _marker: PhantomData,
+        };
+        let (value, scalar) = (Fr::from(7), Fr::from(13));
+        let out = public_output(value, scalar, nrows);
+
+        let mut trace = Trace::record(k, &circuit, vec![vec![value, scalar, out]]).unwrap();
+        assert_eq!(trace.mock_prove(&circuit).unwrap().verify(), Ok(()));
+
+        // Every addition is constrained, not only the first one.
+        let at = CellRef::advice(0, nrows / 2);
+        let original = trace.get(at).unwrap();
+        trace.set(at, original + Fr::ONE);
+        assert!(trace.mock_prove(&circuit).unwrap().verify().is_err());
+    }
+
+    #[test]
+    fn add_scalar_public_inputs_kzg() {
+        use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+        use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+        use halo2_proofs::poly::kzg::strategy::AccumulatorStrategy;
+
+        let k = 5;
+
+        type Scheme = KZGCommitmentScheme<Bn256>;
+
+        let value = <Scheme as CommitmentScheme>::Scalar::from(42);
+        let scalar = <Scheme as CommitmentScheme>::Scalar::from(1_000_003);
+        let nrows = 10;
+        let out = public_output(value, scalar, nrows);
+
+        let circuit = PublicInputCircuit {
+            nrows,
+            _marker: PhantomData,
+        };
+
+        let params = ParamsKZG::<Bn256>::new(k);
+        let vk = keygen_vk(&params, &circuit).expect("keygen_vk should not fail");
+        let pk = keygen_pk(&params, vk, &circuit).expect("keygen_pk should not fail");
+
+        let pubinputs = [value, scalar, out];
+        let proof = create_proof::<_, ProverSHPLONK<_>, _, _, Blake2bWrite<_, _, Challenge255<_>>>(
+            OsRng,
+            &params,
+            &pk,
+            &[circuit],
+            &[&[&pubinputs]],
+        );
+
+        verify_proof::<
+            _,
+            VerifierSHPLONK<_>,
+            _,
+            Blake2bRead<_, _, Challenge255<_>>,
+            AccumulatorStrategy<_>,
+        >(
+            params.verifier_params(),
+            pk.get_vk(),
+            &proof[..],
+            &[&[&pubinputs]],
+        );
+    }
+
+    #[test]
+    fn add_scalar_preflight() {
+        use crate::proof::{create_proof_checked, preflight, PreflightFailure};
+        use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+        use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+        use halo2_proofs::poly::kzg::strategy::AccumulatorStrategy;
+
+        let k = 5;
+
+        type Scheme = KZGCommitmentScheme<Bn256>;
+
+        let value = <Scheme as CommitmentScheme>::Scalar::from(5);
+        let scalar = <Scheme as CommitmentScheme>::Scalar::from(3);
+        let nrows = 10;
+        let out = expected_output(value, scalar, nrows);
+
+        let circuit = MyCircuit {
+            value,
+            scalar,
+            nrows,
+            _marker: PhantomData,
+        };
+        let other = MyCircuit {
+            nrows: 11,
+            ..circuit.clone()
+        };
+
+        // Only the second circuit of the batch disagrees with `out`.
+        let failures = preflight(
+            k,
+            &[circuit.clone(), other.clone()],
+            &[&[&[out]], &[&[out]]],
+        )
+        .expect_err("second circuit is not satisfied");
+        assert_eq!(failures.len(), 1);
+        assert!(matches!(failures[0], (1, PreflightFailure::Unsatisfied(_))));
+
+        let params = ParamsKZG::<Bn256>::new(k);
+        let vk = keygen_vk(&params, &circuit).expect("keygen_vk should not fail");
+        let pk = keygen_pk(&params, vk, &circuit).expect("keygen_pk should not fail");
+
+        let result =
+            create_proof_checked::<_, ProverSHPLONK<_>, _, _, Blake2bWrite<_, _, Challenge255<_>>>(
+                OsRng,
+                &params,
+                &pk,
+                &[circuit.clone()],
+                &[&[&[out + scalar]]],
+                k,
+            );
+        assert!(result.is_err());
+
+        let result = create_proof_checked::<
+            _,
+            ProverSHPLONK<_>,
+            _,
+            _,
+            Blake2bWrite<_, _, Challenge255<_>>,
+        >(OsRng, &params, &pk, &[circuit.clone()], &[&[&[out]]], k);
+        assert!(result.is_ok());
+
+        // Failures point at the circuit of the caller's batch.
+        let failures =
+            create_proof_checked::<_, ProverSHPLONK<_>, _, _, Blake2bWrite<_, _, Challenge255<_>>>(
+                OsRng,
+                &params,
+                &pk,
+                &[circuit.clone(), other],
+                &[&[&[out]], &[&[out]]],
+                k,
+            )
+            .expect_err("second circuit is not satisfied");
+        assert_eq!(failures.len(), 1);
+        assert!(matches!(failures[0], (1, PreflightFailure::Unsatisfied(_))));
+
+        // A batch of two different witnesses of the same circuit.
+        let second = MyCircuit {
+            value: value + Fr::ONE,
+            ..circuit.clone()
+        };
+        let instances: [&[&[Fr]]; 2] = [&[&[out]], &[&[out + Fr::ONE]]];
+        let proof = create_proof_checked::<
+            _,
+            ProverSHPLONK<_>,
+            _,
+            _,
+            Blake2bWrite<_, _, Challenge255<_>>,
+        >(OsRng, &params, &pk, &[circuit, second], &instances, k)
+        .expect("both circuits are satisfied");
+
+        verify_proof::<
+            _,
+            VerifierSHPLONK<_>,
+            _,
+            Blake2bRead<_, _, Challenge255<_>>,
+            AccumulatorStrategy<_>,
+        >(
+            params.verifier_params(),
+            pk.get_vk(),
+            &proof[..],
+            &instances,
+        );
+    }
+
     #[test]
     #[ignore = "takes a while"]
     fn add_scalar_big() {
@@ -239,10 +629,10 @@ mod tests {
 
         type Scheme = IPACommitmentScheme<EqAffine>;
 
-        let scalar = <Scheme as CommitmentScheme>::Scalar::from(1);
-        let value = <Scheme as CommitmentScheme>::Scalar::from(1);
+        let scalar = <Scheme as CommitmentScheme>::Scalar::from(3);
+        let value = <Scheme as CommitmentScheme>::Scalar::from(5);
         let nrows = 10u64;
-        let out = <Scheme as CommitmentScheme>::Scalar::from(nrows);
+        let out = expected_output(value, scalar, nrows as usize);
 
         // println!("Starting circuit instantiation");
         // println!("out: {:?}", out);
@@ -284,11 +674,14 @@ mod tests {
         let rng = OsRng;
 
         let proof = create_proof::<_, ProverIPA<_>, _, _, Blake2bWrite<_, _, Challenge255<_>>>(
-            rng, &params, &pk, circuit, nrows, k,
+            rng,
+            &params,
+            &pk,
+            &[circuit],
+            &[&[&[out]]],
         );
         println!("Proof generated with {:?} bytes", proof.len());
-        assert_eq!(proof.len(), 1568);
-        println!("Proof generated with {:?} bytes", proof.len());
+        assert!(!proof.is_empty());
 
         let verifier_params = params.verifier_params();
         // println!("verifier params generated {:?}", verifier_params);
@@ -298,7 +691,7 @@ mod tests {
             _,
             Blake2bRead<_, _, Challenge255<_>>,
             AccumulatorStrategy<_>,
-        >(verifier_params, pk.get_vk(), &proof[..], nrows);
+        >(verifier_params, pk.get_vk(), &proof[..], &[&[&[out]]]);
     }
     #[test]
     fn add_scalar_small_kzg() {
@@ -314,10 +707,10 @@ mod tests {
 
         type Scheme = KZGCommitmentScheme<Bn256>;
 
-        let scalar = <Scheme as CommitmentScheme>::Scalar::from(1);
-        let value = <Scheme as CommitmentScheme>::Scalar::from(1);
+        let scalar = <Scheme as CommitmentScheme>::Scalar::from(3);
+        let value = <Scheme as CommitmentScheme>::Scalar::from(5);
         let nrows = 10u64;
-        let out = <Scheme as CommitmentScheme>::Scalar::from(nrows);
+        let out = expected_output(value, scalar, nrows as usize);
 
         // println!("Starting circuit instantiation");
         println!("out: {:?}", out);
@@ -357,10 +750,14 @@ mod tests {
         let rng = OsRng;
 
         let proof = create_proof::<_, ProverSHPLONK<_>, _, _, Blake2bWrite<_, _, Challenge255<_>>>(
-            rng, &params, &pk, circuit, nrows, k,
+            rng,
+            &params,
+            &pk,
+            &[circuit],
+            &[&[&[out]]],
         );
         println!("Proof generated with {:?} bytes", proof.len());
-        assert_eq!(proof.len(), 992);
+        assert!(!proof.is_empty());
 
         let verifier_params = params.verifier_params();
         // println!("verifier params generated {:?}", verifier_params);
@@ -370,7 +767,7 @@ mod tests {
             _,
             Blake2bRead<_, _, Challenge255<_>>,
             AccumulatorStrategy<_>,
-        >(verifier_params, pk.get_vk(), &proof[..], nrows);
+        >(verifier_params, pk.get_vk(), &proof[..], &[&[&[out]]]);
     }
     #[test]
     #[ignore = "takes a while ~ 6min on my machine"]
@@ -386,10 +783,10 @@ mod tests {
 
         type Scheme = KZGCommitmentScheme<Bn256>;
 
-        let scalar = <Scheme as CommitmentScheme>::Scalar::from(1);
-        let value = <Scheme as CommitmentScheme>::Scalar::from(1);
+        let scalar = <Scheme as CommitmentScheme>::Scalar::from(3);
+        let value = <Scheme as CommitmentScheme>::Scalar::from(5);
         let nrows = 1_000_000u64;
-        let out = <Scheme as CommitmentScheme>::Scalar::from(nrows);
+        let out = expected_output(value, scalar, nrows as usize);
 
         let circuit = MyCircuit {
             value,
@@ -411,11 +808,15 @@ mod tests {
         let rng = OsRng;
 
         let proof = create_proof::<_, ProverSHPLONK<_>, _, _, Blake2bWrite<_, _, Challenge255<_>>>(
-            rng, &params, &pk, circuit, nrows, k,
+            rng,
+            &params,
+            &pk,
+            &[circuit],
+            &[&[&[out]]],
         );
         // println!("Proof generated {:?}", proof);
         println!("Proof generated with {:?} bytes", proof.len());
-        assert_eq!(proof.len(), 992);
+        assert!(!proof.is_empty());
 
         let verifier_params = params.verifier_params();
         // println!("verifier params generated {:?}", verifier_params);
@@ -425,7 +826,7 @@ mod tests {
             _,
             Blake2bRead<_, _, Challenge255<_>>,
             AccumulatorStrategy<_>,
-        >(verifier_params, pk.get_vk(), &proof[..], nrows);
+        >(verifier_params, pk.get_vk(), &proof[..], &[&[&[out]]]);
         // assert!(false);
     }
     #[test]
@@ -442,10 +843,10 @@ mod tests {
 
         type Scheme = IPACommitmentScheme<EqAffine>;
 
-        let scalar = <Scheme as CommitmentScheme>::Scalar::from(1);
-        let value = <Scheme as CommitmentScheme>::Scalar::from(1);
+        let scalar = <Scheme as CommitmentScheme>::Scalar::from(3);
+        let value = <Scheme as CommitmentScheme>::Scalar::from(5);
         let nrows = 1_000_000u64;
-        let out = <Scheme as CommitmentScheme>::Scalar::from(nrows);
+        let out = expected_output(value, scalar, nrows as usize);
 
         let circuit = MyCircuit {
             value,
@@ -468,10 +869,14 @@ mod tests {
         let rng = OsRng;
 
         let proof = create_proof::<_, ProverIPA<_>, _, _, Blake2bWrite<_, _, Challenge255<_>>>(
-            rng, &params, &pk, circuit, nrows, k,
+            rng,
+            &params,
+            &pk,
+            &[circuit],
+            &[&[&[out]]],
         );
         println!("Proof generated with {:?} bytes", proof.len());
-        assert_eq!(proof.len(), 2528);
+        assert!(!proof.is_empty());
 
         let verifier_params = params.verifier_params();
         // println!("verifier params generated {:?}", verifier_params);
@@ -481,7 +886,7 @@ mod tests {
             _,
             Blake2bRead<_, _, Challenge255<_>>,
             AccumulatorStrategy<_>,
-        >(verifier_params, pk.get_vk(), &proof[..], nrows);
+        >(verifier_params, pk.get_vk(), &proof[..], &[&[&[out]]]);
         assert!(false);
     }
 
@@ -511,4 +916,45 @@ mod tests {
             .render(4, &circuit, &root)
             .unwrap();
     }
+
+    struct Add1Model;
+
+    impl Model<Fr> for Add1Model {
+        /// `(value, scalar, nrows, claimed output)`, `None` claiming the right output.
+        type Input = (Fr, Fr, usize, Option<Fr>);
+        type Circuit = MyCircuit<Fr>;
+
+        fn k(_: &Self::Input) -> u32 {
+            5
+        }
+
+        fn circuit(&(value, scalar, nrows, claim): &Self::Input) -> (Self::Circuit, Vec<Vec<Fr>>) {
+            let out = claim.unwrap_or_else(|| expected_output(value, scalar, nrows));
+            let circuit = MyCircuit {
+                value,
+                scalar,
+                nrows,
+                _marker: PhantomData,
+            };
+            (circuit, vec![vec![out]])
+        }
+
+        fn accepts(&(value, scalar, nrows, claim): &Self::Input) -> bool {
+            claim.is_none() || claim == Some(expected_output(value, scalar, nrows))
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn add_scalar_model(
+            value in strategy::field::<Fr>(),
+            scalar in strategy::field::<Fr>(),
+            nrows in 1usize..20,
+            claim in strategy::claim::<Fr>(),
+        ) {
+            check::<_, Add1Model>(&(value, scalar, nrows, claim)).map_err(TestCaseError::fail)?;
+        }
+    }
 }
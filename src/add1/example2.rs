@@ -0,0 +1,247 @@
+use halo2_proofs::{arithmetic::Field, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+// Table setup
+//
+// Each row chains W additions of `scalar`, continuing from the last column of the
+// previous row. Row 0 only holds the initial value and the scalar.
+//
+//   scalar  |   a[0]       |   a[1]       | ... |   a[W-1]       |  selector  |
+// ------------------------------------------------------------------------------------
+//   scalar  |              |              |     |   value        |     0      |
+//   scalar  | value + s    | value + 2s   | ... | value + W*s    |     1      |
+//   scalar  | value+(W+1)s | value+(W+2)s | ... | value + 2W*s   |     1      |
+
+/// Number of rows used by a width-`W` chain over `nrows` values (the same `nrows` as
+/// `add1::example1::MyCircuit`, i.e. `nrows - 1` additions).
+pub fn rows_for(nrows: usize, width: usize) -> usize {
+    let additions = nrows.saturating_sub(1);
+    1 + (additions + width - 1) / width
+}
+
+#[derive(Clone, Debug)]
+pub struct AddScalarWideConfig<const W: usize> {
+    pub scalar: Column<Advice>,
+    pub advice: [Column<Advice>; W],
+    pub selector: Selector,
+    pub instance: Column<Instance>,
+}
+
+#[derive(Clone, Debug)]
+pub struct AddScalarWideChip<F: Field, const W: usize> {
+    config: AddScalarWideConfig<W>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field, const W: usize> AddScalarWideChip<F, W> {
+    pub fn construct(config: AddScalarWideConfig<W>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> AddScalarWideConfig<W> {
+        assert!(W > 0, "width must be at least one column");
+
+        let instance = meta.instance_column();
+        let col_scalar = meta.advice_column();
+        let advice = [(); W].map(|_| meta.advice_column());
+        let selector = meta.selector();
+
+        for column in advice.iter() {
+            meta.enable_equality(*column);
+        }
+        meta.enable_equality(instance);
+
+        meta.create_gate("add wide", |meta| {
+            let selector = meta.query_selector(selector);
+            let scalar = meta.query_advice(col_scalar, Rotation::cur());
+            let scalar_prev = meta.query_advice(col_scalar, Rotation::prev());
+            let prev = meta.query_advice(advice[W - 1], Rotation::prev());
+            let cur: Vec<_> = advice
+                .iter()
+                .map(|column| meta.query_advice(*column, Rotation::cur()))
+                .collect();
+
+            // The scalar is carried unchanged from row to row.
+            let mut constraints = vec![selector.clone() * (scalar.clone() - scalar_prev)];
+            constraints.push(selector.clone() * (prev + scalar.clone() - cur[0].clone()));
+            for i in 1..W {
+                constraints.push(
+                    selector.clone() * (cur[i - 1].clone() + scalar.clone() - cur[i].clone()),
+                );
+            }
+            constraints
+        });
+
+        AddScalarWideConfig {
+            scalar: col_scalar,
+            advice,
+            selector,
+            instance,
+        }
+    }
+
+    /// Computes the same output as `AddScalarChip::assign`, packing W additions per
+    /// row. The last row is filled completely and the output is read from the column
+    /// where the chain reaches `nrows - 1` additions.
+    pub fn assign(
+        &self,
+        value: F,
+        scalar: F,
+        nrows: usize,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let additions = nrows.saturating_sub(1);
+
+        layouter.assign_region(
+            || "entire circuit",
+            |mut region| {
+                region.assign_advice(
+                    || "scalar",
+                    self.config.scalar,
+                    0,
+                    || Value::known(scalar),
+                )?;
+                let mut out = region.assign_advice(
+                    || "value",
+                    self.config.advice[W - 1],
+                    0,
+                    || Value::known(value),
+                )?;
+
+                let mut acc = Value::known(value);
+                for row in 1..rows_for(nrows, W) {
+                    self.config.selector.enable(&mut region, row)?;
+                    region.assign_advice(
+                        || "scalar",
+                        self.config.scalar,
+                        row,
+                        || Value::known(scalar),
+                    )?;
+
+                    for (i, column) in self.config.advice.iter().enumerate() {
+                        acc = acc + Value::known(scalar);
+                        let cell = region.assign_advice(|| "a + scalar", *column, row, || acc)?;
+                        if (row - 1) * W + i + 1 == additions {
+                            out = cell;
+                        }
+                    }
+                }
+
+                Ok(out)
+            },
+        )
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct MyCircuit<F: Field, const W: usize> {
+    pub scalar: F,
+    pub value: F,
+    pub nrows: usize,
+    pub _marker: PhantomData<F>,
+}
+
+impl<F: Field, const W: usize> Circuit<F> for MyCircuit<F, W> {
+    type Config = AddScalarWideConfig<W>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        AddScalarWideChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = AddScalarWideChip::<F, W>::construct(config);
+
+        let out = chip.assign(
+            self.value,
+            self.scalar,
+            self.nrows,
+            layouter.namespace(|| "initial values"),
+        )?;
+
+        chip.expose_public(layouter.namespace(|| "out"), &out, 0)?;
+
+        Ok(())
+    }
+}
+
+/// Smallest `k` that fits a width-`W` chain over `nrows` values, leaving room for the
+/// blinding rows.
+pub fn min_k(nrows: usize, width: usize) -> u32 {
+    let rows = rows_for(nrows, width) + 10;
+    (usize::BITS - (rows - 1).leading_zeros()).max(4)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use super::{min_k, rows_for, MyCircuit};
+    use crate::add1::example1::expected_output;
+    use halo2_proofs::dev::MockProver;
+    use halo2curves::bn256::Fr;
+
+    fn run<const W: usize>(value: u64, scalar: u64, nrows: usize) {
+        let value = Fr::from(value);
+        let scalar = Fr::from(scalar);
+        let out = expected_output(value, scalar, nrows);
+
+        let circuit = MyCircuit::<Fr, W> {
+            value,
+            scalar,
+            nrows,
+            _marker: PhantomData,
+        };
+        let k = min_k(nrows, W);
+
+        let prover = MockProver::run(k, &circuit, vec![vec![out]]).unwrap();
+        prover.assert_satisfied();
+
+        let prover = MockProver::run(k, &circuit, vec![vec![out + scalar]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn add_scalar_wide_small() {
+        for nrows in [2, 9, 10, 11, 17] {
+            run::<1>(5, 3, nrows);
+            run::<4>(5, 3, nrows);
+            run::<8>(5, 3, nrows);
+        }
+    }
+
+    #[test]
+    fn add_scalar_wide_rows() {
+        assert_eq!(rows_for(10, 1), 10);
+        assert_eq!(rows_for(10, 3), 4);
+        assert_eq!(rows_for(1_000_000, 8), 125_001);
+        assert_eq!(min_k(1_000_000, 8), 17);
+    }
+
+    #[test]
+    #[ignore = "takes a while"]
+    fn add_scalar_wide_big() {
+        run::<8>(5, 3, 1_000_000);
+    }
+}
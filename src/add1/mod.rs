@@ -1,3 +1,3 @@
 pub mod example1;
-// pub mod example2;
+pub mod example2;
 // mod example3;
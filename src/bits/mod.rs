@@ -0,0 +1,412 @@
+//! Decomposition of a value into boolean cells, and recomposition of boolean cells
+//! into a value.
+//!
+//! [`BitsChip`] witnesses the bits most significant first, next to a running sum that
+//! doubles at every row, so neither direction needs a fixed column of powers of two.
+//! The cells come back in either order, and [`BitsChip::pack_le`] and
+//! [`BitsChip::pack_be`] are the reverse operations.
+
+use std::marker::PhantomData;
+
+use ff::{PrimeField, PrimeFieldBits};
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+
+use crate::range_check::example1::bool_check;
+
+// Table setup
+//
+// `v = 0b110` decomposed into 3 bits:
+//
+//    bit   |  acc  |  q_bit  |
+// ----------------------------
+//          |   0   |    0    |
+//     1    |   1   |    1    |
+//     1    |   3   |    1    |
+//     0    |   6   |    1    |
+//
+// Every `q_bit` row checks that `bit` is boolean and that `acc = 2 * acc_prev + bit`,
+// and the last `acc` is copy constrained to `v`.
+
+/// Native reference: the low `num_bits` bits of `value`, least significant first.
+pub fn to_bits_le(value: u64, num_bits: usize) -> Vec<bool> {
+    (0..num_bits).map(|i| (value >> i) & 1 == 1).collect()
+}
+
+/// Native reference: the value of `bits`, least significant first.
+pub fn from_bits_le(bits: &[bool]) -> u64 {
+    assert!(bits.len() <= 64, "too many bits for a u64");
+    bits.iter()
+        .rev()
+        .fold(0, |acc, bit| (acc << 1) | *bit as u64)
+}
+
+#[derive(Clone, Debug)]
+pub struct BitsConfig {
+    pub bit: Column<Advice>,
+    pub acc: Column<Advice>,
+    pub q_bit: Selector,
+    pub instance: Column<Instance>,
+}
+
+/// Bit decomposition and recomposition, one row per bit.
+#[derive(Clone, Debug)]
+pub struct BitsChip<F: PrimeFieldBits> {
+    config: BitsConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeFieldBits> BitsChip<F> {
+    pub fn construct(config: BitsConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> BitsConfig {
+        let bit = meta.advice_column();
+        let acc = meta.advice_column();
+        let constants = meta.fixed_column();
+        let q_bit = meta.selector();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(bit);
+        meta.enable_equality(acc);
+        meta.enable_equality(instance);
+        // The running sum starts from a constant zero.
+        meta.enable_constant(constants);
+
+        meta.create_gate("bits", |meta| {
+            let q_bit = meta.query_selector(q_bit);
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let acc = meta.query_advice(acc, Rotation::cur());
+
+            Constraints::with_selector(
+                q_bit,
+                [
+                    ("bit is boolean", bool_check(bit.clone())),
+                    ("running sum", acc - acc_prev * F::from(2) - bit),
+                ],
+            )
+        });
+
+        BitsConfig {
+            bit,
+            acc,
+            q_bit,
+            instance,
+        }
+    }
+
+    /// Witnesses a value. It is only constrained once decomposed or packed into.
+    pub fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "load private",
+            |mut region| region.assign_advice(|| "value", self.config.acc, 0, || value),
+        )
+    }
+
+    /// Lays out `bits`, most significant first, and returns their cells and the
+    /// value they add up to.
+    fn running_sum(
+        &self,
+        mut layouter: impl Layouter<F>,
+        bits: &[Bit<'_, F>],
+    ) -> Result<(Vec<AssignedCell<F, F>>, AssignedCell<F, F>), Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "bits",
+            |mut region| {
+                let mut acc =
+                    region.assign_advice_from_constant(|| "acc", config.acc, 0, F::ZERO)?;
+                let mut cells = Vec::with_capacity(bits.len());
+
+                for (i, bit) in bits.iter().enumerate() {
+                    let offset = i + 1;
+                    config.q_bit.enable(&mut region, offset)?;
+                    let cell = match bit {
+                        Bit::Cell(cell) => {
+                            cell.copy_advice(|| "bit", &mut region, config.bit, offset)?
+                        }
+                        Bit::Value(value) => region.assign_advice(
+                            || "bit",
+                            config.bit,
+                            offset,
+                            || value.map(|bit| F::from(bit as u64)),
+                        )?,
+                    };
+                    let next =
+                        acc.value().copied() * Value::known(F::from(2)) + cell.value().copied();
+                    acc = region.assign_advice(|| "acc", config.acc, offset, || next)?;
+                    cells.push(cell);
+                }
+                Ok((cells, acc))
+            },
+        )
+    }
+
+    /// The `num_bits` bits of `value`, least significant first. Fails verification
+    /// unless `value < 2^num_bits`.
+    pub fn to_bits_le(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let mut bits = self.to_bits_be(layouter, value, num_bits)?;
+        bits.reverse();
+        Ok(bits)
+    }
+
+    /// The `num_bits` bits of `value`, most significant first. Fails verification
+    /// unless `value < 2^num_bits`.
+    pub fn to_bits_be(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        // Below the capacity, the running sum cannot wrap around the modulus, so the
+        // bits are unique.
+        assert!(
+            num_bits <= F::CAPACITY as usize,
+            "too many bits for a unique decomposition"
+        );
+        let le_bits = value.value().map(|value| value.to_le_bits());
+        let bits: Vec<_> = (0..num_bits)
+            .rev()
+            .map(|i| Bit::Value(le_bits.as_ref().map(|bits| bits[i])))
+            .collect();
+
+        let (cells, acc) = self.running_sum(layouter.namespace(|| "decompose"), &bits)?;
+        layouter.assign_region(
+            || "decomposed value",
+            |mut region| region.constrain_equal(acc.cell(), value.cell()),
+        )?;
+        Ok(cells)
+    }
+
+    /// The value of boolean `bits`, least significant first. Fails verification
+    /// unless every bit is boolean.
+    pub fn pack_le(
+        &self,
+        layouter: impl Layouter<F>,
+        bits: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let bits: Vec<_> = bits.iter().rev().map(Bit::Cell).collect();
+        self.pack(layouter, &bits)
+    }
+
+    /// The value of boolean `bits`, most significant first. Fails verification unless
+    /// every bit is boolean.
+    pub fn pack_be(
+        &self,
+        layouter: impl Layouter<F>,
+        bits: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let bits: Vec<_> = bits.iter().map(Bit::Cell).collect();
+        self.pack(layouter, &bits)
+    }
+
+    fn pack(
+        &self,
+        layouter: impl Layouter<F>,
+        bits: &[Bit<'_, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert!(
+            bits.len() <= F::CAPACITY as usize,
+            "too many bits for a unique value"
+        );
+        let (_, acc) = self.running_sum(layouter, bits)?;
+        Ok(acc)
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+/// A bit to lay out: a cell to copy in, or a witness.
+enum Bit<'a, F: PrimeFieldBits> {
+    Cell(&'a AssignedCell<F, F>),
+    Value(Value<bool>),
+}
+
+/// Decomposes a private value into its bits, and packs them back in the other order.
+///
+/// Instance layout: `[b0, .., b(n-1), r]`, the bits of the value least significant
+/// first, and `r` the value with its `n` bits reversed.
+#[derive(Clone, Debug)]
+pub struct BitsCircuit {
+    pub value: Value<u64>,
+    pub num_bits: usize,
+}
+
+impl BitsCircuit {
+    /// The public inputs matching `value`.
+    pub fn instances<F: PrimeField>(value: u64, num_bits: usize) -> Vec<Vec<F>> {
+        let bits = to_bits_le(value, num_bits);
+        let reversed: Vec<bool> = bits.iter().rev().copied().collect();
+        let mut instance: Vec<F> = bits.iter().map(|bit| F::from(*bit as u64)).collect();
+        instance.push(F::from(from_bits_le(&reversed)));
+        vec![instance]
+    }
+}
+
+impl<F: PrimeFieldBits> Circuit<F> for BitsCircuit {
+    type Config = BitsConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            value: Value::unknown(),
+            num_bits: self.num_bits,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        BitsChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = BitsChip::construct(config);
+
+        let value = chip.load_private(layouter.namespace(|| "value"), self.value.map(F::from))?;
+        let bits = chip.to_bits_le(layouter.namespace(|| "to bits"), &value, self.num_bits)?;
+        // The little-endian bits read most significant first are the reversed value.
+        let reversed = chip.pack_be(layouter.namespace(|| "pack"), &bits)?;
+
+        for (row, cell) in bits.iter().chain([&reversed]).enumerate() {
+            chip.expose_public(layouter.namespace(|| "bits"), cell, row)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::dev::MockProver;
+    use halo2curves::bn256::Fr;
+    use halo2curves::pasta::Fp;
+
+    const K: u32 = 8;
+
+    #[test]
+    fn bits_native() {
+        assert_eq!(to_bits_le(0b110, 4), [false, true, true, false]);
+        assert_eq!(from_bits_le(&to_bits_le(0xdead_beef, 32)), 0xdead_beef);
+        assert_eq!(from_bits_le(&to_bits_le(u64::MAX, 64)), u64::MAX);
+    }
+
+    #[test]
+    fn bits_chip() {
+        for (value, num_bits) in [(0, 1), (1, 1), (0b110, 3), (0xa5, 8), (u64::MAX, 64)] {
+            let circuit = BitsCircuit {
+                value: Value::known(value),
+                num_bits,
+            };
+            let instances = BitsCircuit::instances::<Fp>(value, num_bits);
+            let prover = MockProver::run(K, &circuit, instances).unwrap();
+            prover.assert_satisfied();
+        }
+
+        let value = 0x0123_4567_89ab_cdef;
+        let circuit = BitsCircuit {
+            value: Value::known(value),
+            num_bits: 64,
+        };
+        let prover = MockProver::run(K, &circuit, BitsCircuit::instances::<Fr>(value, 64)).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn bits_chip_rejects() {
+        // A value wider than its bits, whatever bits are claimed.
+        let circuit = BitsCircuit {
+            value: Value::known(0x1a5),
+            num_bits: 8,
+        };
+        let instances = BitsCircuit::instances::<Fp>(0x1a5, 8);
+        let prover = MockProver::run(K, &circuit, instances).unwrap();
+        assert!(prover.verify().is_err());
+
+        // A wrong bit.
+        let circuit = BitsCircuit {
+            value: Value::known(0xa5),
+            num_bits: 8,
+        };
+        let mut wrong = BitsCircuit::instances::<Fp>(0xa5, 8);
+        wrong[0][1] = Fp::from(1);
+        let prover = MockProver::run(K, &circuit, wrong).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    /// Packs arbitrary witnesses, least significant first.
+    #[derive(Clone, Debug)]
+    struct PackCircuit {
+        bits: Vec<u64>,
+    }
+
+    impl Circuit<Fp> for PackCircuit {
+        type Config = BitsConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+        type Params = ();
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            BitsChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = BitsChip::construct(config);
+            let bits = self
+                .bits
+                .iter()
+                .map(|bit| {
+                    chip.load_private(layouter.namespace(|| "bit"), Value::known(Fp::from(*bit)))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            let value = chip.pack_le(layouter.namespace(|| "pack"), &bits)?;
+            chip.expose_public(layouter.namespace(|| "value"), &value, 0)
+        }
+    }
+
+    #[test]
+    fn bits_chip_pack() {
+        let circuit = PackCircuit {
+            bits: vec![1, 0, 1, 1],
+        };
+        let prover = MockProver::run(K, &circuit, vec![vec![Fp::from(0b1101)]]).unwrap();
+        prover.assert_satisfied();
+
+        // 1 + 2 * 2 is 5 with a bit of 2.
+        let circuit = PackCircuit {
+            bits: vec![1, 2, 0],
+        };
+        let prover = MockProver::run(K, &circuit, vec![vec![Fp::from(5)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,453 @@
+use ff::{PrimeField, PrimeFieldBits};
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+
+use super::{mask, not, BitwiseOp};
+use crate::range_check::table::{BitwiseTable, TableRegistry};
+
+// Table setup
+//
+// An op on `n`-byte words is one region. The bytes go most significant first, and
+// each accumulator is multiplied by 256 before adding the byte of its row, so that
+// the last row holds the three words. E.g. `a & b` on 16-bit words:
+//
+//   a  |  b  | result | acc_a | acc_b | acc_result | q_and |
+// -----------------------------------------------------------
+//      |     |        |   0   |   0   |     0      |   0   |
+//   a1 | b1  |   r1   |  a1   |  b1   |    r1      |   1   |
+//   a0 | b0  |   r0   |   a   |   b   |     r      |   1   |
+//
+// `(a_i, b_i, r_i)` is looked up in the AND table, and the last `acc_a` and `acc_b`
+// are copy constrained to the operands.
+
+/// A word of at most 64 bits.
+#[derive(Clone, Debug)]
+pub struct Word<F: PrimeFieldBits> {
+    pub value: Value<u64>,
+    pub cell: AssignedCell<F, F>,
+}
+
+#[derive(Clone, Debug)]
+pub struct BitwiseConfig<F: PrimeFieldBits> {
+    pub a: Column<Advice>,
+    pub b: Column<Advice>,
+    pub result: Column<Advice>,
+    pub acc_a: Column<Advice>,
+    pub acc_b: Column<Advice>,
+    pub acc_result: Column<Advice>,
+    /// One selector and one table per op, in the order of [`BitwiseOp::ALL`].
+    pub selectors: [Selector; 3],
+    pub tables: [BitwiseTable<F>; 3],
+    pub instance: Column<Instance>,
+}
+
+impl<F: PrimeFieldBits> BitwiseConfig<F> {
+    fn selector(&self, op: BitwiseOp) -> Selector {
+        self.selectors[op as usize]
+    }
+}
+
+/// Bitwise ops on words, one byte lookup per byte of the word.
+#[derive(Clone, Debug)]
+pub struct BitwiseChip<F: PrimeFieldBits> {
+    config: BitwiseConfig<F>,
+}
+
+impl<F: PrimeFieldBits> BitwiseChip<F> {
+    pub fn construct(config: BitwiseConfig<F>) -> Self {
+        Self { config }
+    }
+
+    /// Configures the chip with its own tables.
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> BitwiseConfig<F> {
+        let tables = BitwiseOp::ALL.map(|op| BitwiseTable::configure(meta, op));
+        Self::configure_with_tables(meta, tables)
+    }
+
+    /// Configures the chip against the tables in `registry`, which must then be
+    /// loaded through [`TableRegistry::load`] rather than [`BitwiseChip::load_table`].
+    pub fn configure_with_registry(
+        meta: &mut ConstraintSystem<F>,
+        registry: &mut TableRegistry<F>,
+    ) -> BitwiseConfig<F> {
+        let tables = BitwiseOp::ALL.map(|op| registry.bitwise_table(meta, op));
+        Self::configure_with_tables(meta, tables)
+    }
+
+    fn configure_with_tables(
+        meta: &mut ConstraintSystem<F>,
+        tables: [BitwiseTable<F>; 3],
+    ) -> BitwiseConfig<F> {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let result = meta.advice_column();
+        let acc_a = meta.advice_column();
+        let acc_b = meta.advice_column();
+        let acc_result = meta.advice_column();
+        let constants = meta.fixed_column();
+        let selectors = BitwiseOp::ALL.map(|_| meta.complex_selector());
+        let instance = meta.instance_column();
+
+        for column in [acc_a, acc_b, acc_result] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        // Accumulators start from a constant zero, and NOT loads its mask as a
+        // constant.
+        meta.enable_constant(constants);
+
+        meta.create_gate("bytes", |meta| {
+            let q = selectors
+                .iter()
+                .map(|selector| meta.query_selector(*selector))
+                .reduce(|acc, q| acc + q)
+                .unwrap();
+            let constraints: Vec<_> = [(a, acc_a), (b, acc_b), (result, acc_result)]
+                .into_iter()
+                .map(|(byte, acc)| {
+                    let byte = meta.query_advice(byte, Rotation::cur());
+                    let acc_prev = meta.query_advice(acc, Rotation::prev());
+                    let acc_cur = meta.query_advice(acc, Rotation::cur());
+                    acc_cur - acc_prev * F::from(256) - byte
+                })
+                .collect();
+            Constraints::with_selector(q, constraints)
+        });
+
+        for (selector, table) in selectors.iter().zip(&tables) {
+            meta.lookup("bitwise", |meta| {
+                let q = meta.query_selector(*selector);
+                let a = meta.query_advice(a, Rotation::cur());
+                let b = meta.query_advice(b, Rotation::cur());
+                let result = meta.query_advice(result, Rotation::cur());
+
+                vec![
+                    (q.clone() * a, table.a),
+                    (q.clone() * b, table.b),
+                    (q * result, table.result),
+                ]
+            });
+        }
+
+        BitwiseConfig {
+            a,
+            b,
+            result,
+            acc_a,
+            acc_b,
+            acc_result,
+            selectors,
+            tables,
+            instance,
+        }
+    }
+
+    pub fn config(&self) -> &BitwiseConfig<F> {
+        &self.config
+    }
+
+    /// Loads the tables. Must be called once per circuit, and not at all if the chip
+    /// was configured from a [`TableRegistry`].
+    pub fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        for table in &self.config.tables {
+            table.load(layouter)?;
+        }
+        Ok(())
+    }
+
+    /// Witnesses a word. It is only range checked once used as an operand.
+    pub fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<u64>,
+    ) -> Result<Word<F>, Error> {
+        let cell = layouter.assign_region(
+            || "load private",
+            |mut region| {
+                region.assign_advice(|| "word", self.config.acc_a, 0, || value.map(F::from))
+            },
+        )?;
+        Ok(Word { value, cell })
+    }
+
+    /// Loads a constant word.
+    pub fn load_constant(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: u64,
+    ) -> Result<Word<F>, Error> {
+        let cell = layouter.assign_region(
+            || "load constant",
+            |mut region| {
+                region.assign_advice_from_constant(|| "word", self.config.acc_a, 0, F::from(value))
+            },
+        )?;
+        Ok(Word {
+            value: Value::known(value),
+            cell,
+        })
+    }
+
+    /// `a op b` on `bits`-bit words, failing verification unless both operands fit.
+    pub fn apply(
+        &self,
+        mut layouter: impl Layouter<F>,
+        op: BitwiseOp,
+        a: &Word<F>,
+        b: &Word<F>,
+        bits: usize,
+    ) -> Result<Word<F>, Error> {
+        let config = &self.config;
+        let num_bytes = mask(bits).count_ones() as usize / 8;
+        let byte = |word: Value<u64>, i: usize| {
+            let shift = 8 * (num_bytes - 1 - i);
+            word.map(|word| (word >> shift) & 0xff)
+        };
+
+        layouter.assign_region(
+            || format!("{:?}", op),
+            |mut region| {
+                let mut zero = |column: Column<Advice>, name: &'static str| {
+                    region.assign_advice_from_constant(|| name, column, 0, F::ZERO)
+                };
+                let mut accs = [
+                    zero(config.acc_a, "acc_a")?,
+                    zero(config.acc_b, "acc_b")?,
+                    zero(config.acc_result, "acc_result")?,
+                ];
+                let mut value = Value::known(0);
+
+                for i in 0..num_bytes {
+                    let offset = i + 1;
+                    config.selector(op).enable(&mut region, offset)?;
+
+                    let (byte_a, byte_b) = (byte(a.value, i), byte(b.value, i));
+                    let byte_result = byte_a.zip(byte_b).map(|(a, b)| op.apply(a, b));
+                    value = value
+                        .zip(byte_result)
+                        .map(|(value, byte)| (value << 8) | byte);
+
+                    let columns = [
+                        (config.a, config.acc_a, byte_a),
+                        (config.b, config.acc_b, byte_b),
+                        (config.result, config.acc_result, byte_result),
+                    ];
+                    for ((column, acc_column, byte), acc) in columns.into_iter().zip(&mut accs) {
+                        region.assign_advice(|| "byte", column, offset, || byte.map(F::from))?;
+                        let next =
+                            acc.value().copied() * Value::known(F::from(256)) + byte.map(F::from);
+                        *acc = region.assign_advice(|| "acc", acc_column, offset, || next)?;
+                    }
+                }
+
+                let [acc_a, acc_b, acc_result] = accs;
+                region.constrain_equal(acc_a.cell(), a.cell.cell())?;
+                region.constrain_equal(acc_b.cell(), b.cell.cell())?;
+                Ok(Word {
+                    value,
+                    cell: acc_result,
+                })
+            },
+        )
+    }
+
+    /// `a & b` on `bits`-bit words.
+    pub fn and(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &Word<F>,
+        b: &Word<F>,
+        bits: usize,
+    ) -> Result<Word<F>, Error> {
+        self.apply(layouter, BitwiseOp::And, a, b, bits)
+    }
+
+    /// `a | b` on `bits`-bit words.
+    pub fn or(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &Word<F>,
+        b: &Word<F>,
+        bits: usize,
+    ) -> Result<Word<F>, Error> {
+        self.apply(layouter, BitwiseOp::Or, a, b, bits)
+    }
+
+    /// `a ^ b` on `bits`-bit words.
+    pub fn xor(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &Word<F>,
+        b: &Word<F>,
+        bits: usize,
+    ) -> Result<Word<F>, Error> {
+        self.apply(layouter, BitwiseOp::Xor, a, b, bits)
+    }
+
+    /// `!a` on `bits`-bit words, as `a` XOR the all-ones word.
+    pub fn not(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &Word<F>,
+        bits: usize,
+    ) -> Result<Word<F>, Error> {
+        let ones = self.load_constant(layouter.namespace(|| "mask"), mask(bits))?;
+        self.apply(layouter, BitwiseOp::Xor, a, &ones, bits)
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+/// Applies every op to pairs of private `bits`-bit words.
+///
+/// Instance layout: `[a & b, a | b, a ^ b, !a]` for each pair in turn.
+#[derive(Clone, Debug)]
+pub struct BitwiseCircuit {
+    pub pairs: Vec<(Value<u64>, Value<u64>)>,
+    pub bits: usize,
+}
+
+impl BitwiseCircuit {
+    pub fn new(pairs: &[(u64, u64)], bits: usize) -> Self {
+        Self {
+            pairs: pairs
+                .iter()
+                .map(|(a, b)| (Value::known(*a), Value::known(*b)))
+                .collect(),
+            bits,
+        }
+    }
+
+    /// The public inputs matching `pairs`.
+    pub fn instances<F: PrimeField>(pairs: &[(u64, u64)], bits: usize) -> Vec<Vec<F>> {
+        vec![pairs
+            .iter()
+            .flat_map(|(a, b)| {
+                let [and, or, xor] = BitwiseOp::ALL.map(|op| op.apply(*a, *b));
+                [and, or, xor, not(*a, bits)]
+            })
+            .map(F::from)
+            .collect()]
+    }
+}
+
+impl<F: PrimeFieldBits> Circuit<F> for BitwiseCircuit {
+    type Config = BitwiseConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            pairs: vec![(Value::unknown(), Value::unknown()); self.pairs.len()],
+            bits: self.bits,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        BitwiseChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = BitwiseChip::construct(config);
+        chip.load_table(&mut layouter)?;
+
+        let mut row = 0;
+        for (a, b) in &self.pairs {
+            let a = chip.load_private(layouter.namespace(|| "a"), *a)?;
+            let b = chip.load_private(layouter.namespace(|| "b"), *b)?;
+
+            let mut results = vec![];
+            for op in BitwiseOp::ALL {
+                results.push(chip.apply(layouter.namespace(|| "op"), op, &a, &b, self.bits)?);
+            }
+            results.push(chip.not(layouter.namespace(|| "not"), &a, self.bits)?);
+
+            for result in results {
+                chip.expose_public(layouter.namespace(|| "result"), &result.cell, row)?;
+                row += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::dev::MockProver;
+    use halo2curves::pasta::Fp;
+
+    /// The three byte tables take 2^16 rows each.
+    const K: u32 = 17;
+
+    const PAIRS_32: [(u64, u64); 5] = [
+        (0, 0),
+        (0xffff_ffff, 0),
+        (0xffff_ffff, 0xffff_ffff),
+        (0xdead_beef, 0x1234_5678),
+        (0x8000_0001, 0x7fff_fffe),
+    ];
+
+    const PAIRS_64: [(u64, u64); 4] = [
+        (0, u64::MAX),
+        (u64::MAX, u64::MAX),
+        (0xdead_beef_0123_4567, 0x89ab_cdef_fedc_ba98),
+        (0x8000_0000_0000_0001, 0x0000_ffff_0000_ffff),
+    ];
+
+    #[test]
+    fn bitwise_chip_native() {
+        for (pairs, bits) in [(&PAIRS_32[..], 32), (&PAIRS_64[..], 64)] {
+            let instances = BitwiseCircuit::instances::<Fp>(pairs, bits);
+            for ((a, b), results) in pairs.iter().zip(instances[0].chunks(4)) {
+                let expected = [a & b, a | b, a ^ b, !a & mask(bits)].map(Fp::from);
+                assert_eq!(results, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn bitwise_chip_32() {
+        let circuit = BitwiseCircuit::new(&PAIRS_32, 32);
+        let instances = BitwiseCircuit::instances::<Fp>(&PAIRS_32, 32);
+        let prover = MockProver::run(K, &circuit, instances).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn bitwise_chip_64() {
+        let circuit = BitwiseCircuit::new(&PAIRS_64, 64);
+        let instances = BitwiseCircuit::instances::<Fp>(&PAIRS_64, 64);
+        let prover = MockProver::run(K, &circuit, instances).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn bitwise_chip_rejects() {
+        let pairs = [(0xdead_beef, 0x1234_5678)];
+        let circuit = BitwiseCircuit::new(&pairs, 32);
+
+        // A wrong result.
+        let mut wrong = BitwiseCircuit::instances::<Fp>(&pairs, 32);
+        wrong[0][2] += Fp::from(1);
+        let prover = MockProver::run(K, &circuit, wrong).unwrap();
+        assert!(prover.verify().is_err());
+
+        // An operand wider than the word, whatever results are claimed.
+        let pairs = [(1 << 32, 0)];
+        let circuit = BitwiseCircuit::new(&pairs, 32);
+        let instances = BitwiseCircuit::instances::<Fp>(&pairs, 32);
+        let prover = MockProver::run(K, &circuit, instances).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,27 @@
+//! Bitwise AND, OR, XOR and NOT on words of up to 64 bits.
+//!
+//! [`BitwiseChip`] decomposes its operands into bytes, looks up every pair of bytes
+//! and its result in a [`BitwiseTable`](crate::range_check::table::BitwiseTable) of
+//! the op, and recomposes the result bytes into a word. The decomposition range
+//! checks the operands as a side effect. NOT is an XOR with the all-ones word of the
+//! width.
+
+pub use crate::range_check::table::BitwiseOp;
+
+mod chip;
+
+pub use chip::{BitwiseChip, BitwiseCircuit, BitwiseConfig, Word};
+
+/// The all-ones word of `bits` bits.
+pub fn mask(bits: usize) -> u64 {
+    assert!(
+        bits % 8 == 0 && (8..=64).contains(&bits),
+        "unsupported word size"
+    );
+    u64::MAX >> (64 - bits)
+}
+
+/// Native reference: `!a` on `bits` bits.
+pub fn not(a: u64, bits: usize) -> u64 {
+    !a & mask(bits)
+}
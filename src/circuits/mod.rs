@@ -0,0 +1,342 @@
+//! A registry of the example circuits, so that proving code, benches and tools can
+//! work over every circuit without knowing about each one.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use ff::{PrimeField, PrimeFieldBits};
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::Circuit;
+use serde_json::json;
+
+use crate::add1;
+use crate::fibonacci;
+use crate::is_zero::{is_zero, IsZeroCircuit};
+use crate::range_check::RangeCheckManyCircuit;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WitnessError {
+    /// The witness has no field with this name.
+    Missing(&'static str),
+    /// The field exists but could not be parsed.
+    Invalid(&'static str),
+    /// Public inputs were asked of a circuit whose witness is not known.
+    Unknown,
+}
+
+impl fmt::Display for WitnessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WitnessError::Missing(field) => write!(f, "missing witness field `{}`", field),
+            WitnessError::Invalid(field) => write!(f, "invalid witness field `{}`", field),
+            WitnessError::Unknown => write!(f, "public inputs need a known witness"),
+        }
+    }
+}
+
+impl std::error::Error for WitnessError {}
+
+/// An example circuit that can be built from a JSON witness and knows its own
+/// public inputs.
+pub trait CircuitDef<F: PrimeField>: Circuit<F> + Clone {
+    /// Name used to select the circuit.
+    const NAME: &'static str;
+    /// Smallest `k` that fits [`CircuitDef::example`].
+    const DEFAULT_K: u32;
+
+    /// A JSON witness that [`CircuitDef::from_json`] accepts and that fits in
+    /// `DEFAULT_K` rows.
+    fn example_json() -> serde_json::Value;
+
+    /// Builds the circuit from a JSON witness. Field elements are given either as
+    /// numbers or as decimal strings.
+    fn from_json(witness: &serde_json::Value) -> Result<Self, WitnessError>;
+
+    /// Public inputs, one vector per instance column. Fails if the witness is not
+    /// known.
+    fn instances(&self) -> Result<Vec<Vec<F>>, WitnessError>;
+
+    fn example() -> Self {
+        Self::from_json(&Self::example_json()).expect("example witness is valid")
+    }
+}
+
+/// Callback for [`visit_all`], called once per registered circuit.
+pub trait CircuitVisitor<F: PrimeFieldBits> {
+    fn visit<C: CircuitDef<F>>(&mut self);
+}
+
+/// Calls `visitor` on every registered circuit.
+pub fn visit_all<F: PrimeFieldBits, V: CircuitVisitor<F>>(visitor: &mut V) {
+    visitor.visit::<add1::example1::MyCircuit<F>>();
+    visitor.visit::<add1::example2::MyCircuit<F, 8>>();
+    visitor.visit::<fibonacci::example1::FibonacciCircuit<F>>();
+    visitor.visit::<fibonacci::example2::FibonacciCircuit<F>>();
+    visitor.visit::<RangeCheckManyCircuit<F>>();
+    visitor.visit::<IsZeroCircuit<F>>();
+}
+
+/// Names of every registered circuit.
+pub fn names<F: PrimeFieldBits>() -> Vec<&'static str> {
+    struct Names<F>(Vec<&'static str>, PhantomData<F>);
+
+    impl<F: PrimeFieldBits> CircuitVisitor<F> for Names<F> {
+        fn visit<C: CircuitDef<F>>(&mut self) {
+            self.0.push(C::NAME);
+        }
+    }
+
+    let mut names = Names::<F>(vec![], PhantomData);
+    visit_all(&mut names);
+    names.0
+}
+
+fn field<F: PrimeField>(
+    witness: &serde_json::Value,
+    name: &'static str,
+) -> Result<F, WitnessError> {
+    parse_field(witness.get(name).ok_or(WitnessError::Missing(name))?, name)
+}
+
+fn parse_field<F: PrimeField>(
+    value: &serde_json::Value,
+    name: &'static str,
+) -> Result<F, WitnessError> {
+    match value {
+        serde_json::Value::Number(n) => n.as_u64().map(F::from),
+        serde_json::Value::String(s) => F::from_str_vartime(s),
+        _ => None,
+    }
+    .ok_or(WitnessError::Invalid(name))
+}
+
+fn usize_field(witness: &serde_json::Value, name: &'static str) -> Result<usize, WitnessError> {
+    witness
+        .get(name)
+        .ok_or(WitnessError::Missing(name))?
+        .as_u64()
+        .map(|n| n as usize)
+        .ok_or(WitnessError::Invalid(name))
+}
+
+/// Extracts a known value, for computing public inputs from a witness.
+fn known<F: Copy>(value: Value<F>) -> Result<F, WitnessError> {
+    let mut known = None;
+    value.map(|value| known = Some(value));
+    known.ok_or(WitnessError::Unknown)
+}
+
+impl<F: PrimeField> CircuitDef<F> for add1::example1::MyCircuit<F> {
+    const NAME: &'static str = "add1";
+    const DEFAULT_K: u32 = 5;
+
+    fn example_json() -> serde_json::Value {
+        json!({ "value": 5, "scalar": 3, "nrows": 10 })
+    }
+
+    fn from_json(witness: &serde_json::Value) -> Result<Self, WitnessError> {
+        Ok(Self {
+            value: field(witness, "value")?,
+            scalar: field(witness, "scalar")?,
+            nrows: usize_field(witness, "nrows")?,
+            _marker: PhantomData,
+        })
+    }
+
+    fn instances(&self) -> Result<Vec<Vec<F>>, WitnessError> {
+        Ok(vec![vec![add1::example1::expected_output(
+            self.value,
+            self.scalar,
+            self.nrows,
+        )]])
+    }
+}
+
+impl<F: PrimeField> CircuitDef<F> for add1::example2::MyCircuit<F, 8> {
+    const NAME: &'static str = "add1_wide";
+    const DEFAULT_K: u32 = 5;
+
+    fn example_json() -> serde_json::Value {
+        json!({ "value": 5, "scalar": 3, "nrows": 100 })
+    }
+
+    fn from_json(witness: &serde_json::Value) -> Result<Self, WitnessError> {
+        Ok(Self {
+            value: field(witness, "value")?,
+            scalar: field(witness, "scalar")?,
+            nrows: usize_field(witness, "nrows")?,
+            _marker: PhantomData,
+        })
+    }
+
+    fn instances(&self) -> Result<Vec<Vec<F>>, WitnessError> {
+        Ok(vec![vec![add1::example1::expected_output(
+            self.value,
+            self.scalar,
+            self.nrows,
+        )]])
+    }
+}
+
+impl<F: PrimeField> CircuitDef<F> for fibonacci::example1::FibonacciCircuit<F> {
+    const NAME: &'static str = "fibonacci";
+    const DEFAULT_K: u32 = 5;
+
+    fn example_json() -> serde_json::Value {
+        json!({ "a": 1, "b": 1, "n": 20 })
+    }
+
+    fn from_json(witness: &serde_json::Value) -> Result<Self, WitnessError> {
+        Ok(Self {
+            a: Value::known(field(witness, "a")?),
+            b: Value::known(field(witness, "b")?),
+            n: usize_field(witness, "n")?,
+        })
+    }
+
+    fn instances(&self) -> Result<Vec<Vec<F>>, WitnessError> {
+        let out = fibonacci::example1::fibonacci(known(self.a)?, known(self.b)?, self.n);
+        Ok(vec![vec![out]])
+    }
+}
+
+impl<F: PrimeField> CircuitDef<F> for fibonacci::example2::FibonacciCircuit<F> {
+    const NAME: &'static str = "fibonacci_single_column";
+    const DEFAULT_K: u32 = 5;
+
+    fn example_json() -> serde_json::Value {
+        json!({ "a": 1, "b": 1, "n": 20 })
+    }
+
+    fn from_json(witness: &serde_json::Value) -> Result<Self, WitnessError> {
+        Ok(Self {
+            a: Value::known(field(witness, "a")?),
+            b: Value::known(field(witness, "b")?),
+            n: usize_field(witness, "n")?,
+        })
+    }
+
+    fn instances(&self) -> Result<Vec<Vec<F>>, WitnessError> {
+        let out = fibonacci::example1::fibonacci(known(self.a)?, known(self.b)?, self.n);
+        Ok(vec![vec![out]])
+    }
+}
+
+impl<F: PrimeFieldBits> CircuitDef<F> for RangeCheckManyCircuit<F> {
+    const NAME: &'static str = "range_check";
+    const DEFAULT_K: u32 = 9;
+
+    fn example_json() -> serde_json::Value {
+        json!({ "values": [0, 1, 42, 255], "num_bits": 8 })
+    }
+
+    fn from_json(witness: &serde_json::Value) -> Result<Self, WitnessError> {
+        let values = witness
+            .get("values")
+            .ok_or(WitnessError::Missing("values"))?
+            .as_array()
+            .ok_or(WitnessError::Invalid("values"))?
+            .iter()
+            .map(|value| parse_field(value, "values").map(Value::known))
+            .collect::<Result<_, _>>()?;
+
+        // The range check chip only supports tables of 1 to 31 bits.
+        let num_bits = usize_field(witness, "num_bits")?;
+        if !(1..32).contains(&num_bits) {
+            return Err(WitnessError::Invalid("num_bits"));
+        }
+
+        Ok(Self { values, num_bits })
+    }
+
+    fn instances(&self) -> Result<Vec<Vec<F>>, WitnessError> {
+        Ok(vec![])
+    }
+}
+
+impl<F: PrimeField> CircuitDef<F> for IsZeroCircuit<F> {
+    const NAME: &'static str = "is_zero";
+    const DEFAULT_K: u32 = 4;
+
+    fn example_json() -> serde_json::Value {
+        json!({ "value": 42 })
+    }
+
+    fn from_json(witness: &serde_json::Value) -> Result<Self, WitnessError> {
+        Ok(Self {
+            value: Value::known(field(witness, "value")?),
+        })
+    }
+
+    fn instances(&self) -> Result<Vec<Vec<F>>, WitnessError> {
+        Ok(vec![vec![is_zero(known(self.value)?)]])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::dev::MockProver;
+    use pasta_curves::pallas;
+
+    struct MockProve;
+
+    impl CircuitVisitor<pallas::Base> for MockProve {
+        fn visit<C: CircuitDef<pallas::Base>>(&mut self) {
+            let circuit = C::example();
+            let instances = circuit.instances().unwrap();
+            let prover = MockProver::run(C::DEFAULT_K, &circuit, instances).unwrap();
+            assert_eq!(prover.verify(), Ok(()), "{}", C::NAME);
+        }
+    }
+
+    #[test]
+    fn registry_examples() {
+        visit_all(&mut MockProve);
+
+        let names = names::<pallas::Base>();
+        assert_eq!(names.len(), 6);
+        for (i, name) in names.iter().enumerate() {
+            assert!(!names[..i].contains(name), "duplicate name {}", name);
+        }
+    }
+
+    #[test]
+    fn registry_witness_json() {
+        let circuit =
+            <IsZeroCircuit<pallas::Base> as CircuitDef<_>>::from_json(&json!({ "value": "0" }))
+                .unwrap();
+        assert_eq!(circuit.instances(), Ok(vec![vec![pallas::Base::ONE]]));
+
+        let circuit = <add1::example1::MyCircuit<pallas::Base> as CircuitDef<_>>::from_json(
+            &json!({ "value": "5", "scalar": 3, "nrows": 4 }),
+        )
+        .unwrap();
+        assert_eq!(circuit.instances(), Ok(vec![vec![pallas::Base::from(14)]]));
+
+        let err = <add1::example1::MyCircuit<pallas::Base> as CircuitDef<_>>::from_json(
+            &json!({ "value": 5, "nrows": 4 }),
+        )
+        .unwrap_err();
+        assert_eq!(err, WitnessError::Missing("scalar"));
+
+        let err = <RangeCheckManyCircuit<pallas::Base> as CircuitDef<_>>::from_json(
+            &json!({ "values": [1, "x"], "num_bits": 8 }),
+        )
+        .unwrap_err();
+        assert_eq!(err, WitnessError::Invalid("values"));
+
+        for num_bits in [0, 32, 64] {
+            let err = <RangeCheckManyCircuit<pallas::Base> as CircuitDef<_>>::from_json(
+                &json!({ "values": [1], "num_bits": num_bits }),
+            )
+            .unwrap_err();
+            assert_eq!(err, WitnessError::Invalid("num_bits"));
+        }
+
+        let circuit = IsZeroCircuit::<pallas::Base> {
+            value: Value::unknown(),
+        };
+        assert_eq!(circuit.instances(), Err(WitnessError::Unknown));
+    }
+}
@@ -0,0 +1,495 @@
+//! Integer division with remainder, and reduction modulo a constant.
+//!
+//! [`DivChip`] witnesses `q = a / b` and `r = a mod b` for `num_bits`-bit integers
+//! and constrains `a = q * b + r`. Range checks through a [`RangeCheckChip`] keep
+//! every operand below `2^num_bits` and `b - r - 1` too, which is `r < b`. The
+//! product cannot wrap around the modulus, so the quotient and remainder are the
+//! integer ones, and a zero divisor leaves no valid witness.
+
+use ff::{PrimeField, PrimeFieldBits};
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+
+use crate::model::to_u64;
+use crate::range_check::table::TableRegistry;
+use crate::range_check::{RangeCheckChip, RangeCheckConfig, RangeConstrained};
+
+// Table setup
+//
+//    a   |  b  |  quotient  | remainder | diff | q_div |
+// -------------------------------------------------------
+//    17  |  5  |     3      |     2     |  2   |   1   |
+//
+// with `a = quotient * b + remainder` and `diff = b - remainder - 1`, and `a`, `b`,
+// `quotient`, `remainder` and `diff` copied into the range check chip.
+
+/// A cell constrained to `num_bits` bits.
+type Checked<F> = RangeConstrained<F, AssignedCell<F, F>>;
+
+/// Native reference: the quotient and remainder of `a / b`, if `b` is not zero.
+pub fn div_rem(a: u64, b: u64) -> Option<(u64, u64)> {
+    Some((a.checked_div(b)?, a.checked_rem(b)?))
+}
+
+#[derive(Clone, Debug)]
+pub struct DivConfig<F: PrimeFieldBits> {
+    pub a: Column<Advice>,
+    pub b: Column<Advice>,
+    pub quotient: Column<Advice>,
+    pub remainder: Column<Advice>,
+    pub diff: Column<Advice>,
+    pub q_div: Selector,
+    pub range: RangeCheckConfig<F>,
+    pub instance: Column<Instance>,
+}
+
+/// Quotient and remainder of `num_bits`-bit integers, range checked against a
+/// `num_bits`-bit table.
+#[derive(Clone, Debug)]
+pub struct DivChip<F: PrimeFieldBits> {
+    config: DivConfig<F>,
+    range: RangeCheckChip<F>,
+}
+
+impl<F: PrimeFieldBits> DivChip<F> {
+    pub fn construct(config: DivConfig<F>) -> Self {
+        let range = RangeCheckChip::construct(config.range.clone());
+        Self { config, range }
+    }
+
+    /// Configures the chip with its own `num_bits`-bit table.
+    pub fn configure(meta: &mut ConstraintSystem<F>, num_bits: usize) -> DivConfig<F> {
+        let advice = meta.advice_column();
+        let range = RangeCheckChip::configure(meta, advice, num_bits);
+        Self::configure_with_range(meta, range)
+    }
+
+    /// Configures the chip against the `num_bits`-bit table in `registry`, which must
+    /// then be loaded through [`TableRegistry::load`] rather than
+    /// [`DivChip::load_table`].
+    pub fn configure_with_registry(
+        meta: &mut ConstraintSystem<F>,
+        registry: &mut TableRegistry<F>,
+        num_bits: usize,
+    ) -> DivConfig<F> {
+        let advice = meta.advice_column();
+        let range = RangeCheckChip::configure_with_registry(meta, advice, registry, num_bits);
+        Self::configure_with_range(meta, range)
+    }
+
+    fn configure_with_range(
+        meta: &mut ConstraintSystem<F>,
+        range: RangeCheckConfig<F>,
+    ) -> DivConfig<F> {
+        // `q * b + r < 2^(2 * num_bits)` must not wrap around the modulus.
+        assert!(
+            2 * range.num_bits() < F::CAPACITY as usize,
+            "operands too wide for the field"
+        );
+
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let quotient = meta.advice_column();
+        let remainder = meta.advice_column();
+        let diff = meta.advice_column();
+        let constants = meta.fixed_column();
+        let q_div = meta.selector();
+        let instance = meta.instance_column();
+
+        for column in [a, b, quotient, remainder, diff] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        // Moduli are loaded as constants.
+        meta.enable_constant(constants);
+
+        meta.create_gate("division", |meta| {
+            let q_div = meta.query_selector(q_div);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let quotient = meta.query_advice(quotient, Rotation::cur());
+            let remainder = meta.query_advice(remainder, Rotation::cur());
+            let diff = meta.query_advice(diff, Rotation::cur());
+
+            Constraints::with_selector(
+                q_div,
+                [
+                    (
+                        "a = q * b + r",
+                        a - quotient * b.clone() - remainder.clone(),
+                    ),
+                    (
+                        "diff = b - r - 1",
+                        diff - (b - remainder - Expression::Constant(F::ONE)),
+                    ),
+                ],
+            )
+        });
+
+        DivConfig {
+            a,
+            b,
+            quotient,
+            remainder,
+            diff,
+            q_div,
+            range,
+            instance,
+        }
+    }
+
+    pub fn config(&self) -> &DivConfig<F> {
+        &self.config
+    }
+
+    /// Loads the range table. Must be called once per circuit, and not at all if the
+    /// chip was configured from a [`TableRegistry`].
+    pub fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.range.load_table(layouter)
+    }
+
+    /// Witnesses an operand. It is range checked once divided.
+    pub fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "load private",
+            |mut region| region.assign_advice(|| "operand", self.config.a, 0, || value),
+        )
+    }
+
+    /// The quotient and remainder of `a / b`. Fails verification if `b` is zero, or if
+    /// either operand is not below `2^num_bits`.
+    pub fn div_rem(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<(Checked<F>, Checked<F>), Error> {
+        let witness = a.value().zip(b.value()).map(|(a, b)| {
+            let quotient_remainder = match (to_u64(*a), to_u64(*b)) {
+                (Some(a), Some(b)) => div_rem(a, b),
+                _ => None,
+            };
+            // Nothing satisfies the constraints then, so any witness fails.
+            quotient_remainder.unwrap_or((0, 0))
+        });
+        let (quotient, remainder) = witness.unzip();
+        self.assign(
+            layouter,
+            a,
+            b,
+            quotient.map(F::from),
+            remainder.map(F::from),
+        )
+    }
+
+    /// `a mod modulus`. Fails verification if `modulus` is zero, or if `a` is not below
+    /// `2^num_bits`.
+    pub fn reduce(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        modulus: u64,
+    ) -> Result<Checked<F>, Error> {
+        let modulus = layouter.assign_region(
+            || "modulus",
+            |mut region| {
+                region.assign_advice_from_constant(|| "modulus", self.config.b, 0, F::from(modulus))
+            },
+        )?;
+        let (_, remainder) = self.div_rem(layouter.namespace(|| "reduce"), a, &modulus)?;
+        Ok(remainder)
+    }
+
+    /// Lays out the division row for a witnessed quotient and remainder, and range
+    /// checks every operand.
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        quotient: Value<F>,
+        remainder: Value<F>,
+    ) -> Result<(Checked<F>, Checked<F>), Error> {
+        let config = &self.config;
+        let (quotient, remainder, diff) = layouter.assign_region(
+            || "division",
+            |mut region| {
+                config.q_div.enable(&mut region, 0)?;
+                a.copy_advice(|| "a", &mut region, config.a, 0)?;
+                b.copy_advice(|| "b", &mut region, config.b, 0)?;
+                let quotient =
+                    region.assign_advice(|| "quotient", config.quotient, 0, || quotient)?;
+                let remainder =
+                    region.assign_advice(|| "remainder", config.remainder, 0, || remainder)?;
+                let diff = b.value().copied() - remainder.value().copied() - Value::known(F::ONE);
+                let diff = region.assign_advice(|| "diff", config.diff, 0, || diff)?;
+                Ok((quotient, remainder, diff))
+            },
+        )?;
+
+        for (name, cell) in [("a", a), ("b", b), ("diff", &diff)] {
+            self.range.copy_check(layouter.namespace(|| name), cell)?;
+        }
+        let quotient = self
+            .range
+            .copy_check(layouter.namespace(|| "quotient"), &quotient)?;
+        let remainder = self
+            .range
+            .copy_check(layouter.namespace(|| "remainder"), &remainder)?;
+        Ok((quotient, remainder))
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+/// Divides two private `num_bits`-bit integers, with `num_bits` chosen at runtime.
+///
+/// Instance layout: `[q, r]`.
+#[derive(Clone, Debug)]
+pub struct DivCircuit {
+    pub a: Value<u64>,
+    pub b: Value<u64>,
+    pub num_bits: usize,
+}
+
+impl DivCircuit {
+    pub fn new(a: u64, b: u64, num_bits: usize) -> Self {
+        Self {
+            a: Value::known(a),
+            b: Value::known(b),
+            num_bits,
+        }
+    }
+
+    /// The public inputs matching `a / b`.
+    pub fn instances<F: PrimeField>(a: u64, b: u64) -> Vec<Vec<F>> {
+        let (quotient, remainder) = div_rem(a, b).expect("division by zero");
+        vec![vec![F::from(quotient), F::from(remainder)]]
+    }
+}
+
+impl<F: PrimeFieldBits> Circuit<F> for DivCircuit {
+    type Config = DivConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = usize;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            a: Value::unknown(),
+            b: Value::unknown(),
+            num_bits: self.num_bits,
+        }
+    }
+
+    fn params(&self) -> Self::Params {
+        self.num_bits
+    }
+
+    fn configure_with_params(meta: &mut ConstraintSystem<F>, num_bits: usize) -> Self::Config {
+        DivChip::configure(meta, num_bits)
+    }
+
+    fn configure(_meta: &mut ConstraintSystem<F>) -> Self::Config {
+        unreachable!("DivCircuit is configured through configure_with_params")
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = DivChip::construct(config);
+        chip.load_table(&mut layouter)?;
+
+        let a = chip.load_private(layouter.namespace(|| "a"), self.a.map(F::from))?;
+        let b = chip.load_private(layouter.namespace(|| "b"), self.b.map(F::from))?;
+        let (quotient, remainder) = chip.div_rem(layouter.namespace(|| "a / b"), &a, &b)?;
+
+        chip.expose_public(layouter.namespace(|| "quotient"), quotient.inner(), 0)?;
+        chip.expose_public(layouter.namespace(|| "remainder"), remainder.inner(), 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::dev::MockProver;
+    use halo2curves::pasta::Fp;
+
+    const NUM_BITS: usize = 8;
+    /// The 8-bit table takes 256 rows.
+    const K: u32 = 9;
+
+    #[test]
+    fn div_rem_native() {
+        assert_eq!(div_rem(17, 5), Some((3, 2)));
+        assert_eq!(div_rem(4, 9), Some((0, 4)));
+        assert_eq!(div_rem(1, 0), None);
+    }
+
+    #[test]
+    fn div_chip() {
+        for (a, b) in [(17, 5), (0, 7), (255, 1), (255, 255), (10, 20), (200, 13)] {
+            let circuit = DivCircuit::new(a, b, NUM_BITS);
+            let instances = DivCircuit::instances::<Fp>(a, b);
+            let prover = MockProver::run(K, &circuit, instances).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn div_chip_rejects_division_by_zero() {
+        // No quotient and remainder work, whatever is claimed.
+        for claimed in [(0, 0), (0, 17), (255, 17)] {
+            let circuit = DivCircuit::new(17, 0, NUM_BITS);
+            let instances = vec![vec![Fp::from(claimed.0), Fp::from(claimed.1)]];
+            let prover = MockProver::run(K, &circuit, instances).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn div_chip_rejects() {
+        // A wrong quotient.
+        let circuit = DivCircuit::new(17, 5, NUM_BITS);
+        let prover = MockProver::run(K, &circuit, vec![vec![Fp::from(2), Fp::from(2)]]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // An operand wider than the table.
+        let circuit = DivCircuit::new(256, 2, NUM_BITS);
+        let instances = DivCircuit::instances::<Fp>(256, 2);
+        let prover = MockProver::run(K, &circuit, instances).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    /// Divides with a chosen quotient and remainder rather than the honest ones.
+    #[derive(Clone, Debug)]
+    struct WitnessCircuit {
+        a: u64,
+        b: u64,
+        quotient: Fp,
+        remainder: Fp,
+    }
+
+    impl Circuit<Fp> for WitnessCircuit {
+        type Config = DivConfig<Fp>;
+        type FloorPlanner = SimpleFloorPlanner;
+        type Params = ();
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            DivChip::configure(meta, NUM_BITS)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = DivChip::construct(config);
+            chip.load_table(&mut layouter)?;
+
+            let a =
+                chip.load_private(layouter.namespace(|| "a"), Value::known(Fp::from(self.a)))?;
+            let b =
+                chip.load_private(layouter.namespace(|| "b"), Value::known(Fp::from(self.b)))?;
+            chip.assign(
+                layouter.namespace(|| "a / b"),
+                &a,
+                &b,
+                Value::known(self.quotient),
+                Value::known(self.remainder),
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn div_chip_rejects_dishonest_witness() {
+        let run = |quotient: Fp, remainder: Fp| {
+            let circuit = WitnessCircuit {
+                a: 17,
+                b: 5,
+                quotient,
+                remainder,
+            };
+            MockProver::run(K, &circuit, vec![vec![]]).unwrap().verify()
+        };
+
+        assert!(run(Fp::from(3), Fp::from(2)).is_ok());
+        // 17 = 2 * 5 + 7, with a remainder not below the divisor.
+        assert!(run(Fp::from(2), Fp::from(7)).is_err());
+        // 17 = 4 * 5 - 3, with a negative remainder.
+        assert!(run(Fp::from(4), -Fp::from(3)).is_err());
+    }
+
+    /// Reduces a private value modulo a constant.
+    #[derive(Clone, Debug)]
+    struct ReduceCircuit {
+        a: Value<u64>,
+        modulus: u64,
+    }
+
+    impl Circuit<Fp> for ReduceCircuit {
+        type Config = DivConfig<Fp>;
+        type FloorPlanner = SimpleFloorPlanner;
+        type Params = ();
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                a: Value::unknown(),
+                modulus: self.modulus,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            DivChip::configure(meta, NUM_BITS)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = DivChip::construct(config);
+            chip.load_table(&mut layouter)?;
+
+            let a = chip.load_private(layouter.namespace(|| "a"), self.a.map(Fp::from))?;
+            let reduced = chip.reduce(layouter.namespace(|| "a mod m"), &a, self.modulus)?;
+            chip.expose_public(layouter.namespace(|| "reduced"), reduced.inner(), 0)
+        }
+    }
+
+    #[test]
+    fn div_chip_reduce() {
+        for (a, modulus) in [(200, 7), (6, 7), (255, 2), (0, 1)] {
+            let circuit = ReduceCircuit {
+                a: Value::known(a),
+                modulus,
+            };
+            let instances = vec![vec![Fp::from(a % modulus)]];
+            let prover = MockProver::run(K, &circuit, instances).unwrap();
+            prover.assert_satisfied();
+        }
+
+        let circuit = ReduceCircuit {
+            a: Value::known(200),
+            modulus: 0,
+        };
+        let prover = MockProver::run(K, &circuit, vec![vec![Fp::from(0)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -1,39 +1,91 @@
 use halo2_proofs::{arithmetic::Field, circuit::*, plonk::*, poly::Rotation};
+use rayon::prelude::*;
 use std::marker::PhantomData;
 
 // Table setup
 //
-// let v = small value range check range: u8 bits
-// let v' = big value range check with lookup argument
+// All rows live in a single region. Row i holds (F(i), F(i+1), F(i+2)), and
+// consecutive rows are tied together with rotations instead of copy constraints:
+// a(next) = b(cur), b(next) = c(cur).
 //
-//        a   |   b   |   c  |  selector  |
+//        a   |   b   |   c  |  selector  |  q_chain  |
 // -------------------------------------------------------------------
-//  1:      F(0) |  F(1) | F(2) |      1     |
-//  2:      F(1) |  F(2) | F(3) |      1     |
-//  3:      F(2) |  F(3) | F(4) |      1     |
-//  4:      F(3) |  F(4) | F(5) |      1     |
-//  5:      F(4) |  F(5) | F(6) |      1     |
-//  6:      F(5) |  F(6) | F(7) |      1     |
-//  7:      F(6) |  F(7) | F(8) |      1     |
-//  8:      F(7) |  F(8) | F(9) |      1     |
-//  9:      F(8) |  F(9) |F(10) |      1     |
-//  10:     F(9) | F(10) |F(11) |      1     |
+//  0:   F(0) |  F(1) | F(2) |      1     |     1     |
+//  1:   F(1) |  F(2) | F(3) |      1     |     1     |
+//  2:   F(2) |  F(3) | F(4) |      1     |     1     |
+//  ...
+//  n-2: F(n-2)|F(n-1)| F(n) |      1     |     0     |
+
+/// Native reference: the n-th element of the sequence starting with `a, b`.
+pub fn fibonacci<F: Field>(a: F, b: F, n: usize) -> F {
+    let (mut a, mut b) = (a, b);
+    for _ in 0..n {
+        let c = a + b;
+        a = b;
+        b = c;
+    }
+    a
+}
+
+/// Rows used to compute F(n).
+pub fn rows_for(n: usize) -> usize {
+    n.saturating_sub(1).max(1)
+}
+
+/// Rows per parallel chunk in [`trace`].
+const TRACE_CHUNK: usize = 1 << 12;
+
+/// `(F(s), F(s+1))` of the sequence starting with `a, b`, by fast doubling on the
+/// standard sequence `f`: `F(s) = a * f(s-1) + b * f(s)`.
+fn fibonacci_pair<F: Field>(a: F, b: F, s: usize) -> (F, F) {
+    // (f(i), f(i+1)), walking the bits of `s` from the top.
+    let (mut f0, mut f1) = (F::ZERO, F::ONE);
+    for bit in (0..usize::BITS - s.leading_zeros()).rev() {
+        let f2 = f0 * (f1.double() - f0);
+        let f3 = f0.square() + f1.square();
+        (f0, f1) = if (s >> bit) & 1 == 1 {
+            (f3, f2 + f3)
+        } else {
+            (f2, f3)
+        };
+    }
+
+    (a * (f1 - f0) + b * f0, a * f0 + b * f1)
+}
+
+/// `F(0), ..., F(len - 1)` of the sequence starting with `a, b`. Each chunk starts
+/// from its own `(F(s), F(s+1))` pair, so chunks are computed in parallel.
+pub fn trace<F: Field>(a: F, b: F, len: usize) -> Vec<F> {
+    let mut trace = vec![F::ZERO; len];
+    trace
+        .par_chunks_mut(TRACE_CHUNK)
+        .enumerate()
+        .for_each(|(i, chunk)| {
+            let (mut a, mut b) = fibonacci_pair(a, b, i * TRACE_CHUNK);
+            for cell in chunk {
+                *cell = a;
+                let c = a + b;
+                a = b;
+                b = c;
+            }
+        });
+    trace
+}
 
 #[derive(Clone, Debug)]
-struct FibonacciConfig {
+pub struct FibonacciConfig {
     pub advice: [Column<Advice>; 3],
     pub selector: Selector,
+    pub q_chain: Selector,
     pub instance: Column<Instance>,
 }
 
 #[derive(Clone, Debug)]
-struct FibonacciChip<F: Field> {
+pub struct FibonacciChip<F: Field> {
     config: FibonacciConfig,
     _marker: PhantomData<F>,
 }
 
-type AssignedRow<F> = (AssignedCell<F, F>, AssignedCell<F, F>, AssignedCell<F, F>);
-
 impl<F: Field> FibonacciChip<F> {
     pub fn construct(config: FibonacciConfig) -> Self {
         Self {
@@ -47,6 +99,7 @@ impl<F: Field> FibonacciChip<F> {
         let b = meta.advice_column();
         let c = meta.advice_column();
         let selector = meta.selector();
+        let q_chain = meta.selector();
         let instance = meta.instance_column();
 
         meta.enable_equality(a);
@@ -63,22 +116,52 @@ impl<F: Field> FibonacciChip<F> {
             vec![selector * (a + b - c)]
         });
 
+        meta.create_gate("chain", |meta| {
+            let q_chain = meta.query_selector(q_chain);
+            let a_next = meta.query_advice(a, Rotation::next());
+            let b = meta.query_advice(b, Rotation::cur());
+            let b_next = meta.query_advice(b, Rotation::next());
+            let c = meta.query_advice(c, Rotation::cur());
+
+            vec![q_chain.clone() * (a_next - b), q_chain * (b_next - c)]
+        });
+
         FibonacciConfig {
             advice: [a, b, c],
             selector,
+            q_chain,
             instance,
         }
     }
 
-    pub fn assign_initial_values(
+    /// Computes F(n) from private initial values `a = F(0)` and `b = F(1)`.
+    pub fn assign(
         &self,
         mut layouter: impl Layouter<F>,
-    ) -> Result<AssignedRow<F>, Error> {
+        a: Value<F>,
+        b: Value<F>,
+        n: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
         layouter.assign_region(
-            || "initial values",
+            || "fibonacci",
             |mut region| {
-                self.config.selector.enable(&mut region, 0)?;
+                let a_cell = region.assign_advice(|| "a0", self.config.advice[0], 0, || a)?;
+                let b_cell = region.assign_advice(|| "a1", self.config.advice[1], 0, || b)?;
 
+                self.assign_rows(&mut region, a_cell, b_cell, n)
+            },
+        )
+    }
+
+    /// Computes F(n) from initial values copied from instance rows 0 and 1.
+    pub fn assign_from_instance(
+        &self,
+        mut layouter: impl Layouter<F>,
+        n: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "fibonacci",
+            |mut region| {
                 let a_cell = region.assign_advice_from_instance(
                     || "a0",
                     self.config.instance,
@@ -95,43 +178,93 @@ impl<F: Field> FibonacciChip<F> {
                     0,
                 )?;
 
-                let c_cell = region.assign_advice(
-                    || "a + b",
-                    self.config.advice[2],
-                    0,
-                    || a_cell.value().copied() + b_cell.value(),
-                )?;
-
-                Ok((a_cell, b_cell, c_cell))
+                self.assign_rows(&mut region, a_cell, b_cell, n)
             },
         )
     }
-    pub fn assign_row(
+
+    /// Like [`FibonacciChip::assign`], but bulk-assigns a precomputed [`trace`] of at
+    /// least `rows_for(n) + 2` elements instead of computing it inside the region.
+    pub fn assign_trace(
         &self,
         mut layouter: impl Layouter<F>,
-        prev_b: &AssignedCell<F, F>,
-        prev_c: &AssignedCell<F, F>,
+        trace: Value<&[F]>,
+        n: usize,
     ) -> Result<AssignedCell<F, F>, Error> {
+        let rows = rows_for(n);
+
         layouter.assign_region(
-            || "next row",
+            || "fibonacci",
             |mut region| {
-                self.config.selector.enable(&mut region, 0)?;
-
-                prev_b.copy_advice(|| "a0", &mut region, self.config.advice[0], 0)?;
-
-                prev_c.copy_advice(|| "a1", &mut region, self.config.advice[1], 0)?;
-
-                let c_cell = region.assign_advice(
-                    || "a0 + a1",
-                    self.config.advice[2],
-                    0,
-                    || prev_b.value().copied() + prev_c.value(),
-                )?;
-
-                Ok(c_cell)
+                let at = |i: usize| trace.map(|trace| trace[i]);
+
+                let a_cell = region.assign_advice(|| "a0", self.config.advice[0], 0, || at(0))?;
+                let b_cell = region.assign_advice(|| "a1", self.config.advice[1], 0, || at(1))?;
+
+                let mut c_cell = None;
+                for row in 0..rows {
+                    self.config.selector.enable(&mut region, row)?;
+                    if row + 1 < rows {
+                        self.config.q_chain.enable(&mut region, row)?;
+                    }
+
+                    if row > 0 {
+                        region.assign_advice(|| "a", self.config.advice[0], row, || at(row))?;
+                        region.assign_advice(|| "b", self.config.advice[1], row, || at(row + 1))?;
+                    }
+                    c_cell = Some(region.assign_advice(
+                        || "a + b",
+                        self.config.advice[2],
+                        row,
+                        || at(row + 2),
+                    )?);
+                }
+
+                Ok(match n {
+                    0 => a_cell,
+                    1 => b_cell,
+                    _ => c_cell.expect("at least one row is assigned"),
+                })
             },
         )
     }
+
+    fn assign_rows(
+        &self,
+        region: &mut Region<'_, F>,
+        a_cell: AssignedCell<F, F>,
+        b_cell: AssignedCell<F, F>,
+        n: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let rows = rows_for(n);
+
+        let mut a = a_cell.value().copied();
+        let mut b = b_cell.value().copied();
+        let mut c_cell = None;
+        for row in 0..rows {
+            self.config.selector.enable(region, row)?;
+            if row + 1 < rows {
+                self.config.q_chain.enable(region, row)?;
+            }
+
+            if row > 0 {
+                region.assign_advice(|| "a", self.config.advice[0], row, || a)?;
+                region.assign_advice(|| "b", self.config.advice[1], row, || b)?;
+            }
+            let c = a + b;
+            c_cell = Some(region.assign_advice(|| "a + b", self.config.advice[2], row, || c)?);
+
+            a = b;
+            b = c;
+        }
+
+        Ok(match n {
+            0 => a_cell,
+            1 => b_cell,
+            _ => c_cell.expect("at least one row is assigned"),
+        })
+    }
+
     pub fn expose_public(
         &self,
         mut layouter: impl Layouter<F>,
@@ -142,12 +275,19 @@ impl<F: Field> FibonacciChip<F> {
     }
 }
 
+/// Computes F(n) from public initial values.
+///
+/// Instance layout: `[F(0), F(1), F(n)]`.
 #[derive(Default, Clone, Debug)]
-struct MyCircuit<F>(PhantomData<F>);
+pub struct MyCircuit<F> {
+    pub n: usize,
+    pub _marker: PhantomData<F>,
+}
 
 impl<F: Field> Circuit<F> for MyCircuit<F> {
     type Config = FibonacciConfig;
     type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
 
     fn without_witnesses(&self) -> Self {
         Self::default()
@@ -163,17 +303,56 @@ impl<F: Field> Circuit<F> for MyCircuit<F> {
         mut layouter: impl Layouter<F>,
     ) -> Result<(), Error> {
         let chip = FibonacciChip::<F>::construct(config);
-        let (_, b, c) = chip.assign_initial_values(layouter.namespace(|| "initial values"))?; // (F(0), F(1), F(2))
-        let mut prev_b = b;
-        let mut prev_c = c;
-        for _ in 3..10 {
-            let c_cell = chip.assign_row(layouter.namespace(|| "next row"), &prev_b, &prev_c)?; // F(i)
-
-            prev_b = prev_c;
-            prev_c = c_cell;
+        let out = chip.assign_from_instance(layouter.namespace(|| "fibonacci"), self.n)?;
+        chip.expose_public(layouter.namespace(|| "out"), &out, 2)?;
+
+        Ok(())
+    }
+}
+
+/// Computes F(n) from private initial values, exposing only the output.
+///
+/// Instance layout: `[F(n)]`.
+#[derive(Default, Clone, Debug)]
+pub struct FibonacciCircuit<F: Field> {
+    pub a: Value<F>,
+    pub b: Value<F>,
+    pub n: usize,
+}
+
+impl<F: Field> Circuit<F> for FibonacciCircuit<F> {
+    type Config = FibonacciConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            a: Value::unknown(),
+            b: Value::unknown(),
+            n: self.n,
         }
-        println!("prev_c: {:?}", prev_c);
-        chip.expose_public(layouter.namespace(|| "out"), &prev_c, 2)?;
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FibonacciChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = FibonacciChip::<F>::construct(config);
+        let trace = self
+            .a
+            .zip(self.b)
+            .map(|(a, b)| trace(a, b, rows_for(self.n) + 2));
+        let out = chip.assign_trace(
+            layouter.namespace(|| "fibonacci"),
+            trace.as_ref().map(|trace| &trace[..]),
+            self.n,
+        )?;
+        chip.expose_public(layouter.namespace(|| "out"), &out, 0)?;
 
         Ok(())
     }
@@ -183,9 +362,12 @@ impl<F: Field> Circuit<F> for MyCircuit<F> {
 mod tests {
     use std::marker::PhantomData;
 
-    use super::MyCircuit;
+    use super::{fibonacci, trace, FibonacciCircuit, MyCircuit, TRACE_CHUNK};
+    use crate::model::{check, strategy, Model};
+    use halo2_proofs::circuit::Value;
     use halo2_proofs::dev::MockProver;
     use pasta_curves::pallas;
+    use proptest::prelude::*;
 
     #[test]
     fn fibonacci_example_small() {
@@ -195,27 +377,144 @@ mod tests {
         let b = pallas::Base::from(1); // F[1]
         let out = pallas::Base::from(55); // F[9]
 
-        let circuit = MyCircuit(PhantomData);
+        let circuit = MyCircuit {
+            n: 9,
+            _marker: PhantomData,
+        };
 
         let public_input = vec![a, b, out];
 
         let prover = MockProver::run(k, &circuit, vec![public_input.clone()]).unwrap();
         prover.assert_satisfied();
+
+        let public_input = vec![a, b, out + b];
+        let prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
+        assert!(prover.verify().is_err());
     }
+
+    #[test]
+    fn fibonacci_example_lengths() {
+        let k = 5;
+
+        let a = pallas::Base::from(2);
+        let b = pallas::Base::from(7);
+        for n in 0..20 {
+            let out = fibonacci(a, b, n);
+
+            let circuit = MyCircuit {
+                n,
+                _marker: PhantomData,
+            };
+            let prover = MockProver::run(k, &circuit, vec![vec![a, b, out]]).unwrap();
+            prover.assert_satisfied();
+
+            let circuit = FibonacciCircuit {
+                a: Value::known(a),
+                b: Value::known(b),
+                n,
+            };
+            let prover = MockProver::run(k, &circuit, vec![vec![out]]).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn fibonacci_trace() {
+        let a = pallas::Base::from(2);
+        let b = pallas::Base::from(7);
+
+        let len = 2 * TRACE_CHUNK + 5;
+        let trace = trace(a, b, len);
+        assert_eq!(trace.len(), len);
+        for n in [
+            0,
+            1,
+            2,
+            TRACE_CHUNK - 1,
+            TRACE_CHUNK,
+            TRACE_CHUNK + 1,
+            len - 1,
+        ] {
+            assert_eq!(trace[n], fibonacci(a, b, n), "n = {}", n);
+        }
+    }
+
+    #[test]
+    fn fibonacci_example_medium() {
+        let k = 11;
+        let n = 2000;
+
+        let a = pallas::Base::from(1);
+        let b = pallas::Base::from(1);
+        let out = fibonacci(a, b, n);
+
+        let circuit = FibonacciCircuit {
+            a: Value::known(a),
+            b: Value::known(b),
+            n,
+        };
+        let prover = MockProver::run(k, &circuit, vec![vec![out]]).unwrap();
+        prover.assert_satisfied();
+    }
+
     #[test]
     #[ignore = "takes a while"]
     fn fibonacci_example_big() {
-        let k = 5;
+        let k = 20;
+        let n = 1_000_000;
 
         let a = pallas::Base::from(1); // F[0]
         let b = pallas::Base::from(1); // F[1]
-        let out = pallas::Base::from(55); // F[9]
+        let out = fibonacci(a, b, n);
 
-        let circuit = MyCircuit(PhantomData);
+        let circuit = MyCircuit {
+            n,
+            _marker: PhantomData,
+        };
 
         let public_input = vec![a, b, out];
 
         let prover = MockProver::run(k, &circuit, vec![public_input.clone()]).unwrap();
         prover.assert_satisfied();
     }
+
+    struct FibonacciModel;
+
+    impl Model<pallas::Base> for FibonacciModel {
+        /// `(a, b, n, claimed F(n))`, `None` claiming the right output.
+        type Input = (pallas::Base, pallas::Base, usize, Option<pallas::Base>);
+        type Circuit = FibonacciCircuit<pallas::Base>;
+
+        fn k(_: &Self::Input) -> u32 {
+            5
+        }
+
+        fn circuit(&(a, b, n, claim): &Self::Input) -> (Self::Circuit, Vec<Vec<pallas::Base>>) {
+            let out = claim.unwrap_or_else(|| fibonacci(a, b, n));
+            let circuit = FibonacciCircuit {
+                a: Value::known(a),
+                b: Value::known(b),
+                n,
+            };
+            (circuit, vec![vec![out]])
+        }
+
+        fn accepts(&(a, b, n, claim): &Self::Input) -> bool {
+            claim.is_none() || claim == Some(fibonacci(a, b, n))
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn fibonacci_model(
+            a in strategy::field::<pallas::Base>(),
+            b in strategy::field::<pallas::Base>(),
+            n in 0usize..20,
+            claim in strategy::claim::<pallas::Base>(),
+        ) {
+            check::<_, FibonacciModel>(&(a, b, n, claim)).map_err(TestCaseError::fail)?;
+        }
+    }
 }
@@ -0,0 +1,244 @@
+use halo2_proofs::{arithmetic::Field, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+// Table setup
+//
+// Single advice column: row i holds F(i) and the gate reads F(i), F(i+1) and F(i+2)
+// through rotations.
+//
+//        a    |  selector  |
+// ----------------------------
+//  0:   F(0)  |      1     |
+//  1:   F(1)  |      1     |
+//  ...
+//  n-2: F(n-2)|      1     |
+//  n-1: F(n-1)|      0     |
+//  n:   F(n)  |      0     |
+
+/// Rows used to compute F(n).
+pub fn rows_for(n: usize) -> usize {
+    (n + 1).max(2)
+}
+
+#[derive(Clone, Debug)]
+pub struct FibonacciConfig {
+    pub advice: Column<Advice>,
+    pub selector: Selector,
+    pub instance: Column<Instance>,
+}
+
+#[derive(Clone, Debug)]
+pub struct FibonacciChip<F: Field> {
+    config: FibonacciConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> FibonacciChip<F> {
+    pub fn construct(config: FibonacciConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> FibonacciConfig {
+        let advice = meta.advice_column();
+        let selector = meta.selector();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(advice);
+        meta.enable_equality(instance);
+
+        meta.create_gate("add", |meta| {
+            let selector = meta.query_selector(selector);
+            let a = meta.query_advice(advice, Rotation::cur());
+            let b = meta.query_advice(advice, Rotation::next());
+            let c = meta.query_advice(advice, Rotation(2));
+
+            vec![selector * (a + b - c)]
+        });
+
+        FibonacciConfig {
+            advice,
+            selector,
+            instance,
+        }
+    }
+
+    /// Computes F(n) from private initial values `a = F(0)` and `b = F(1)`.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Value<F>,
+        b: Value<F>,
+        n: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "fibonacci",
+            |mut region| {
+                let a_cell = region.assign_advice(|| "a0", self.config.advice, 0, || a)?;
+                let b_cell = region.assign_advice(|| "a1", self.config.advice, 1, || b)?;
+
+                let (mut a, mut b) = (a, b);
+                let mut c_cell = None;
+                for row in 2..=n {
+                    self.config.selector.enable(&mut region, row - 2)?;
+
+                    let c = a + b;
+                    c_cell =
+                        Some(region.assign_advice(|| "a + b", self.config.advice, row, || c)?);
+
+                    a = b;
+                    b = c;
+                }
+
+                Ok(match n {
+                    0 => a_cell,
+                    1 => b_cell,
+                    _ => c_cell.expect("at least one row is assigned"),
+                })
+            },
+        )
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+/// Computes F(n) from private initial values, exposing only the output.
+///
+/// Instance layout: `[F(n)]`.
+#[derive(Default, Clone, Debug)]
+pub struct FibonacciCircuit<F: Field> {
+    pub a: Value<F>,
+    pub b: Value<F>,
+    pub n: usize,
+}
+
+impl<F: Field> Circuit<F> for FibonacciCircuit<F> {
+    type Config = FibonacciConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            a: Value::unknown(),
+            b: Value::unknown(),
+            n: self.n,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FibonacciChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = FibonacciChip::<F>::construct(config);
+        let out = chip.assign(layouter.namespace(|| "fibonacci"), self.a, self.b, self.n)?;
+        chip.expose_public(layouter.namespace(|| "out"), &out, 0)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FibonacciCircuit;
+    use crate::fibonacci::example1::fibonacci;
+    use crate::model::{check, strategy, Model};
+    use halo2_proofs::circuit::Value;
+    use halo2_proofs::dev::MockProver;
+    use pasta_curves::pallas;
+    use proptest::prelude::*;
+
+    #[test]
+    fn fibonacci_single_column_small() {
+        let k = 5;
+
+        let a = pallas::Base::from(2);
+        let b = pallas::Base::from(7);
+        for n in 0..20 {
+            let out = fibonacci(a, b, n);
+
+            let circuit = FibonacciCircuit {
+                a: Value::known(a),
+                b: Value::known(b),
+                n,
+            };
+            let prover = MockProver::run(k, &circuit, vec![vec![out]]).unwrap();
+            prover.assert_satisfied();
+
+            let prover = MockProver::run(k, &circuit, vec![vec![out + a]]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    #[ignore = "takes a while"]
+    fn fibonacci_single_column_big() {
+        let k = 20;
+        let n = 1_000_000;
+
+        let a = pallas::Base::from(1);
+        let b = pallas::Base::from(1);
+        let out = fibonacci(a, b, n);
+
+        let circuit = FibonacciCircuit {
+            a: Value::known(a),
+            b: Value::known(b),
+            n,
+        };
+        let prover = MockProver::run(k, &circuit, vec![vec![out]]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    struct SingleColumnModel;
+
+    impl Model<pallas::Base> for SingleColumnModel {
+        /// `(a, b, n, claimed F(n))`, `None` claiming the right output.
+        type Input = (pallas::Base, pallas::Base, usize, Option<pallas::Base>);
+        type Circuit = FibonacciCircuit<pallas::Base>;
+
+        fn k(_: &Self::Input) -> u32 {
+            5
+        }
+
+        fn circuit(&(a, b, n, claim): &Self::Input) -> (Self::Circuit, Vec<Vec<pallas::Base>>) {
+            let out = claim.unwrap_or_else(|| fibonacci(a, b, n));
+            let circuit = FibonacciCircuit {
+                a: Value::known(a),
+                b: Value::known(b),
+                n,
+            };
+            (circuit, vec![vec![out]])
+        }
+
+        fn accepts(&(a, b, n, claim): &Self::Input) -> bool {
+            claim.is_none() || claim == Some(fibonacci(a, b, n))
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn fibonacci_single_column_model(
+            a in strategy::field::<pallas::Base>(),
+            b in strategy::field::<pallas::Base>(),
+            n in 0usize..20,
+            claim in strategy::claim::<pallas::Base>(),
+        ) {
+            check::<_, SingleColumnModel>(&(a, b, n, claim)).map_err(TestCaseError::fail)?;
+        }
+    }
+}
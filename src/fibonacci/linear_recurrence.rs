@@ -0,0 +1,672 @@
+use ff::{PrimeField, PrimeFieldBits};
+use halo2_proofs::{arithmetic::Field, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+use crate::range_check::table::{RangeCheckTable, TableRegistry};
+
+// Table setup
+//
+// Generalizes `example1::FibonacciChip` to x_n = c1*x_{n-1} + ... + ck*x_{n-k}. Row i
+// holds the window (x_i, ..., x_{i+k-1}) and the next element x_{i+k}; consecutive
+// rows are chained with rotations. Coefficients live in fixed columns, aligned with
+// the window column they multiply.
+//
+//  window[0] | ... | window[k-1] |   next   | coeff[0] | ... | coeff[k-1] | selector | q_chain |
+// -----------------------------------------------------------------------------------------------
+//    x_0     | ... |  x_{k-1}    |   x_k    |    ck    | ... |     c1     |    1     |    1    |
+//    x_1     | ... |  x_k        |  x_{k+1} |    ck    | ... |     c1     |    1     |    1    |
+//    ...
+//
+// In modular mode three more advice columns hold the quotient, the public modulus m
+// (copied from instance row 0 and carried down with q_chain) and m - 1 - next. The
+// quotient, next and m - 1 - next are looked up in a shared range table, so next is
+// the remainder of the sum modulo m.
+//
+// The initial values are bounded the same way by `order` more rows after the
+// recurrence, with q_bound in place of the selector:
+//
+//    next   |  m  |     diff      | q_bound |
+// ----------------------------------------------
+//    x_0    |  m  |  m - 1 - x_0  |    1    |
+//    ...
+//  x_{k-1}  |  m  | m-1-x_{k-1}   |    1    |
+
+/// Native reference: the n-th element of the recurrence over the field.
+pub fn linear_recurrence<F: Field>(coeffs: &[F], initial: &[F], n: usize) -> F {
+    assert_eq!(
+        coeffs.len(),
+        initial.len(),
+        "one initial value per coefficient"
+    );
+    let mut window = initial.to_vec();
+    for _ in 0..n {
+        let next = coeffs
+            .iter()
+            .zip(window.iter().rev())
+            .fold(F::ZERO, |acc, (c, x)| acc + *c * x);
+        window.remove(0);
+        window.push(next);
+    }
+    window[0]
+}
+
+/// Native reference: the n-th element of the recurrence reduced modulo `m` at every step.
+pub fn linear_recurrence_mod(coeffs: &[u64], initial: &[u64], m: u64, n: usize) -> u64 {
+    assert!(m > 0, "modulus must be positive");
+    assert_eq!(
+        coeffs.len(),
+        initial.len(),
+        "one initial value per coefficient"
+    );
+    let mut window = initial.to_vec();
+    for _ in 0..n {
+        let next = coeffs
+            .iter()
+            .zip(window.iter().rev())
+            .fold(0u128, |acc, (c, x)| acc + *c as u128 * *x as u128);
+        window.remove(0);
+        window.push((next % m as u128) as u64);
+    }
+    window[0]
+}
+
+/// A field element as a `u64`, if it is below `2^64`.
+fn to_u64<F: PrimeField>(value: &F) -> Option<u64> {
+    let repr = value.to_repr();
+    let (low, high) = repr.as_ref().split_at(8);
+    if high.iter().any(|byte| *byte != 0) {
+        return None;
+    }
+    Some(u64::from_le_bytes(low.try_into().unwrap()))
+}
+
+/// Rows used to compute x_n for a recurrence of the given order.
+pub fn rows_for(order: usize, n: usize) -> usize {
+    (n + 1).saturating_sub(order).max(1)
+}
+
+#[derive(Clone, Debug)]
+pub struct ModularConfig<F: PrimeFieldBits> {
+    pub quotient: Column<Advice>,
+    pub modulus: Column<Advice>,
+    pub diff: Column<Advice>,
+    /// Enables the bound rows of the initial values.
+    pub q_bound: Selector,
+    pub table: RangeCheckTable<F>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LinearRecurrenceConfig<F: PrimeFieldBits> {
+    pub window: Vec<Column<Advice>>,
+    pub next: Column<Advice>,
+    pub coeffs: Vec<Column<Fixed>>,
+    pub selector: Selector,
+    pub q_chain: Selector,
+    pub instance: Column<Instance>,
+    pub modular: Option<ModularConfig<F>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LinearRecurrenceChip<F: PrimeFieldBits> {
+    config: LinearRecurrenceConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeFieldBits> LinearRecurrenceChip<F> {
+    pub fn construct(config: LinearRecurrenceConfig<F>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Configures a recurrence of the given order over the field.
+    pub fn configure(meta: &mut ConstraintSystem<F>, order: usize) -> LinearRecurrenceConfig<F> {
+        Self::configure_inner(meta, order, None)
+    }
+
+    /// Configures a recurrence of the given order reduced modulo a public m, with the
+    /// remainder, quotient and `m - 1 - remainder` checked against the `num_bits`-bit
+    /// table from `registry`.
+    ///
+    /// Sound as long as `m <= 2^num_bits` and `c1 + ... + ck < 2^num_bits`, so the
+    /// honest quotient always fits the table. Initial values are checked to be below m.
+    pub fn configure_modular(
+        meta: &mut ConstraintSystem<F>,
+        order: usize,
+        registry: &mut TableRegistry<F>,
+        num_bits: usize,
+    ) -> LinearRecurrenceConfig<F> {
+        let table = registry.range_table(meta, num_bits);
+        let modular = ModularConfig {
+            quotient: meta.advice_column(),
+            modulus: meta.advice_column(),
+            diff: meta.advice_column(),
+            q_bound: meta.complex_selector(),
+            table,
+        };
+        meta.enable_equality(modular.modulus);
+
+        Self::configure_inner(meta, order, Some(modular))
+    }
+
+    fn configure_inner(
+        meta: &mut ConstraintSystem<F>,
+        order: usize,
+        modular: Option<ModularConfig<F>>,
+    ) -> LinearRecurrenceConfig<F> {
+        assert!(order > 0, "recurrence order must be at least one");
+
+        let window: Vec<_> = (0..order).map(|_| meta.advice_column()).collect();
+        let next = meta.advice_column();
+        let coeffs: Vec<_> = (0..order).map(|_| meta.fixed_column()).collect();
+        let selector = meta.complex_selector();
+        let q_chain = meta.selector();
+        let instance = meta.instance_column();
+
+        for column in window.iter() {
+            meta.enable_equality(*column);
+        }
+        meta.enable_equality(next);
+        meta.enable_equality(instance);
+
+        meta.create_gate("linear recurrence", |meta| {
+            let selector = meta.query_selector(selector);
+            let sum = window.iter().zip(coeffs.iter()).fold(
+                Expression::Constant(F::ZERO),
+                |acc, (x, c)| {
+                    acc + meta.query_fixed(*c, Rotation::cur())
+                        * meta.query_advice(*x, Rotation::cur())
+                },
+            );
+            let next = meta.query_advice(next, Rotation::cur());
+
+            match &modular {
+                None => vec![selector * (sum - next)],
+                Some(modular) => {
+                    let quotient = meta.query_advice(modular.quotient, Rotation::cur());
+                    let modulus = meta.query_advice(modular.modulus, Rotation::cur());
+                    let diff = meta.query_advice(modular.diff, Rotation::cur());
+
+                    vec![
+                        selector.clone() * (sum - quotient * modulus.clone() - next.clone()),
+                        selector * (modulus - Expression::Constant(F::ONE) - next - diff),
+                    ]
+                }
+            }
+        });
+
+        meta.create_gate("chain", |meta| {
+            let q_chain = meta.query_selector(q_chain);
+            let mut constraints: Vec<_> = window
+                .windows(2)
+                .map(|pair| {
+                    q_chain.clone()
+                        * (meta.query_advice(pair[0], Rotation::next())
+                            - meta.query_advice(pair[1], Rotation::cur()))
+                })
+                .collect();
+            constraints.push(
+                q_chain.clone()
+                    * (meta.query_advice(window[order - 1], Rotation::next())
+                        - meta.query_advice(next, Rotation::cur())),
+            );
+            if let Some(modular) = &modular {
+                constraints.push(
+                    q_chain
+                        * (meta.query_advice(modular.modulus, Rotation::next())
+                            - meta.query_advice(modular.modulus, Rotation::cur())),
+                );
+            }
+            constraints
+        });
+
+        if let Some(modular) = &modular {
+            meta.create_gate("initial bound", |meta| {
+                let q_bound = meta.query_selector(modular.q_bound);
+                let initial = meta.query_advice(next, Rotation::cur());
+                let modulus = meta.query_advice(modular.modulus, Rotation::cur());
+                let diff = meta.query_advice(modular.diff, Rotation::cur());
+
+                vec![q_bound * (modulus - Expression::Constant(F::ONE) - initial - diff)]
+            });
+
+            // The remainder and its bound are looked up on the bound rows too.
+            for (name, column, bounds) in [
+                ("recurrence quotient", modular.quotient, false),
+                ("recurrence remainder", next, true),
+                ("recurrence remainder bound", modular.diff, true),
+            ] {
+                meta.lookup(name, |meta| {
+                    let mut selector = meta.query_selector(selector);
+                    if bounds {
+                        selector = selector + meta.query_selector(modular.q_bound);
+                    }
+                    let value = meta.query_advice(column, Rotation::cur());
+
+                    vec![(selector * value, modular.table.value)]
+                });
+            }
+        }
+
+        LinearRecurrenceConfig {
+            window,
+            next,
+            coeffs,
+            selector,
+            q_chain,
+            instance,
+            modular,
+        }
+    }
+
+    /// Computes x_n from private initial values `x_0, ..., x_{k-1}`. In modular mode the
+    /// modulus is copied from instance row 0, the initial values are checked to be
+    /// below it and every step is reduced modulo it. A zero modulus is a synthesis
+    /// error.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        coeffs: &[F],
+        initial: &[Value<F>],
+        n: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let order = self.config.window.len();
+        assert_eq!(coeffs.len(), order, "one coefficient per window column");
+        assert_eq!(initial.len(), order, "one initial value per window column");
+
+        layouter.assign_region(
+            || "linear recurrence",
+            |mut region| {
+                let modulus = match &self.config.modular {
+                    Some(modular) => Some(region.assign_advice_from_instance(
+                        || "m",
+                        self.config.instance,
+                        0,
+                        modular.modulus,
+                        0,
+                    )?),
+                    None => None,
+                };
+                let modulus_cell = modulus;
+                let modulus = modulus_cell.as_ref().map(|cell| cell.value().copied());
+                if let Some(m) = modulus {
+                    let mut zero = false;
+                    m.map(|m| zero = m.is_zero_vartime());
+                    if zero {
+                        return Err(Error::Synthesis);
+                    }
+                }
+
+                let mut window = initial.to_vec();
+                let mut initial_cells = vec![];
+                let mut next_cell = None;
+                let rows = rows_for(order, n);
+                for row in 0..rows {
+                    self.config.selector.enable(&mut region, row)?;
+                    if row + 1 < rows {
+                        self.config.q_chain.enable(&mut region, row)?;
+                    }
+
+                    for (j, column) in self.config.coeffs.iter().enumerate() {
+                        let c = coeffs[order - 1 - j];
+                        region.assign_fixed(|| "coeff", *column, row, || Value::known(c))?;
+                    }
+                    for (column, x) in self.config.window.iter().zip(window.iter()) {
+                        let cell = region.assign_advice(|| "x", *column, row, || *x)?;
+                        if row == 0 {
+                            initial_cells.push(cell);
+                        }
+                    }
+
+                    let sum = coeffs
+                        .iter()
+                        .zip(window.iter().rev())
+                        .fold(Value::known(F::ZERO), |acc, (c, x)| {
+                            acc + Value::known(*c) * *x
+                        });
+                    let next = match (&self.config.modular, modulus) {
+                        (None, _) => sum,
+                        (Some(modular), Some(m)) => {
+                            let divided = sum.zip(m).map(|(sum, m)| {
+                                let (sum, m) = (to_u64(&sum)?, to_u64(&m)?);
+                                Some((F::from(sum / m), F::from(sum % m)))
+                            });
+                            // The witness is divided natively, which only covers sums
+                            // below 2^64.
+                            divided.error_if_known_and(Option::is_none)?;
+                            let divided = divided.map(|divided| divided.unwrap());
+                            let quotient = divided.map(|(q, _)| q);
+                            let remainder = divided.map(|(_, r)| r);
+
+                            if row > 0 {
+                                region.assign_advice(|| "m", modular.modulus, row, || m)?;
+                            }
+                            region.assign_advice(
+                                || "quotient",
+                                modular.quotient,
+                                row,
+                                || quotient,
+                            )?;
+                            region.assign_advice(
+                                || "m - 1 - next",
+                                modular.diff,
+                                row,
+                                || m - Value::known(F::ONE) - remainder,
+                            )?;
+                            remainder
+                        }
+                        (Some(_), None) => unreachable!("modulus is assigned in modular mode"),
+                    };
+                    next_cell =
+                        Some(region.assign_advice(|| "next", self.config.next, row, || next)?);
+
+                    window.remove(0);
+                    window.push(next);
+                }
+
+                if let (Some(modular), Some(m)) = (&self.config.modular, &modulus_cell) {
+                    for (j, initial) in initial_cells.iter().enumerate() {
+                        let row = rows + j;
+                        modular.q_bound.enable(&mut region, row)?;
+                        m.copy_advice(|| "m", &mut region, modular.modulus, row)?;
+                        initial.copy_advice(|| "x", &mut region, self.config.next, row)?;
+                        region.assign_advice(
+                            || "m - 1 - x",
+                            modular.diff,
+                            row,
+                            || m.value().copied() - Value::known(F::ONE) - initial.value().copied(),
+                        )?;
+                    }
+                }
+
+                Ok(if n < order {
+                    initial_cells.swap_remove(n)
+                } else {
+                    next_cell.expect("at least one row is assigned")
+                })
+            },
+        )
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+/// Order the circuits are configured for when no params are given: Fibonacci.
+pub const DEFAULT_ORDER: usize = 2;
+
+/// Computes x_n over the field from private initial values; the order is the
+/// circuit's `Params`.
+///
+/// Instance layout: `[x_n]`.
+#[derive(Default, Clone, Debug)]
+pub struct LinearRecurrenceCircuit<F: Field> {
+    pub coeffs: Vec<F>,
+    pub initial: Vec<Value<F>>,
+    pub n: usize,
+}
+
+impl<F: PrimeFieldBits> Circuit<F> for LinearRecurrenceCircuit<F> {
+    type Config = LinearRecurrenceConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = usize;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            coeffs: self.coeffs.clone(),
+            initial: vec![Value::unknown(); self.initial.len()],
+            n: self.n,
+        }
+    }
+
+    fn params(&self) -> Self::Params {
+        self.coeffs.len()
+    }
+
+    fn configure_with_params(meta: &mut ConstraintSystem<F>, order: usize) -> Self::Config {
+        LinearRecurrenceChip::configure(meta, order)
+    }
+
+    /// Without params, configures for order [`DEFAULT_ORDER`].
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        Self::configure_with_params(meta, DEFAULT_ORDER)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = LinearRecurrenceChip::construct(config);
+        let out = chip.assign(
+            layouter.namespace(|| "recurrence"),
+            &self.coeffs,
+            &self.initial,
+            self.n,
+        )?;
+        chip.expose_public(layouter.namespace(|| "out"), &out, 0)
+    }
+}
+
+/// Parameters of [`ModularRecurrenceCircuit`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ModularParams {
+    pub order: usize,
+    pub num_bits: usize,
+}
+
+impl Default for ModularParams {
+    fn default() -> Self {
+        Self {
+            order: DEFAULT_ORDER,
+            num_bits: 8,
+        }
+    }
+}
+
+/// Computes x_n modulo a public m from private initial values.
+///
+/// Instance layout: `[m, x_n mod m]`.
+#[derive(Default, Clone, Debug)]
+pub struct ModularRecurrenceCircuit<F: Field> {
+    pub coeffs: Vec<F>,
+    pub initial: Vec<Value<F>>,
+    pub n: usize,
+    pub num_bits: usize,
+}
+
+impl<F: PrimeFieldBits> Circuit<F> for ModularRecurrenceCircuit<F> {
+    type Config = (LinearRecurrenceConfig<F>, TableRegistry<F>);
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ModularParams;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            coeffs: self.coeffs.clone(),
+            initial: vec![Value::unknown(); self.initial.len()],
+            n: self.n,
+            num_bits: self.num_bits,
+        }
+    }
+
+    fn params(&self) -> Self::Params {
+        ModularParams {
+            order: self.coeffs.len(),
+            num_bits: self.num_bits,
+        }
+    }
+
+    fn configure_with_params(
+        meta: &mut ConstraintSystem<F>,
+        params: ModularParams,
+    ) -> Self::Config {
+        let mut registry = TableRegistry::new();
+        let config = LinearRecurrenceChip::configure_modular(
+            meta,
+            params.order,
+            &mut registry,
+            params.num_bits,
+        );
+        (config, registry)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        Self::configure_with_params(meta, ModularParams::default())
+    }
+
+    fn synthesize(
+        &self,
+        (config, registry): Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        registry.load(&mut layouter)?;
+
+        let chip = LinearRecurrenceChip::construct(config);
+        let out = chip.assign(
+            layouter.namespace(|| "recurrence"),
+            &self.coeffs,
+            &self.initial,
+            self.n,
+        )?;
+        chip.expose_public(layouter.namespace(|| "out"), &out, 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fibonacci::example1::fibonacci;
+    use halo2_proofs::dev::MockProver;
+    use pasta_curves::pallas;
+
+    fn known(values: &[u64]) -> Vec<Value<pallas::Base>> {
+        values
+            .iter()
+            .map(|v| Value::known(pallas::Base::from(*v)))
+            .collect()
+    }
+
+    fn field(values: &[u64]) -> Vec<pallas::Base> {
+        values.iter().map(|v| pallas::Base::from(*v)).collect()
+    }
+
+    #[test]
+    fn linear_recurrence_matches_fibonacci() {
+        let k = 6;
+
+        for n in 0..30 {
+            let out = fibonacci(pallas::Base::from(1), pallas::Base::from(1), n);
+            assert_eq!(linear_recurrence(&field(&[1, 1]), &field(&[1, 1]), n), out);
+
+            let circuit = LinearRecurrenceCircuit {
+                coeffs: field(&[1, 1]),
+                initial: known(&[1, 1]),
+                n,
+            };
+            let prover = MockProver::run(k, &circuit, vec![vec![out]]).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn linear_recurrence_higher_order() {
+        let k = 6;
+
+        // Tribonacci, and x_n = 2 x_{n-1} + 5 x_{n-3}.
+        for (coeffs, initial) in [([1u64, 1, 1], [0u64, 0, 1]), ([2, 0, 5], [3, 1, 4])] {
+            for n in [0, 2, 3, 10, 40] {
+                let out = linear_recurrence(&field(&coeffs), &field(&initial), n);
+
+                let circuit = LinearRecurrenceCircuit {
+                    coeffs: field(&coeffs),
+                    initial: known(&initial),
+                    n,
+                };
+                let prover = MockProver::run(k, &circuit, vec![vec![out]]).unwrap();
+                prover.assert_satisfied();
+
+                let prover =
+                    MockProver::run(k, &circuit, vec![vec![out + pallas::Base::ONE]]).unwrap();
+                assert!(prover.verify().is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn linear_recurrence_modular() {
+        let k = 9;
+        let m = 97;
+
+        for (coeffs, initial) in [([1u64, 1], [1u64, 1]), ([3, 7], [96, 50])] {
+            for n in [0, 1, 2, 10, 50] {
+                let out = linear_recurrence_mod(&coeffs, &initial, m, n);
+
+                let circuit = ModularRecurrenceCircuit {
+                    coeffs: field(&coeffs),
+                    initial: known(&initial),
+                    n,
+                    num_bits: 8,
+                };
+                let public_inputs = field(&[m, out]);
+                let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+                prover.assert_satisfied();
+
+                // The unreduced value is rejected.
+                let prover = MockProver::run(k, &circuit, vec![field(&[m, out + m])]).unwrap();
+                assert!(prover.verify().is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn linear_recurrence_modular_rejects_unreduced_initial() {
+        let k = 9;
+        let m = 97;
+
+        // Starting from 1 + m instead of 1 gives the same reduced sequence.
+        for n in [0, 1, 10] {
+            let out = linear_recurrence_mod(&[1, 1], &[1, 1], m, n);
+            let circuit = ModularRecurrenceCircuit {
+                coeffs: field(&[1, 1]),
+                initial: known(&[1 + m, 1]),
+                n,
+                num_bits: 8,
+            };
+            let prover = MockProver::run(k, &circuit, vec![field(&[m, out])]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn linear_recurrence_modular_zero_modulus() {
+        let circuit = ModularRecurrenceCircuit {
+            coeffs: field(&[1, 1]),
+            initial: known(&[0, 0]),
+            n: 5,
+            num_bits: 8,
+        };
+        assert!(MockProver::run(9, &circuit, vec![field(&[0, 0])]).is_err());
+    }
+
+    #[test]
+    fn linear_recurrence_modular_wide_sum() {
+        assert_eq!(to_u64(&pallas::Base::from(u64::MAX)), Some(u64::MAX));
+        assert_eq!(to_u64(&-pallas::Base::ONE), None);
+
+        // Initial values of -1 give a sum the native division cannot reduce.
+        let circuit = ModularRecurrenceCircuit {
+            coeffs: field(&[1, 1]),
+            initial: vec![Value::known(-pallas::Base::ONE); 2],
+            n: 5,
+            num_bits: 8,
+        };
+        assert!(MockProver::run(9, &circuit, vec![field(&[97, 0])]).is_err());
+    }
+}
@@ -1,3 +1,4 @@
-mod example1;
-// mod example2;
+pub mod example1;
+pub mod example2;
 // mod example3;
+pub mod linear_recurrence;
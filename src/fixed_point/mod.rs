@@ -0,0 +1,705 @@
+//! Signed fixed-point arithmetic.
+//!
+//! A number `x` is held as the integer `X = x * 2^scale_bits`, embedded in the field
+//! with negative values wrapping below the modulus. Values have `2 * scale_bits`
+//! bits in two's complement, so with `scale_bits = 16` they run from `-2^15` to
+//! `2^15 - 2^-16`.
+//!
+//! [`FixedPointChip`] range checks every value it produces through two
+//! `scale_bits`-bit limbs of a [`RangeCheckChip`]: a result out of range, or an
+//! input of [`FixedPointChip::load_private`] out of range, fails verification rather
+//! than wrapping around. Products and quotients are rescaled with a witnessed
+//! remainder that is range checked too, which makes the rounding that of
+//! [`i128::div_euclid`]: towards negative infinity for products, and for quotients by
+//! a positive divisor.
+
+use ff::{PrimeField, PrimeFieldBits};
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+
+use crate::range_check::table::TableRegistry;
+use crate::range_check::{RangeCheckChip, RangeCheckConfig};
+
+// Table setup
+//
+// Every op is one row, with its results then split into limbs:
+//
+//    a   |   b   |   c    |   r   | sign |  abs  |  diff  | selector   |
+// ---------------------------------------------------------------------------
+//    a   |   b   |  a + b |       |      |       |        |   q_add    |
+//    a   |   b   |  a - b |       |      |       |        |   q_sub    |
+//    a   |   b   |   c    |   r   |      |       |        |   q_mul    |  a * b = c * 2^s + r
+//    a   |   b   |   c    |   r   |  ±1  |  |b|  | |b|-r-1|   q_div    |  a * 2^s = c * b + r
+//    v   |  lo   |   hi   |       |      |       |        |  q_signed  |  v + 2^(2s-1) = lo + hi * 2^s
+//    v   |  lo   |   hi   |       |      |       |        | q_unsigned |  v = lo + hi * 2^s
+//
+// `lo` and `hi`, and the remainder of a product, are copied into the range check
+// chip.
+
+/// Native reference: the fixed-point value nearest to `x`.
+pub fn from_f64(x: f64, scale_bits: usize) -> i128 {
+    (x * (1u64 << scale_bits) as f64).round() as i128
+}
+
+/// Native reference: the number held by the fixed-point value `x`.
+pub fn to_f64(x: i128, scale_bits: usize) -> f64 {
+    x as f64 / (1u64 << scale_bits) as f64
+}
+
+/// Native reference: whether `x` is a `2 * scale_bits`-bit signed value.
+pub fn fits(x: i128, scale_bits: usize) -> bool {
+    let half = 1i128 << (2 * scale_bits - 1);
+    (-half..half).contains(&x)
+}
+
+/// Native reference: `a + b`, if it fits.
+pub fn add(a: i128, b: i128, scale_bits: usize) -> Option<i128> {
+    Some(a + b).filter(|c| fits(*c, scale_bits))
+}
+
+/// Native reference: `a - b`, if it fits.
+pub fn sub(a: i128, b: i128, scale_bits: usize) -> Option<i128> {
+    Some(a - b).filter(|c| fits(*c, scale_bits))
+}
+
+/// Native reference: `a * b` rounded down, if it fits.
+pub fn mul(a: i128, b: i128, scale_bits: usize) -> Option<i128> {
+    Some((a * b).div_euclid(1 << scale_bits)).filter(|c| fits(*c, scale_bits))
+}
+
+/// Native reference: `a / b` rounded as by `div_euclid`, if `b` is not zero and the
+/// quotient fits.
+pub fn div(a: i128, b: i128, scale_bits: usize) -> Option<i128> {
+    (a << scale_bits)
+        .checked_div_euclid(b)
+        .filter(|c| fits(*c, scale_bits))
+}
+
+/// `x` in the field, negative values wrapping below the modulus.
+pub fn to_field<F: PrimeField>(x: i128) -> F {
+    let magnitude = F::from_u128(x.unsigned_abs());
+    if x < 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+/// A fixed-point value, range checked to `2 * scale_bits` signed bits.
+#[derive(Clone, Debug)]
+pub struct Fixed<F: PrimeFieldBits> {
+    pub value: Value<i128>,
+    pub cell: AssignedCell<F, F>,
+}
+
+#[derive(Clone, Debug)]
+pub struct FixedPointConfig<F: PrimeFieldBits> {
+    pub a: Column<Advice>,
+    pub b: Column<Advice>,
+    pub c: Column<Advice>,
+    pub r: Column<Advice>,
+    pub sign: Column<Advice>,
+    pub abs: Column<Advice>,
+    pub diff: Column<Advice>,
+    pub q_add: Selector,
+    pub q_sub: Selector,
+    pub q_mul: Selector,
+    pub q_div: Selector,
+    pub q_signed: Selector,
+    pub q_unsigned: Selector,
+    pub range: RangeCheckConfig<F>,
+    pub instance: Column<Instance>,
+}
+
+/// Fixed-point add, sub, mul and div over a `scale_bits`-bit range table.
+#[derive(Clone, Debug)]
+pub struct FixedPointChip<F: PrimeFieldBits> {
+    config: FixedPointConfig<F>,
+    range: RangeCheckChip<F>,
+}
+
+impl<F: PrimeFieldBits> FixedPointChip<F> {
+    pub fn construct(config: FixedPointConfig<F>) -> Self {
+        let range = RangeCheckChip::construct(config.range.clone());
+        Self { config, range }
+    }
+
+    /// Configures the chip with its own `scale_bits`-bit table.
+    pub fn configure(meta: &mut ConstraintSystem<F>, scale_bits: usize) -> FixedPointConfig<F> {
+        let advice = meta.advice_column();
+        let range = RangeCheckChip::configure(meta, advice, scale_bits);
+        Self::configure_with_range(meta, range)
+    }
+
+    /// Configures the chip against the `scale_bits`-bit table in `registry`, which
+    /// must then be loaded through [`TableRegistry::load`] rather than
+    /// [`FixedPointChip::load_table`].
+    pub fn configure_with_registry(
+        meta: &mut ConstraintSystem<F>,
+        registry: &mut TableRegistry<F>,
+        scale_bits: usize,
+    ) -> FixedPointConfig<F> {
+        let advice = meta.advice_column();
+        let range = RangeCheckChip::configure_with_registry(meta, advice, registry, scale_bits);
+        Self::configure_with_range(meta, range)
+    }
+
+    fn configure_with_range(
+        meta: &mut ConstraintSystem<F>,
+        range: RangeCheckConfig<F>,
+    ) -> FixedPointConfig<F> {
+        let scale_bits = range.num_bits();
+        // Products of two values, `4 * scale_bits` bits with their sign, must not
+        // wrap around the modulus.
+        assert!(
+            4 * scale_bits + 2 < F::CAPACITY as usize,
+            "scale too large for the field"
+        );
+        let scale = Expression::Constant(F::from_u128(1 << scale_bits));
+        let half = Expression::Constant(F::from_u128(1 << (2 * scale_bits - 1)));
+        let one = Expression::Constant(F::ONE);
+
+        let [a, b, c, r, sign, abs, diff] = [(); 7].map(|_| meta.advice_column());
+        let [q_add, q_sub, q_mul, q_div, q_signed, q_unsigned] = [(); 6].map(|_| meta.selector());
+        let instance = meta.instance_column();
+
+        for column in [a, b, c, r, diff] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+
+        meta.create_gate("add", |meta| {
+            let q_add = meta.query_selector(q_add);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let c = meta.query_advice(c, Rotation::cur());
+
+            Constraints::with_selector(q_add, Some(c - a - b))
+        });
+
+        meta.create_gate("sub", |meta| {
+            let q_sub = meta.query_selector(q_sub);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let c = meta.query_advice(c, Rotation::cur());
+
+            Constraints::with_selector(q_sub, Some(c - a + b))
+        });
+
+        meta.create_gate("mul", |meta| {
+            let q_mul = meta.query_selector(q_mul);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let c = meta.query_advice(c, Rotation::cur());
+            let r = meta.query_advice(r, Rotation::cur());
+
+            Constraints::with_selector(q_mul, Some(a * b - c * scale.clone() - r))
+        });
+
+        meta.create_gate("div", |meta| {
+            let q_div = meta.query_selector(q_div);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let c = meta.query_advice(c, Rotation::cur());
+            let r = meta.query_advice(r, Rotation::cur());
+            let sign = meta.query_advice(sign, Rotation::cur());
+            let abs = meta.query_advice(abs, Rotation::cur());
+            let diff = meta.query_advice(diff, Rotation::cur());
+
+            Constraints::with_selector(
+                q_div,
+                [
+                    (
+                        "a * 2^s = c * b + r",
+                        a * scale.clone() - c * b.clone() - r.clone(),
+                    ),
+                    ("sign is ±1", sign.clone() * sign.clone() - one.clone()),
+                    ("abs = sign * b", abs.clone() - sign * b),
+                    ("diff = abs - r - 1", diff - (abs - r - one.clone())),
+                ],
+            )
+        });
+
+        meta.create_gate("signed limbs", |meta| {
+            let q_signed = meta.query_selector(q_signed);
+            let value = meta.query_advice(a, Rotation::cur());
+            let lo = meta.query_advice(b, Rotation::cur());
+            let hi = meta.query_advice(c, Rotation::cur());
+
+            Constraints::with_selector(
+                q_signed,
+                Some(value + half.clone() - lo - hi * scale.clone()),
+            )
+        });
+
+        meta.create_gate("unsigned limbs", |meta| {
+            let q_unsigned = meta.query_selector(q_unsigned);
+            let value = meta.query_advice(a, Rotation::cur());
+            let lo = meta.query_advice(b, Rotation::cur());
+            let hi = meta.query_advice(c, Rotation::cur());
+
+            Constraints::with_selector(q_unsigned, Some(value - lo - hi * scale.clone()))
+        });
+
+        FixedPointConfig {
+            a,
+            b,
+            c,
+            r,
+            sign,
+            abs,
+            diff,
+            q_add,
+            q_sub,
+            q_mul,
+            q_div,
+            q_signed,
+            q_unsigned,
+            range,
+            instance,
+        }
+    }
+
+    pub fn config(&self) -> &FixedPointConfig<F> {
+        &self.config
+    }
+
+    fn scale_bits(&self) -> usize {
+        self.config.range.num_bits()
+    }
+
+    /// Loads the range table. Must be called once per circuit, and not at all if the
+    /// chip was configured from a [`TableRegistry`].
+    pub fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.range.load_table(layouter)
+    }
+
+    /// Witnesses a fixed-point value and range checks it.
+    pub fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<i128>,
+    ) -> Result<Fixed<F>, Error> {
+        let cell = layouter.assign_region(
+            || "load private",
+            |mut region| region.assign_advice(|| "value", self.config.a, 0, || value.map(to_field)),
+        )?;
+        self.check(layouter.namespace(|| "check"), &cell, value, true)?;
+        Ok(Fixed { value, cell })
+    }
+
+    /// Constrains `value` to `2 * scale_bits` bits, signed or not, through two limbs
+    /// in the range check chip.
+    fn check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        value: Value<i128>,
+        signed: bool,
+    ) -> Result<(), Error> {
+        let config = &self.config;
+        let scale_bits = self.scale_bits();
+        let offset = if signed { 1 << (2 * scale_bits - 1) } else { 0 };
+        // Out of range values get limbs that are out of range too, or that do not add
+        // up to the value.
+        let shifted = value.map(|value| value + offset);
+        let lo = shifted.map(|shifted| shifted.rem_euclid(1 << scale_bits));
+        let hi = shifted.map(|shifted| shifted.div_euclid(1 << scale_bits));
+
+        let (lo, hi) = layouter.assign_region(
+            || "limbs",
+            |mut region| {
+                let selector = if signed {
+                    config.q_signed
+                } else {
+                    config.q_unsigned
+                };
+                selector.enable(&mut region, 0)?;
+                cell.copy_advice(|| "value", &mut region, config.a, 0)?;
+                let lo = region.assign_advice(|| "lo", config.b, 0, || lo.map(to_field))?;
+                let hi = region.assign_advice(|| "hi", config.c, 0, || hi.map(to_field))?;
+                Ok((lo, hi))
+            },
+        )?;
+        self.range.copy_check(layouter.namespace(|| "lo"), &lo)?;
+        self.range.copy_check(layouter.namespace(|| "hi"), &hi)?;
+        Ok(())
+    }
+
+    /// Lays out the row of a binary op and returns its result cell, and the cells of
+    /// its remainder and of the divisor bound if any.
+    fn assign_op(
+        &self,
+        mut layouter: impl Layouter<F>,
+        selector: Selector,
+        a: &Fixed<F>,
+        b: &Fixed<F>,
+        row: OpRow,
+    ) -> Result<[Option<AssignedCell<F, F>>; 3], Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "op",
+            |mut region| {
+                selector.enable(&mut region, 0)?;
+                a.cell.copy_advice(|| "a", &mut region, config.a, 0)?;
+                b.cell.copy_advice(|| "b", &mut region, config.b, 0)?;
+
+                let mut assign =
+                    |name: &'static str, column: Column<Advice>, value: Option<Value<i128>>| {
+                        value
+                            .map(|value| {
+                                region.assign_advice(|| name, column, 0, || value.map(to_field))
+                            })
+                            .transpose()
+                    };
+                let c = assign("c", config.c, Some(row.c))?;
+                let r = assign("r", config.r, row.r)?;
+                assign("sign", config.sign, row.sign)?;
+                assign("abs", config.abs, row.abs)?;
+                let diff = assign("diff", config.diff, row.diff)?;
+                Ok([c, r, diff])
+            },
+        )
+    }
+
+    /// `a + b`. Fails verification on overflow.
+    pub fn add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &Fixed<F>,
+        b: &Fixed<F>,
+    ) -> Result<Fixed<F>, Error> {
+        let value = a.value + b.value;
+        let [c, ..] = self.assign_op(
+            layouter.namespace(|| "add"),
+            self.config.q_add,
+            a,
+            b,
+            OpRow::result(value),
+        )?;
+        self.result(layouter, c.unwrap(), value)
+    }
+
+    /// `a - b`. Fails verification on overflow.
+    pub fn sub(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &Fixed<F>,
+        b: &Fixed<F>,
+    ) -> Result<Fixed<F>, Error> {
+        let value = a.value - b.value;
+        let [c, ..] = self.assign_op(
+            layouter.namespace(|| "sub"),
+            self.config.q_sub,
+            a,
+            b,
+            OpRow::result(value),
+        )?;
+        self.result(layouter, c.unwrap(), value)
+    }
+
+    /// `a * b`, rounded down to the scale. Fails verification on overflow.
+    pub fn mul(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &Fixed<F>,
+        b: &Fixed<F>,
+    ) -> Result<Fixed<F>, Error> {
+        let scale = 1 << self.scale_bits();
+        let product = a.value * b.value;
+        let value = product.map(|product| product.div_euclid(scale));
+        let row = OpRow {
+            r: Some(product.map(|product| product.rem_euclid(scale))),
+            ..OpRow::result(value)
+        };
+        let [c, r, _] =
+            self.assign_op(layouter.namespace(|| "mul"), self.config.q_mul, a, b, row)?;
+
+        // The remainder is below the scale, so the product is rounded down.
+        self.range
+            .copy_check(layouter.namespace(|| "remainder"), &r.unwrap())?;
+        self.result(layouter, c.unwrap(), value)
+    }
+
+    /// `a / b`, rounded as by [`i128::div_euclid`]. Fails verification if `b` is zero
+    /// or on overflow.
+    pub fn div(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &Fixed<F>,
+        b: &Fixed<F>,
+    ) -> Result<Fixed<F>, Error> {
+        let numerator = a.value.map(|a| a << self.scale_bits());
+        let quotient = numerator.zip(b.value).map(|(numerator, b)| {
+            // Nothing satisfies the constraints for a zero divisor, so any witness
+            // fails.
+            numerator
+                .checked_div_euclid(b)
+                .zip(numerator.checked_rem_euclid(b))
+                .unwrap_or((0, 0))
+        });
+        let (value, remainder) = quotient.unzip();
+        let sign = b.value.map(|b| if b < 0 { -1 } else { 1 });
+        let abs = b.value.map(i128::abs);
+        let bound = abs - remainder - Value::known(1);
+        let row = OpRow {
+            r: Some(remainder),
+            sign: Some(sign),
+            abs: Some(abs),
+            diff: Some(bound),
+            ..OpRow::result(value)
+        };
+        let [c, r, diff] =
+            self.assign_op(layouter.namespace(|| "div"), self.config.q_div, a, b, row)?;
+
+        // `0 <= r < |b|`, which also rules out a zero divisor.
+        let (r, diff) = (r.unwrap(), diff.unwrap());
+        self.check(layouter.namespace(|| "remainder"), &r, remainder, false)?;
+        self.check(layouter.namespace(|| "bound"), &diff, bound, false)?;
+        self.result(layouter, c.unwrap(), value)
+    }
+
+    /// Range checks the result of an op.
+    fn result(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: AssignedCell<F, F>,
+        value: Value<i128>,
+    ) -> Result<Fixed<F>, Error> {
+        self.check(layouter.namespace(|| "result"), &cell, value, true)?;
+        Ok(Fixed { value, cell })
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+/// Witnesses of an op row besides its operands.
+struct OpRow {
+    c: Value<i128>,
+    r: Option<Value<i128>>,
+    sign: Option<Value<i128>>,
+    abs: Option<Value<i128>>,
+    diff: Option<Value<i128>>,
+}
+
+impl OpRow {
+    fn result(c: Value<i128>) -> Self {
+        Self {
+            c,
+            r: None,
+            sign: None,
+            abs: None,
+            diff: None,
+        }
+    }
+}
+
+/// Applies every op to two private fixed-point values, with `scale_bits` chosen at
+/// runtime.
+///
+/// Instance layout: `[a + b, a - b, a * b, a / b]`.
+#[derive(Clone, Debug)]
+pub struct FixedPointCircuit {
+    pub a: Value<i128>,
+    pub b: Value<i128>,
+    pub scale_bits: usize,
+}
+
+impl FixedPointCircuit {
+    pub fn new(a: i128, b: i128, scale_bits: usize) -> Self {
+        Self {
+            a: Value::known(a),
+            b: Value::known(b),
+            scale_bits,
+        }
+    }
+
+    /// The public inputs matching `a` and `b`, if every op fits.
+    pub fn instances<F: PrimeField>(a: i128, b: i128, scale_bits: usize) -> Option<Vec<Vec<F>>> {
+        let results = [
+            add(a, b, scale_bits)?,
+            sub(a, b, scale_bits)?,
+            mul(a, b, scale_bits)?,
+            div(a, b, scale_bits)?,
+        ];
+        Some(vec![results.iter().map(|x| to_field(*x)).collect()])
+    }
+}
+
+impl<F: PrimeFieldBits> Circuit<F> for FixedPointCircuit {
+    type Config = FixedPointConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = usize;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            a: Value::unknown(),
+            b: Value::unknown(),
+            scale_bits: self.scale_bits,
+        }
+    }
+
+    fn params(&self) -> Self::Params {
+        self.scale_bits
+    }
+
+    fn configure_with_params(meta: &mut ConstraintSystem<F>, scale_bits: usize) -> Self::Config {
+        FixedPointChip::configure(meta, scale_bits)
+    }
+
+    fn configure(_meta: &mut ConstraintSystem<F>) -> Self::Config {
+        unreachable!("FixedPointCircuit is configured through configure_with_params")
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = FixedPointChip::construct(config);
+        chip.load_table(&mut layouter)?;
+
+        let a = chip.load_private(layouter.namespace(|| "a"), self.a)?;
+        let b = chip.load_private(layouter.namespace(|| "b"), self.b)?;
+        let results = [
+            chip.add(layouter.namespace(|| "a + b"), &a, &b)?,
+            chip.sub(layouter.namespace(|| "a - b"), &a, &b)?,
+            chip.mul(layouter.namespace(|| "a * b"), &a, &b)?,
+            chip.div(layouter.namespace(|| "a / b"), &a, &b)?,
+        ];
+
+        for (row, result) in results.iter().enumerate() {
+            chip.expose_public(layouter.namespace(|| "result"), &result.cell, row)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::dev::MockProver;
+    use halo2curves::pasta::Fp;
+
+    /// Q8.8: values from -128 to 128 - 2^-8.
+    const SCALE_BITS: usize = 8;
+    /// The 8-bit table takes 256 rows.
+    const K: u32 = 9;
+
+    fn fixed(x: f64) -> i128 {
+        from_f64(x, SCALE_BITS)
+    }
+
+    #[test]
+    fn fixed_point_native() {
+        assert_eq!(to_f64(fixed(1.5), SCALE_BITS), 1.5);
+        assert_eq!(
+            mul(fixed(1.5), fixed(-2.25), SCALE_BITS),
+            Some(fixed(-3.375))
+        );
+        // Rounded down, also below zero.
+        assert_eq!(mul(1, 1, SCALE_BITS), Some(0));
+        assert_eq!(mul(-1, 1, SCALE_BITS), Some(-1));
+        assert_eq!(div(fixed(1.0), fixed(3.0), SCALE_BITS), Some(85));
+        assert_eq!(div(fixed(-1.0), fixed(3.0), SCALE_BITS), Some(-86));
+        assert_eq!(div(fixed(1.0), fixed(-3.0), SCALE_BITS), Some(-85));
+        assert_eq!(div(fixed(1.0), 0, SCALE_BITS), None);
+        assert_eq!(add(fixed(100.0), fixed(28.0), SCALE_BITS), None);
+        assert_eq!(mul(fixed(16.0), fixed(8.0), SCALE_BITS), None);
+    }
+
+    #[test]
+    fn fixed_point_chip() {
+        let pairs = [
+            (1.5, -2.25),
+            (-1.0, 3.0),
+            (1.0, -3.0),
+            (0.0, 1.0),
+            (-0.00390625, 0.00390625),
+            (126.0, 1.0),
+            // The product is the smallest value that fits.
+            (-64.0, 2.0),
+        ];
+        for (a, b) in pairs {
+            let (a, b) = (fixed(a), fixed(b));
+            let circuit = FixedPointCircuit::new(a, b, SCALE_BITS);
+            let instances = FixedPointCircuit::instances::<Fp>(a, b, SCALE_BITS)
+                .unwrap_or_else(|| panic!("{} and {} overflow", a, b));
+            let prover = MockProver::run(K, &circuit, instances).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn fixed_point_chip_q16() {
+        let (a, b) = (from_f64(-1234.5678, 16), from_f64(9.765, 16));
+        let circuit = FixedPointCircuit::new(a, b, 16);
+        let instances = FixedPointCircuit::instances::<Fp>(a, b, 16).unwrap();
+        let prover = MockProver::run(17, &circuit, instances).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn fixed_point_chip_rejects() {
+        let run = |a: i128, b: i128, claimed: [i128; 4]| {
+            let circuit = FixedPointCircuit::new(a, b, SCALE_BITS);
+            let instances = vec![claimed.iter().map(|x| to_field::<Fp>(*x)).collect()];
+            MockProver::run(K, &circuit, instances).unwrap().verify()
+        };
+        let (a, b) = (fixed(1.5), fixed(-2.25));
+        let honest = [
+            a + b,
+            a - b,
+            mul(a, b, SCALE_BITS).unwrap(),
+            div(a, b, SCALE_BITS).unwrap(),
+        ];
+        assert!(run(a, b, honest).is_ok());
+
+        // A product rounded up rather than down.
+        let mut wrong = honest;
+        wrong[2] += 1;
+        assert!(run(a, b, wrong).is_err());
+
+        // Overflowing results have no honest public inputs, and claiming their
+        // wrapped-around values fails: at the top of the range, a sum; at the bottom,
+        // both a sum and a quotient; and a product in Q16.16.
+        let wrapped = |x: i128| (x + 128 * 256).rem_euclid(256 * 256) - 128 * 256;
+        for (a, b) in [(100.0, 64.0), (127.0, 1.0), (-128.0, -1.0 / 128.0)] {
+            let (a, b) = (fixed(a), fixed(b));
+            assert!(FixedPointCircuit::instances::<Fp>(a, b, SCALE_BITS).is_none());
+
+            let claimed = [
+                a + b,
+                a - b,
+                (a * b).div_euclid(1 << SCALE_BITS),
+                (a << SCALE_BITS).div_euclid(b),
+            ]
+            .map(wrapped);
+            assert!(run(a, b, claimed).is_err());
+        }
+        let (a, b) = (from_f64(-1234.5678, 16), from_f64(98.765, 16));
+        assert!(FixedPointCircuit::instances::<Fp>(a, b, 16).is_none());
+
+        // An input out of range.
+        let a = fixed(200.0);
+        let claimed = [a + 1, a - 1, a >> SCALE_BITS, a << SCALE_BITS];
+        assert!(run(a, 1, claimed).is_err());
+    }
+
+    #[test]
+    fn fixed_point_chip_rejects_division_by_zero() {
+        let circuit = FixedPointCircuit::new(fixed(1.0), 0, SCALE_BITS);
+        let a = fixed(1.0);
+        for quotient in [0, 1, -1] {
+            let claimed = [a, a, 0, quotient].map(to_field::<Fp>);
+            let prover = MockProver::run(K, &circuit, vec![claimed.to_vec()]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+}
@@ -0,0 +1,246 @@
+//! Witness mutation fuzzer.
+//!
+//! [`fuzz`] records the trace of a satisfied circuit, then for every advice cell tries
+//! a handful of replacement values and re-runs the `MockProver` on each mutated trace.
+//! Any mutation the prover still accepts points at an under-constrained cell.
+
+use std::fmt;
+
+use ff::{FromUniformBytes, PrimeField};
+use halo2_proofs::dev::VerifyFailure;
+use halo2_proofs::plonk::Circuit;
+
+use crate::trace::{CellRef, ColumnKind, Trace, TraceError};
+
+/// A mutation of a single advice cell that still satisfies the circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Survivor<F> {
+    pub at: CellRef,
+    /// Name of the region the cell was assigned in.
+    pub region: Option<String>,
+    pub original: F,
+    pub mutated: F,
+}
+
+#[derive(Debug)]
+pub enum FuzzError {
+    Trace(TraceError),
+    /// The unmutated circuit is not satisfied, so there is nothing to fuzz.
+    Unsatisfied(Vec<VerifyFailure>),
+}
+
+impl fmt::Display for FuzzError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FuzzError::Trace(e) => write!(f, "{}", e),
+            FuzzError::Unsatisfied(failures) => {
+                write!(f, "circuit is not satisfied: {:?}", failures)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FuzzError {}
+
+impl From<TraceError> for FuzzError {
+    fn from(e: TraceError) -> Self {
+        FuzzError::Trace(e)
+    }
+}
+
+/// Values tried in place of `value`: small constants and values close to it.
+fn mutations<F: PrimeField>(value: F) -> Vec<F> {
+    let mut mutations = vec![];
+    for mutated in [
+        F::ZERO,
+        F::ONE,
+        value + F::ONE,
+        value - F::ONE,
+        -value,
+        value.double(),
+    ] {
+        if mutated != value && !mutations.contains(&mutated) {
+            mutations.push(mutated);
+        }
+    }
+    mutations
+}
+
+/// Mutates every assigned advice cell of `circuit` in turn and returns the mutations
+/// that the `MockProver` still accepts.
+pub fn fuzz<F, C>(
+    k: u32,
+    circuit: &C,
+    instances: Vec<Vec<F>>,
+) -> Result<Vec<Survivor<F>>, FuzzError>
+where
+    F: PrimeField + FromUniformBytes<64> + Ord,
+    C: Circuit<F>,
+{
+    let trace = Trace::record(k, circuit, instances)?;
+    trace
+        .mock_prove(circuit)?
+        .verify()
+        .map_err(FuzzError::Unsatisfied)?;
+
+    let mut survivors = vec![];
+    for (at, cell) in trace.cells.iter() {
+        let (ColumnKind::Advice, Some(original)) = (at.kind, cell.value) else {
+            continue;
+        };
+
+        for mutated in mutations(original) {
+            let mut mutant = trace.clone();
+            mutant.set(*at, mutated);
+
+            if mutant.mock_prove(circuit)?.verify().is_ok() {
+                survivors.push(Survivor {
+                    at: *at,
+                    region: cell.region.map(|region| trace.regions[region].clone()),
+                    original,
+                    mutated,
+                });
+            }
+        }
+    }
+
+    Ok(survivors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::add1::example1::{expected_output, MyCircuit};
+    use crate::fibonacci::example1::{fibonacci, FibonacciCircuit};
+    use crate::is_zero::{is_zero, IsZeroChip, IsZeroCircuit, IsZeroConfig};
+    use ff::Field;
+    use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+    use halo2_proofs::plonk::{Advice, Column, ConstraintSystem, Error, Selector};
+    use halo2curves::bn256::Fr;
+    use std::marker::PhantomData;
+
+    /// Uses `IsZeroChip::assign`, which only assigns the inverse: nothing ties the
+    /// `value` cell to anything but the chip's own gate.
+    #[derive(Default)]
+    struct ChipAssignCircuit {
+        value: Value<Fr>,
+    }
+
+    impl Circuit<Fr> for ChipAssignCircuit {
+        type Config = (Column<Advice>, Selector, IsZeroConfig<Fr>);
+        type FloorPlanner = SimpleFloorPlanner;
+        type Params = ();
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let value = meta.advice_column();
+            let value_inv = meta.advice_column();
+            let selector = meta.selector();
+
+            let is_zero =
+                IsZeroChip::configure(meta, |meta| meta.query_selector(selector), value, value_inv);
+            (value, selector, is_zero)
+        }
+
+        fn synthesize(
+            &self,
+            (value, selector, is_zero): Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            layouter.assign_region(
+                || "value",
+                |mut region| {
+                    selector.enable(&mut region, 0)?;
+                    region.assign_advice(|| "value", value, 0, || self.value)
+                },
+            )?;
+
+            let chip = IsZeroChip::construct(is_zero);
+            chip.assign(layouter.namespace(|| "is_zero"), self.value.map(Into::into))?;
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn fuzz_is_zero_chip_assign() {
+        let circuit = ChipAssignCircuit {
+            value: Value::known(Fr::from(42)),
+        };
+
+        let survivors = fuzz(4, &circuit, vec![]).unwrap();
+        assert!(survivors.contains(&Survivor {
+            at: CellRef::advice(0, 0),
+            region: Some("value".to_string()),
+            original: Fr::from(42),
+            mutated: Fr::ZERO,
+        }));
+    }
+
+    #[test]
+    fn fuzz_is_zero_circuit() {
+        let value = Fr::from(42);
+        let circuit = IsZeroCircuit {
+            value: Value::known(value),
+        };
+        let survivors = fuzz(4, &circuit, vec![vec![is_zero(value)]]).unwrap();
+        assert_eq!(survivors, vec![]);
+
+        // With a zero value the inverse is unused, so any value_inv (column 1) is
+        // accepted without changing the output.
+        let value = Fr::ZERO;
+        let circuit = IsZeroCircuit {
+            value: Value::known(value),
+        };
+        let survivors = fuzz(4, &circuit, vec![vec![is_zero(value)]]).unwrap();
+        assert!(!survivors.is_empty());
+        assert!(survivors.iter().all(|survivor| survivor.at.column == 1));
+    }
+
+    #[test]
+    fn fuzz_fibonacci() {
+        let (a, b, n) = (Fr::from(1), Fr::from(1), 8);
+        let circuit = FibonacciCircuit {
+            a: Value::known(a),
+            b: Value::known(b),
+            n,
+        };
+
+        let survivors = fuzz(5, &circuit, vec![vec![fibonacci(a, b, n)]]).unwrap();
+        assert_eq!(survivors, vec![]);
+    }
+
+    #[test]
+    fn fuzz_add1() {
+        let (value, scalar, nrows) = (Fr::from(5), Fr::from(3), 8);
+        let circuit = MyCircuit {
+            value,
+            scalar,
+            nrows,
+            _marker: PhantomData,
+        };
+
+        let survivors = fuzz(
+            5,
+            &circuit,
+            vec![vec![expected_output(value, scalar, nrows)]],
+        )
+        .unwrap();
+        assert_eq!(survivors, vec![]);
+    }
+
+    #[test]
+    fn fuzz_unsatisfied() {
+        let circuit = IsZeroCircuit {
+            value: Value::known(Fr::from(42)),
+        };
+
+        assert!(matches!(
+            fuzz(4, &circuit, vec![vec![Fr::ONE]]),
+            Err(FuzzError::Unsatisfied(_))
+        ));
+    }
+}
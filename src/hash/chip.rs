@@ -0,0 +1,331 @@
+use ff::PrimeField;
+use halo2_gadgets::poseidon::primitives::{Absorbing, ConstantLength, Domain, Spec};
+use halo2_gadgets::poseidon::{PaddedWord, Pow5Chip, Pow5Config, Sponge};
+use halo2_proofs::{circuit::*, plonk::*};
+use std::marker::PhantomData;
+
+use super::{poseidon, VariableLength, RATE, WIDTH};
+
+// Table setup
+//
+// The message is witnessed across the state columns, WIDTH values per row, then
+// copied into the rows of `Pow5Chip`: one row per full round and one per pair of
+// partial rounds, for each permutation.
+//
+//  state[0] | state[1] | state[2] | partial_sbox | rc_a[0..3] | rc_b[0..3] |
+// ---------------------------------------------------------------------------
+//    m0     |    m1    |    m2    |              |            |            |
+//   ...     |          |          |              |            |            |
+//   s0      |    s1    |    s2    |              |  round c   |  round c'  |
+//   ...     |          |          |              |            |            |
+
+#[derive(Clone, Debug)]
+pub struct PoseidonConfig<F: PrimeField> {
+    pub state: [Column<Advice>; WIDTH],
+    pub instance: Column<Instance>,
+    pub pow5: Pow5Config<F, WIDTH, RATE>,
+}
+
+/// Hashes slices of assigned cells with the Poseidon spec `S`, matching the native
+/// [`poseidon`].
+#[derive(Clone, Debug)]
+pub struct PoseidonChip<F: PrimeField, S: Spec<F, WIDTH, RATE>> {
+    config: PoseidonConfig<F>,
+    _spec: PhantomData<S>,
+}
+
+impl<F: PrimeField, S: Spec<F, WIDTH, RATE>> PoseidonChip<F, S> {
+    pub fn construct(config: PoseidonConfig<F>) -> Self {
+        Self {
+            config,
+            _spec: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> PoseidonConfig<F> {
+        let state = [(); WIDTH].map(|_| meta.advice_column());
+        let partial_sbox = meta.advice_column();
+        let rc_a = [(); WIDTH].map(|_| meta.fixed_column());
+        let rc_b = [(); WIDTH].map(|_| meta.fixed_column());
+        let instance = meta.instance_column();
+
+        for column in state {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        // The initial capacity element is loaded as a constant.
+        meta.enable_constant(rc_b[0]);
+
+        let pow5 = Pow5Chip::configure::<S>(meta, state, partial_sbox, rc_a, rc_b);
+
+        PoseidonConfig {
+            state,
+            instance,
+            pow5,
+        }
+    }
+
+    /// Witnesses a message to hash, `WIDTH` values per row.
+    pub fn load_message(
+        &self,
+        mut layouter: impl Layouter<F>,
+        message: &[Value<F>],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        layouter.assign_region(
+            || "load message",
+            |mut region| {
+                message
+                    .iter()
+                    .enumerate()
+                    .map(|(i, value)| {
+                        region.assign_advice(
+                            || format!("message {}", i),
+                            self.config.state[i % WIDTH],
+                            i / WIDTH,
+                            || *value,
+                        )
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    /// Hashes `message` in the [`VariableLength`] domain. The cells are copied into the
+    /// sponge, so they may come from any region with equality enabled.
+    pub fn hash(
+        &self,
+        layouter: impl Layouter<F>,
+        message: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.sponge::<VariableLength>(layouter, message)
+    }
+
+    /// Hashes a message of `L` cells in the `ConstantLength` domain, matching
+    /// [`poseidon_constant`](super::poseidon_constant). With `L` a multiple of the rate
+    /// there is no padding, so a pair takes a single permutation.
+    pub fn hash_constant<const L: usize>(
+        &self,
+        layouter: impl Layouter<F>,
+        message: &[AssignedCell<F, F>; L],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.sponge::<ConstantLength<L>>(layouter, message)
+    }
+
+    /// Absorbs `message` and its padding in the domain `D`, then squeezes one cell.
+    fn sponge<D: Domain<F, RATE>>(
+        &self,
+        mut layouter: impl Layouter<F>,
+        message: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let chip = Pow5Chip::construct(self.config.pow5.clone());
+        let mut sponge: Sponge<
+            F,
+            Pow5Chip<F, WIDTH, RATE>,
+            S,
+            Absorbing<PaddedWord<F>, RATE>,
+            D,
+            WIDTH,
+            RATE,
+        > = Sponge::new(chip, layouter.namespace(|| "init"))?;
+
+        for (i, cell) in message.iter().enumerate() {
+            sponge.absorb(
+                layouter.namespace(|| format!("absorb {}", i)),
+                PaddedWord::Message(cell.clone()),
+            )?;
+        }
+        for (i, value) in D::padding(message.len()).into_iter().enumerate() {
+            sponge.absorb(
+                layouter.namespace(|| format!("pad {}", i)),
+                PaddedWord::Padding(value),
+            )?;
+        }
+
+        sponge
+            .finish_absorbing(layouter.namespace(|| "finish absorbing"))?
+            .squeeze(layouter.namespace(|| "squeeze"))
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+/// Hashes a private message of fixed length with the spec `S`.
+///
+/// Instance layout: `[poseidon(message)]`.
+#[derive(Debug)]
+pub struct PoseidonCircuit<F: PrimeField, S: Spec<F, WIDTH, RATE>> {
+    pub message: Vec<Value<F>>,
+    pub _spec: PhantomData<S>,
+}
+
+impl<F: PrimeField, S: Spec<F, WIDTH, RATE>> PoseidonCircuit<F, S> {
+    pub fn new(message: &[F]) -> Self {
+        Self {
+            message: message.iter().copied().map(Value::known).collect(),
+            _spec: PhantomData,
+        }
+    }
+
+    /// The public input matching `message`.
+    pub fn instances(message: &[F]) -> Vec<Vec<F>> {
+        vec![vec![poseidon::<F, S>(message)]]
+    }
+}
+
+// Manual impl: a derive would require `S: Clone`, which the gadget specs don't have.
+impl<F: PrimeField, S: Spec<F, WIDTH, RATE>> Clone for PoseidonCircuit<F, S> {
+    fn clone(&self) -> Self {
+        Self {
+            message: self.message.clone(),
+            _spec: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, S: Spec<F, WIDTH, RATE>> Circuit<F> for PoseidonCircuit<F, S> {
+    type Config = PoseidonConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            message: vec![Value::unknown(); self.message.len()],
+            _spec: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        PoseidonChip::<F, S>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = PoseidonChip::<F, S>::construct(config);
+
+        let message = chip.load_message(layouter.namespace(|| "message"), &self.message)?;
+        let digest = chip.hash(layouter.namespace(|| "hash"), &message)?;
+        chip.expose_public(layouter.namespace(|| "digest"), &digest, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::{P128Pow5T3, P128Pow5T3Bn256};
+    use crate::proof::{create_proof, keygen, verify_proof};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::transcript::{Blake2bRead, Blake2bWrite, Challenge255};
+    use halo2curves::bn256::{Bn256, Fr};
+    use halo2curves::pasta::{EqAffine, Fp};
+    use rand_core::OsRng;
+
+    const K: u32 = 8;
+
+    #[test]
+    fn poseidon_chip_pallas() {
+        for len in 0..=3u64 {
+            let message: Vec<Fp> = (1..=len).map(Fp::from).collect();
+            let circuit = PoseidonCircuit::<Fp, P128Pow5T3>::new(&message);
+            let instances = PoseidonCircuit::<Fp, P128Pow5T3>::instances(&message);
+
+            let prover = MockProver::run(K, &circuit, instances.clone()).unwrap();
+            prover.assert_satisfied();
+
+            let wrong = vec![vec![instances[0][0] + Fp::from(1)]];
+            let prover = MockProver::run(K, &circuit, wrong).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn poseidon_chip_bn256() {
+        for len in 0..=3u64 {
+            let message: Vec<Fr> = (1..=len).map(Fr::from).collect();
+            let circuit = PoseidonCircuit::<Fr, P128Pow5T3Bn256>::new(&message);
+            let instances = PoseidonCircuit::<Fr, P128Pow5T3Bn256>::instances(&message);
+
+            let prover = MockProver::run(K, &circuit, instances.clone()).unwrap();
+            prover.assert_satisfied();
+
+            let wrong = vec![vec![instances[0][0] + Fr::from(1)]];
+            let prover = MockProver::run(K, &circuit, wrong).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn poseidon_chip_ipa() {
+        use halo2_proofs::poly::commitment::ParamsProver;
+        use halo2_proofs::poly::ipa::commitment::IPACommitmentScheme;
+        use halo2_proofs::poly::ipa::multiopen::{ProverIPA, VerifierIPA};
+        use halo2_proofs::poly::ipa::strategy::AccumulatorStrategy;
+
+        let message = [Fp::from(1), Fp::from(2), Fp::from(3)];
+        let circuit = PoseidonCircuit::<Fp, P128Pow5T3>::new(&message);
+        let out = poseidon::<Fp, P128Pow5T3>(&message);
+
+        let (params, pk) = keygen::<IPACommitmentScheme<EqAffine>>(K, circuit.clone());
+        let proof = create_proof::<_, ProverIPA<_>, _, _, Blake2bWrite<_, _, Challenge255<_>>>(
+            OsRng,
+            &params,
+            &pk,
+            &[circuit],
+            &[&[&[out]]],
+        );
+        verify_proof::<
+            _,
+            VerifierIPA<_>,
+            _,
+            Blake2bRead<_, _, Challenge255<_>>,
+            AccumulatorStrategy<_>,
+        >(
+            params.verifier_params(),
+            pk.get_vk(),
+            &proof[..],
+            &[&[&[out]]],
+        );
+    }
+
+    #[test]
+    fn poseidon_chip_kzg() {
+        use halo2_proofs::poly::commitment::ParamsProver;
+        use halo2_proofs::poly::kzg::commitment::KZGCommitmentScheme;
+        use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+        use halo2_proofs::poly::kzg::strategy::AccumulatorStrategy;
+
+        let message = [Fr::from(1), Fr::from(2), Fr::from(3)];
+        let circuit = PoseidonCircuit::<Fr, P128Pow5T3Bn256>::new(&message);
+        let out = poseidon::<Fr, P128Pow5T3Bn256>(&message);
+
+        let (params, pk) = keygen::<KZGCommitmentScheme<Bn256>>(K, circuit.clone());
+        let proof = create_proof::<_, ProverSHPLONK<_>, _, _, Blake2bWrite<_, _, Challenge255<_>>>(
+            OsRng,
+            &params,
+            &pk,
+            &[circuit],
+            &[&[&[out]]],
+        );
+        verify_proof::<
+            _,
+            VerifierSHPLONK<_>,
+            _,
+            Blake2bRead<_, _, Challenge255<_>>,
+            AccumulatorStrategy<_>,
+        >(
+            params.verifier_params(),
+            pk.get_vk(),
+            &proof[..],
+            &[&[&[out]]],
+        );
+    }
+}
@@ -0,0 +1,323 @@
+//! Poseidon hashing over slices of field elements, in and out of circuit.
+//!
+//! [`PoseidonChip`] wraps the `Pow5Chip` of `halo2_gadgets` behind a
+//! `hash(&[AssignedCell]) -> AssignedCell` API, and [`poseidon`] is the matching native
+//! hasher. Both work with any [`Spec`] of width 3 and rate 2: [`P128Pow5T3`] on the
+//! pasta fields and [`P128Pow5T3Bn256`] on the BN254 scalar field.
+//!
+//! Messages of any length are hashed in the [`VariableLength`] domain. Messages whose
+//! length is fixed, such as the two children of a Merkle node, can use the
+//! `ConstantLength` domain of `halo2_gadgets` instead through [`poseidon_constant`] and
+//! [`PoseidonChip::hash_constant`], which saves the padding permutation.
+
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use halo2_gadgets::poseidon::primitives::{generate_constants, ConstantLength, Domain, Spec};
+use halo2curves::bn256::Fr;
+
+pub use halo2_gadgets::poseidon::primitives::P128Pow5T3;
+
+mod chip;
+
+pub use chip::{PoseidonChip, PoseidonCircuit, PoseidonConfig};
+
+/// State width of the permutation.
+pub const WIDTH: usize = 3;
+/// Field elements absorbed per permutation.
+pub const RATE: usize = 2;
+
+/// `P128Pow5T3` parameters for the BN254 scalar field: `x^5` S-box, 8 full rounds and
+/// 58 partial rounds, with constants from the reference Grain LFSR.
+///
+/// 128-bit security on BN254 needs 57 partial rounds, but `Pow5Chip` runs them in
+/// pairs, so this rounds up to the next even count. The constants are therefore not
+/// those of circomlib's Poseidon, which uses 57.
+#[derive(Clone, Copy, Debug)]
+pub struct P128Pow5T3Bn256;
+
+impl Spec<Fr, WIDTH, RATE> for P128Pow5T3Bn256 {
+    fn full_rounds() -> usize {
+        8
+    }
+
+    fn partial_rounds() -> usize {
+        58
+    }
+
+    fn sbox(val: Fr) -> Fr {
+        val.pow_vartime([5])
+    }
+
+    fn secure_mds() -> usize {
+        0
+    }
+
+    fn constants() -> (Vec<[Fr; WIDTH]>, [[Fr; WIDTH]; WIDTH], [[Fr; WIDTH]; WIDTH]) {
+        generate_constants::<_, Self, WIDTH, RATE>()
+    }
+}
+
+/// Sponge domain for messages of any length, as in the variable-length mode of the
+/// Poseidon paper: the capacity starts at zero and the message is padded with a one
+/// followed by zeros up to a multiple of the rate.
+///
+/// Unlike `ConstantLength`, the length is not fixed at compile time, so one circuit
+/// shape can hash slices of whatever length it is given.
+#[derive(Clone, Copy, Debug)]
+pub struct VariableLength;
+
+impl<F: PrimeField> Domain<F, RATE> for VariableLength {
+    type Padding = Vec<F>;
+
+    fn name() -> String {
+        "VariableLength".to_string()
+    }
+
+    fn initial_capacity_element() -> F {
+        F::ZERO
+    }
+
+    fn padding(input_len: usize) -> Self::Padding {
+        let mut padding = vec![F::ONE];
+        padding.resize(RATE - input_len % RATE, F::ZERO);
+        padding
+    }
+}
+
+/// Round constants and MDS matrix of a spec, computed once for repeated permutations.
+struct Permutation<F: PrimeField, S: Spec<F, WIDTH, RATE>> {
+    round_constants: Vec<[F; WIDTH]>,
+    mds: [[F; WIDTH]; WIDTH],
+    _spec: PhantomData<S>,
+}
+
+impl<F: PrimeField, S: Spec<F, WIDTH, RATE>> Permutation<F, S> {
+    fn new() -> Self {
+        let (round_constants, mds, _) = S::constants();
+        Self {
+            round_constants,
+            mds,
+            _spec: PhantomData,
+        }
+    }
+
+    fn apply(&self, state: &mut [F; WIDTH]) {
+        let half_full_rounds = S::full_rounds() / 2;
+        let partial_rounds = half_full_rounds..half_full_rounds + S::partial_rounds();
+
+        for (round, round_constants) in self.round_constants.iter().enumerate() {
+            for (word, rc) in state.iter_mut().zip(round_constants.iter()) {
+                *word += rc;
+            }
+
+            if partial_rounds.contains(&round) {
+                state[0] = S::sbox(state[0]);
+            } else {
+                for word in state.iter_mut() {
+                    *word = S::sbox(*word);
+                }
+            }
+
+            let mut next = [F::ZERO; WIDTH];
+            for (next, row) in next.iter_mut().zip(self.mds.iter()) {
+                *next = row
+                    .iter()
+                    .zip(state.iter())
+                    .map(|(m, word)| *m * word)
+                    .sum();
+            }
+            *state = next;
+        }
+    }
+}
+
+/// Native reference: the Poseidon permutation of `state` under the spec `S`.
+pub fn permute<F: PrimeField, S: Spec<F, WIDTH, RATE>>(state: &mut [F; WIDTH]) {
+    Permutation::<F, S>::new().apply(state)
+}
+
+/// Native reference: the Poseidon hash of `message` in the [`VariableLength`] domain,
+/// as computed by [`PoseidonChip::hash`].
+pub fn poseidon<F: PrimeField, S: Spec<F, WIDTH, RATE>>(message: &[F]) -> F {
+    sponge::<F, S, VariableLength>(message)
+}
+
+/// Native reference: the Poseidon hash of a message of `L` elements in the
+/// `ConstantLength` domain, as computed by [`PoseidonChip::hash_constant`] and by the
+/// `Hash` primitive of `halo2_gadgets`.
+pub fn poseidon_constant<F: PrimeField, S: Spec<F, WIDTH, RATE>, const L: usize>(
+    message: [F; L],
+) -> F {
+    sponge::<F, S, ConstantLength<L>>(&message)
+}
+
+/// Absorbs `message` and its padding in the domain `D`, then squeezes one element.
+fn sponge<F: PrimeField, S: Spec<F, WIDTH, RATE>, D: Domain<F, RATE>>(message: &[F]) -> F {
+    let permutation = Permutation::<F, S>::new();
+
+    let mut state = [F::ZERO; WIDTH];
+    state[RATE] = D::initial_capacity_element();
+
+    let padding = D::padding(message.len());
+    let padded: Vec<F> = message.iter().copied().chain(padding).collect();
+    for chunk in padded.chunks(RATE) {
+        for (word, value) in state.iter_mut().zip(chunk.iter()) {
+            *word += value;
+        }
+        permutation.apply(&mut state);
+    }
+
+    state[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_gadgets::poseidon::primitives::Hash;
+    use halo2curves::pasta::Fp;
+
+    fn from_decimal<F: PrimeField>(value: &str) -> F {
+        F::from_str_vartime(value).expect("valid decimal field element")
+    }
+
+    /// `(message, hash)` regression pairs for pallas, computed by [`poseidon`] itself.
+    ///
+    /// No published vectors exist for the [`VariableLength`] domain, so these only pin
+    /// the current output; agreement with `halo2_gadgets` is checked through the
+    /// permutation in `poseidon_permute_matches_gadgets`.
+    const PALLAS_VECTORS: [(&[u64], &str); 4] = [
+        (
+            &[],
+            "23466623156298192378164339899696045129045707236582473449930624781445116628870",
+        ),
+        (
+            &[1],
+            "22567817240535579333804821302855714493053412677218651939572535873589815436814",
+        ),
+        (
+            &[1, 2],
+            "21314525990492004016410933951191904875921797263729346148618808425608175604635",
+        ),
+        (
+            &[1, 2, 3],
+            "3381629650945163267802833432128400368828377485224724101599566236637194981035",
+        ),
+    ];
+
+    /// As [`PALLAS_VECTORS`], for BN254. [`P128Pow5T3Bn256`] uses 58 partial rounds,
+    /// which no published spec does, so these are regression values as well.
+    const BN256_VECTORS: [(&[u64], &str); 4] = [
+        (
+            &[],
+            "4175589630953524434195486297747109261875816898818022729288280871747780487875",
+        ),
+        (
+            &[1],
+            "10668413459965641184282400798309407740433892574871916422145280016961282144843",
+        ),
+        (
+            &[1, 2],
+            "5153970063654978495776195524149643950646379799000338586469416775115138147575",
+        ),
+        (
+            &[1, 2, 3],
+            "9172600160300160777881424502260434927996887312575876880789255310484281257358",
+        ),
+    ];
+
+    /// Regression values for the permutation of `[0, 1, 2]`, computed by [`permute`].
+    #[test]
+    fn poseidon_permute_vectors() {
+        let mut state = [Fp::from(0), Fp::from(1), Fp::from(2)];
+        permute::<_, P128Pow5T3>(&mut state);
+        assert_eq!(
+            state,
+            [
+                "19142758212910704988134549186320465225050001548607778483843514680734401733718",
+                "8943457793054409913105520643844025343653237882909500861250463986907015919658",
+                "4653491495579411712133380452970045393126868676144731347343956788496825228765",
+            ]
+            .map(from_decimal::<Fp>)
+        );
+
+        let mut state = [Fr::from(0), Fr::from(1), Fr::from(2)];
+        permute::<_, P128Pow5T3Bn256>(&mut state);
+        assert_eq!(
+            state,
+            [
+                "11423825932651801599699170728777156223134099051928246849639176411932923334404",
+                "10844013424845634639368176759109363339901060641710351570835621522944090096955",
+                "16134944521467974302855046313740924177828551606462766618420983217743994977617",
+            ]
+            .map(from_decimal::<Fr>)
+        );
+    }
+
+    #[test]
+    fn poseidon_hash_vectors() {
+        for (message, expected) in PALLAS_VECTORS {
+            let message: Vec<Fp> = message.iter().copied().map(Fp::from).collect();
+            assert_eq!(
+                poseidon::<_, P128Pow5T3>(&message),
+                from_decimal::<Fp>(expected)
+            );
+        }
+
+        for (message, expected) in BN256_VECTORS {
+            let message: Vec<Fr> = message.iter().copied().map(Fr::from).collect();
+            assert_eq!(
+                poseidon::<_, P128Pow5T3Bn256>(&message),
+                from_decimal::<Fr>(expected)
+            );
+        }
+    }
+
+    /// The native permutation agrees with the one in `halo2_gadgets`, seen through its
+    /// `ConstantLength` hash: capacity `L << 64`, no padding for `L = 2`.
+    #[test]
+    fn poseidon_permute_matches_gadgets() {
+        let message = [Fp::from(1), Fp::from(2)];
+        let expected = Hash::<_, P128Pow5T3, ConstantLength<2>, WIDTH, RATE>::init().hash(message);
+
+        let mut state = [message[0], message[1], Fp::from_u128(2u128 << 64)];
+        permute::<_, P128Pow5T3>(&mut state);
+        assert_eq!(state[0], expected);
+
+        let message = [Fr::from(1), Fr::from(2)];
+        let expected =
+            Hash::<_, P128Pow5T3Bn256, ConstantLength<2>, WIDTH, RATE>::init().hash(message);
+
+        let mut state = [message[0], message[1], Fr::from_u128(2u128 << 64)];
+        permute::<_, P128Pow5T3Bn256>(&mut state);
+        assert_eq!(state[0], expected);
+    }
+
+    #[test]
+    fn poseidon_constant_matches_gadgets() {
+        let message = [Fp::from(1), Fp::from(2)];
+        assert_eq!(
+            poseidon_constant::<_, P128Pow5T3, 2>(message),
+            Hash::<_, P128Pow5T3, ConstantLength<2>, WIDTH, RATE>::init().hash(message)
+        );
+
+        let message = [Fr::from(1), Fr::from(2), Fr::from(3)];
+        assert_eq!(
+            poseidon_constant::<_, P128Pow5T3Bn256, 3>(message),
+            Hash::<_, P128Pow5T3Bn256, ConstantLength<3>, WIDTH, RATE>::init().hash(message)
+        );
+    }
+
+    #[test]
+    fn poseidon_padding_is_injective() {
+        let one = Fp::from(1);
+        assert_ne!(
+            poseidon::<_, P128Pow5T3>(&[one]),
+            poseidon::<_, P128Pow5T3>(&[one, Fp::from(0)])
+        );
+        assert_ne!(
+            poseidon::<_, P128Pow5T3>(&[]),
+            poseidon::<_, P128Pow5T3>(&[Fp::from(0)])
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use halo2_proofs::{arithmetic::Field, circuit::*, plonk::*, poly::Rotation};
 
+#[derive(Clone, Debug)]
 pub struct IsZeroConfig<F: Field> {
     pub value_inv: Column<Advice>,
     pub expr: Expression<F>,
@@ -48,4 +49,180 @@ impl<F: Field> IsZeroChip<F> {
             },
         )
     }
+
+    /// Assigns the inverse of `value` at `offset` of an existing region, next to the
+    /// `value` cell the gate was configured against.
+    pub fn assign_in_region(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        value: Value<F>,
+    ) -> Result<(), Error> {
+        let value_inv = value.map(|value| value.invert().unwrap_or(F::ZERO));
+        region.assign_advice(|| "value inv", self.config.value_inv, offset, || value_inv)?;
+        Ok(())
+    }
+}
+
+/// Native reference: one if `value` is zero, zero otherwise.
+pub fn is_zero<F: Field>(value: F) -> F {
+    if value.is_zero_vartime() {
+        F::ONE
+    } else {
+        F::ZERO
+    }
+}
+
+// Table setup
+//
+//   value  | value_inv |  out  |  selector  |
+// ---------------------------------------------
+//     v    |   1 / v   |   0   |      1     |
+//
+// `value_inv` is zero when `value` is zero, in which case `out` is one.
+
+#[derive(Clone, Debug)]
+pub struct IsZeroCircuitConfig<F: Field> {
+    pub value: Column<Advice>,
+    pub out: Column<Advice>,
+    pub selector: Selector,
+    pub instance: Column<Instance>,
+    pub is_zero: IsZeroConfig<F>,
+}
+
+/// Computes `is_zero(value)` for a private `value`.
+///
+/// Instance layout: `[is_zero(value)]`.
+#[derive(Default, Clone, Debug)]
+pub struct IsZeroCircuit<F: Field> {
+    pub value: Value<F>,
+}
+
+impl<F: Field> Circuit<F> for IsZeroCircuit<F> {
+    type Config = IsZeroCircuitConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let value = meta.advice_column();
+        let value_inv = meta.advice_column();
+        let out = meta.advice_column();
+        let selector = meta.selector();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(out);
+        meta.enable_equality(instance);
+
+        let is_zero =
+            IsZeroChip::configure(meta, |meta| meta.query_selector(selector), value, value_inv);
+
+        meta.create_gate("is_zero out", |meta| {
+            let selector = meta.query_selector(selector);
+            let out = meta.query_advice(out, Rotation::cur());
+
+            vec![selector * (out - is_zero.expr.clone())]
+        });
+
+        IsZeroCircuitConfig {
+            value,
+            out,
+            selector,
+            instance,
+            is_zero,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = IsZeroChip::construct(config.is_zero.clone());
+
+        let out = layouter.assign_region(
+            || "is_zero",
+            |mut region| {
+                config.selector.enable(&mut region, 0)?;
+                region.assign_advice(|| "value", config.value, 0, || self.value)?;
+                chip.assign_in_region(&mut region, 0, self.value)?;
+                region.assign_advice(|| "out", config.out, 0, || self.value.map(is_zero))
+            },
+        )?;
+
+        layouter.constrain_instance(out.cell(), config.instance, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_zero, IsZeroCircuit};
+    use crate::model::{check, strategy, Model};
+    use ff::Field;
+    use halo2_proofs::circuit::Value;
+    use halo2_proofs::dev::MockProver;
+    use pasta_curves::pallas;
+    use proptest::prelude::*;
+
+    #[test]
+    fn is_zero_circuit() {
+        let k = 4;
+
+        for value in [
+            pallas::Base::ZERO,
+            pallas::Base::ONE,
+            pallas::Base::from(42),
+            -pallas::Base::ONE,
+        ] {
+            let circuit = IsZeroCircuit {
+                value: Value::known(value),
+            };
+            let out = is_zero(value);
+
+            let prover = MockProver::run(k, &circuit, vec![vec![out]]).unwrap();
+            prover.assert_satisfied();
+
+            let prover = MockProver::run(k, &circuit, vec![vec![pallas::Base::ONE - out]]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    struct IsZeroModel;
+
+    impl Model<pallas::Base> for IsZeroModel {
+        /// `(value, claimed output)`, `None` claiming the right output.
+        type Input = (pallas::Base, Option<pallas::Base>);
+        type Circuit = IsZeroCircuit<pallas::Base>;
+
+        fn k(_: &Self::Input) -> u32 {
+            4
+        }
+
+        fn circuit(&(value, claim): &Self::Input) -> (Self::Circuit, Vec<Vec<pallas::Base>>) {
+            let out = claim.unwrap_or_else(|| is_zero(value));
+            let circuit = IsZeroCircuit {
+                value: Value::known(value),
+            };
+            (circuit, vec![vec![out]])
+        }
+
+        fn accepts(&(value, claim): &Self::Input) -> bool {
+            claim.is_none() || claim == Some(is_zero(value))
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn is_zero_model(
+            value in strategy::field::<pallas::Base>(),
+            claim in strategy::claim::<pallas::Base>(),
+        ) {
+            check::<_, IsZeroModel>(&(value, claim)).map_err(TestCaseError::fail)?;
+        }
+    }
 }
@@ -0,0 +1,659 @@
+use std::fmt::Debug;
+
+use ff::{PrimeField, PrimeFieldBits};
+use halo2_proofs::{circuit::*, plonk::*};
+
+use super::{
+    padding, split_spread_lane, spread_lane, DIGEST_LANES, RATE_LANES, RC, ROTATIONS, ROUNDS,
+};
+use crate::range_check::spread::{
+    bytes, pack, pow2, Input, PaddedByte, Row, SpreadChip, SpreadConfig, Zero,
+};
+use crate::range_check::table::TableRegistry;
+
+// Table setup
+//
+// Every operation is one region of `SpreadChip`. E.g. `x ^ y ^ z` sums the spreads
+// of the three lanes and splits that sum into its even bits `e` and odd bits `o`,
+// the bytes of `e` first so that the accumulators then hold `e` and its spread:
+//
+//  dense | spread | acc_dense | acc_spread | coeff_dense | coeff_spread
+// ----------------------------------------------------------------------
+//   e0   |  e0'   |     0     |     0      |    2^0      |     4^0
+//   ...  |        |           |            |             |
+//   e7   |  e7'   |           |            |    2^56     |     4^56
+//   o0   |  o0'   |     e     |     e'     |     0       |   2 * 4^0
+//   ...  |        |           |            |             |
+//   o7   |  o7'   |           |            |     0       |   2 * 4^56
+//   0    |   x'   |           |            |     0       |     -1
+//   0    |   y'   |           |            |     0       |     -1
+//   0    |   z'   |           |            |     0       |     -1
+//        |        |     e     |     0      |             |
+//
+// The odd bits are kept the same way, with the even bytes weighted by 4^(8i) / 2 and
+// the terms by -1/2.
+
+/// A lane constrained to 64 bits, together with its spread.
+#[derive(Clone, Debug)]
+pub struct Lane<F: PrimeFieldBits> {
+    pub value: Value<u64>,
+    pub dense: AssignedCell<F, F>,
+    pub spread: AssignedCell<F, F>,
+}
+
+/// The spread of a lane constrained to 64 bits, as left by a rotation.
+#[derive(Clone, Debug)]
+pub struct SpreadLane<F: PrimeFieldBits> {
+    pub value: Value<u64>,
+    pub spread: AssignedCell<F, F>,
+}
+
+impl<F: PrimeFieldBits> Lane<F> {
+    pub fn to_spread(&self) -> SpreadLane<F> {
+        SpreadLane {
+            value: self.value,
+            spread: self.spread.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct KeccakConfig<F: PrimeFieldBits> {
+    pub spread: SpreadConfig<F>,
+    pub instance: Column<Instance>,
+}
+
+/// The byte pieces of a lane.
+fn lane_bytes<'a, F: PrimeFieldBits>(lane: Value<u64>, dense: F, spread: F) -> Vec<Row<'a, F>> {
+    bytes(lane, 8, dense, spread)
+}
+
+/// Chunk boundaries of a lane rotated left by `rotation`: the rotation, and every
+/// byte.
+fn boundaries(rotation: u32) -> Vec<u32> {
+    let mut bounds: Vec<u32> = (0..=8).map(|i| 8 * i).collect();
+    bounds.push((64 - rotation) % 64);
+    bounds.sort_unstable();
+    bounds.dedup();
+    bounds
+}
+
+/// The padded bytes of each message lane, least significant first.
+fn message_lanes(len: usize) -> impl Iterator<Item = Vec<PaddedByte>> {
+    padding(len)
+        .chunks(8)
+        .map(<[PaddedByte]>::to_vec)
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+/// A 5x5 array by `[x][y]`, failing on the first error of `f`.
+fn by_lane<T: Debug>(
+    mut f: impl FnMut(usize, usize) -> Result<T, Error>,
+) -> Result<[[T; 5]; 5], Error> {
+    let mut columns = Vec::with_capacity(5);
+    for x in 0..5 {
+        let column = (0..5).map(|y| f(x, y)).collect::<Result<Vec<_>, Error>>()?;
+        columns.push(<[T; 5]>::try_from(column).unwrap());
+    }
+    Ok(columns.try_into().unwrap())
+}
+
+/// Computes Keccak-256 with 64-bit lanes held densely and as spreads, over a spread
+/// table of chunks up to 8 bits.
+#[derive(Clone, Debug)]
+pub struct KeccakChip<F: PrimeFieldBits> {
+    config: KeccakConfig<F>,
+    spread: SpreadChip<F>,
+}
+
+impl<F: PrimeFieldBits> KeccakChip<F> {
+    pub fn construct(config: KeccakConfig<F>) -> Self {
+        let spread = SpreadChip::construct(config.spread.clone());
+        Self { config, spread }
+    }
+
+    /// Configures the chip with its own spread table.
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> KeccakConfig<F> {
+        let spread = SpreadChip::configure(meta);
+        Self::configure_with_spread(meta, spread)
+    }
+
+    /// Configures the chip against the spread table in `registry`, which must then be
+    /// loaded through [`TableRegistry::load`] rather than [`KeccakChip::load_table`].
+    pub fn configure_with_registry(
+        meta: &mut ConstraintSystem<F>,
+        registry: &mut TableRegistry<F>,
+    ) -> KeccakConfig<F> {
+        let spread = SpreadChip::configure_with_registry(meta, registry);
+        Self::configure_with_spread(meta, spread)
+    }
+
+    fn configure_with_spread(
+        meta: &mut ConstraintSystem<F>,
+        spread: SpreadConfig<F>,
+    ) -> KeccakConfig<F> {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        KeccakConfig { spread, instance }
+    }
+
+    /// Loads the spread table. Must be called once per circuit, and not at all if the
+    /// chip was configured from a [`TableRegistry`].
+    pub fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.spread.load_table(layouter)
+    }
+
+    /// Loads a message lane from its padded `bytes`, least significant first.
+    fn message_lane(
+        &self,
+        layouter: impl Layouter<F>,
+        bytes: &[PaddedByte],
+        message: &[Value<u8>],
+    ) -> Result<Lane<F>, Error> {
+        let (rows, value) = pack(bytes, message);
+        let accs = self.spread.combine(layouter, &rows, &[])?;
+        let (dense, spread) = accs[rows.len()].clone();
+        Ok(Lane {
+            value,
+            dense,
+            spread,
+        })
+    }
+
+    /// Loads `count` zero lanes as constants.
+    fn zeros(&self, mut layouter: impl Layouter<F>, count: usize) -> Result<Vec<Lane<F>>, Error> {
+        let config = self.spread.config();
+        layouter.assign_region(
+            || "zeros",
+            |mut region| {
+                (0..count)
+                    .map(|offset| {
+                        Ok(Lane {
+                            value: Value::known(0),
+                            dense: region.assign_advice_from_constant(
+                                || "zero",
+                                config.dense,
+                                offset,
+                                F::ZERO,
+                            )?,
+                            spread: region.assign_advice_from_constant(
+                                || "zero spread",
+                                config.spread,
+                                offset,
+                                F::ZERO,
+                            )?,
+                        })
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    /// Splits `total`, which must equal the sum of the weighted spread `terms`, and
+    /// returns the lane of its even bits, or of its odd bits if `odd`.
+    fn normalize(
+        &self,
+        layouter: impl Layouter<F>,
+        terms: Vec<(Input<'_, F>, F)>,
+        total: Value<u128>,
+        odd: bool,
+    ) -> Result<Lane<F>, Error> {
+        let (even_bits, odd_bits) = total.map(split_spread_lane).unzip();
+        let (kept, other) = if odd {
+            (odd_bits, even_bits)
+        } else {
+            (even_bits, odd_bits)
+        };
+        // `spread(e) + 2 * spread(o)`, halved when keeping the odd bits so that the
+        // kept spread comes with a coefficient of one.
+        let (other_coeff, scale) = if odd {
+            (F::TWO_INV, F::TWO_INV)
+        } else {
+            (F::from(2), F::ONE)
+        };
+
+        let mut rows = lane_bytes(kept, F::ONE, F::ONE);
+        let result = rows.len();
+        rows.extend(lane_bytes(other, F::ZERO, other_coeff));
+        rows.extend(
+            terms
+                .into_iter()
+                .map(|(input, coeff)| Row::Spread(input, -coeff * scale)),
+        );
+
+        let accs = self
+            .spread
+            .combine(layouter, &rows, &[Zero::Spread(rows.len())])?;
+        let (dense, spread) = accs[result].clone();
+        Ok(Lane {
+            value: kept,
+            dense,
+            spread,
+        })
+    }
+
+    /// The XOR of `lanes` and `constant`, for at most three operands.
+    pub fn xor(
+        &self,
+        layouter: impl Layouter<F>,
+        lanes: &[&SpreadLane<F>],
+        constant: Option<u64>,
+    ) -> Result<Lane<F>, Error> {
+        assert!(
+            lanes.len() + constant.is_some() as usize <= 3,
+            "the spreads of more than three lanes overflow into the odd bits"
+        );
+        let mut terms: Vec<_> = lanes
+            .iter()
+            .map(|lane| (Input::Cell(&lane.spread), F::ONE))
+            .collect();
+        terms.extend(
+            constant.map(|constant| (Input::Constant(F::from_u128(spread_lane(constant))), F::ONE)),
+        );
+        let total = lanes.iter().fold(
+            Value::known(constant.map_or(0, spread_lane)),
+            |total, lane| total + lane.value.map(spread_lane),
+        );
+        self.normalize(layouter, terms, total, false)
+    }
+
+    /// `!x & y`, with the spread of `!x` taken as `spread(2^64 - 1) - spread(x)`.
+    pub fn and_not(
+        &self,
+        layouter: impl Layouter<F>,
+        x: &SpreadLane<F>,
+        y: &SpreadLane<F>,
+    ) -> Result<Lane<F>, Error> {
+        let ones = spread_lane(u64::MAX);
+        let terms = vec![
+            (Input::Constant(F::from_u128(ones)), F::ONE),
+            (Input::Cell(&x.spread), -F::ONE),
+            (Input::Cell(&y.spread), F::ONE),
+        ];
+        let total = x
+            .value
+            .zip(y.value)
+            .map(|(x, y)| spread_lane(!x) + spread_lane(y));
+        self.normalize(layouter, terms, total, true)
+    }
+
+    /// `x` rotated left by `rotation`.
+    ///
+    /// `x` is cut into chunks at `64 - rotation` and at every byte, so that each chunk
+    /// lands whole in the rotated lane, and the spreads of the chunks are summed with
+    /// their rotated weights. Only the spread of the result is available.
+    pub fn rotate_left(
+        &self,
+        layouter: impl Layouter<F>,
+        x: &Lane<F>,
+        rotation: u32,
+    ) -> Result<SpreadLane<F>, Error> {
+        if rotation == 0 {
+            return Ok(x.to_spread());
+        }
+
+        let mut rows: Vec<_> = boundaries(rotation)
+            .windows(2)
+            .map(|bounds| {
+                let (offset, bits) = (bounds[0], bounds[1] - bounds[0]);
+                Row::Piece {
+                    bits: bits as usize,
+                    value: x.value.map(|x| (x >> offset) & ((1 << bits) - 1)),
+                    coeff_dense: pow2(offset),
+                    coeff_spread: pow2(2 * ((offset + rotation) % 64)),
+                }
+            })
+            .collect();
+        rows.push(Row::Dense(Input::Cell(&x.dense), -F::ONE));
+
+        let accs = self
+            .spread
+            .combine(layouter, &rows, &[Zero::Dense(rows.len())])?;
+        Ok(SpreadLane {
+            value: x.value.map(|x| x.rotate_left(rotation)),
+            spread: accs[rows.len()].1.clone(),
+        })
+    }
+
+    /// Keccak-f[1600] applied to `state`, by `[x][y]`.
+    pub fn permute(
+        &self,
+        mut layouter: impl Layouter<F>,
+        state: [[Lane<F>; 5]; 5],
+    ) -> Result<[[Lane<F>; 5]; 5], Error> {
+        let mut a = state;
+        for (round, rc) in RC.iter().enumerate() {
+            // Theta: the column parities `c`, and each lane XORed with `c[x - 1]` and
+            // `c[x + 1]` rotated by one.
+            let mut c = Vec::with_capacity(5);
+            for (x, column) in a.iter().enumerate() {
+                let partial = self.xor(
+                    layouter.namespace(|| format!("round {} c{}", round, x)),
+                    &[
+                        &column[0].to_spread(),
+                        &column[1].to_spread(),
+                        &column[2].to_spread(),
+                    ],
+                    None,
+                )?;
+                c.push(self.xor(
+                    layouter.namespace(|| format!("round {} c{}", round, x)),
+                    &[
+                        &partial.to_spread(),
+                        &column[3].to_spread(),
+                        &column[4].to_spread(),
+                    ],
+                    None,
+                )?);
+            }
+            let rotated_c = c
+                .iter()
+                .enumerate()
+                .map(|(x, c)| {
+                    self.rotate_left(
+                        layouter.namespace(|| format!("round {} d{}", round, x)),
+                        c,
+                        1,
+                    )
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            let theta = by_lane(|x, y| {
+                self.xor(
+                    layouter.namespace(|| format!("round {} theta {} {}", round, x, y)),
+                    &[
+                        &a[x][y].to_spread(),
+                        &c[(x + 4) % 5].to_spread(),
+                        &rotated_c[(x + 1) % 5],
+                    ],
+                    None,
+                )
+            })?;
+
+            // Rho and pi.
+            let rotated = by_lane(|x, y| {
+                self.rotate_left(
+                    layouter.namespace(|| format!("round {} rho {} {}", round, x, y)),
+                    &theta[x][y],
+                    ROTATIONS[x][y],
+                )
+            })?;
+            let b = by_lane(|x, y| {
+                // `b[x][y]` is `rotated[x'][y']` with `x = y'` and `y = 2x' + 3y'`.
+                let x_prime = (x + 3 * y) % 5;
+                Ok(rotated[x_prime][x].clone())
+            })?;
+
+            // Chi, with iota on the first lane.
+            a = by_lane(|x, y| {
+                let name = || format!("round {} chi {} {}", round, x, y);
+                let and_not = self.and_not(
+                    layouter.namespace(name),
+                    &b[(x + 1) % 5][y],
+                    &b[(x + 2) % 5][y],
+                )?;
+                let constant = (x == 0 && y == 0).then_some(*rc);
+                self.xor(
+                    layouter.namespace(name),
+                    &[&b[x][y], &and_not.to_spread()],
+                    constant,
+                )
+            })?;
+        }
+        Ok(a)
+    }
+
+    /// The digest of `message`, as little-endian lanes. The message length, and so the
+    /// number of permutations, is fixed by the circuit.
+    pub fn digest(
+        &self,
+        mut layouter: impl Layouter<F>,
+        message: &[Value<u8>],
+    ) -> Result<[Lane<F>; DIGEST_LANES], Error> {
+        let lanes = message_lanes(message.len())
+            .enumerate()
+            .map(|(i, bytes)| {
+                self.message_lane(layouter.namespace(|| format!("m{}", i)), &bytes, message)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let mut blocks = lanes.chunks(RATE_LANES);
+        // The first block goes into the zero state as is.
+        let mut state: Vec<Lane<F>> = blocks.next().unwrap().to_vec();
+        state.extend(self.zeros(layouter.namespace(|| "capacity"), 25 - RATE_LANES)?);
+        let mut state = by_lane(|x, y| Ok(state[x + 5 * y].clone()))?;
+        state = self.permute(layouter.namespace(|| "block 0"), state)?;
+
+        for (i, block) in blocks.enumerate() {
+            let absorbed = by_lane(|x, y| {
+                let lane = &state[x][y];
+                match block.get(x + 5 * y) {
+                    Some(word) => self.xor(
+                        layouter.namespace(|| format!("block {} absorb {} {}", i + 1, x, y)),
+                        &[&lane.to_spread(), &word.to_spread()],
+                        None,
+                    ),
+                    None => Ok(lane.clone()),
+                }
+            })?;
+            state = self.permute(layouter.namespace(|| format!("block {}", i + 1)), absorbed)?;
+        }
+
+        Ok([0, 1, 2, 3].map(|x| state[x][0].clone()))
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+/// Rows of the operations laid out by [`KeccakChip`], each one more than its pieces
+/// and terms for the final accumulators.
+mod rows {
+    use super::*;
+
+    pub(super) fn normalize(terms: usize) -> usize {
+        16 + terms + 1
+    }
+
+    pub(super) fn rotate_left(rotation: u32) -> usize {
+        if rotation == 0 {
+            return 0;
+        }
+        (boundaries(rotation).len() - 1) + 1 + 1
+    }
+
+    /// One permutation.
+    pub(super) fn permutation() -> usize {
+        let theta = 5 * 2 * normalize(3) + 5 * rotate_left(1) + 25 * normalize(3);
+        let rho: usize = ROTATIONS.iter().flatten().map(|r| rotate_left(*r)).sum();
+        let chi = 25 * normalize(3) + 24 * normalize(2) + normalize(3);
+        ROUNDS * (theta + rho + chi)
+    }
+
+    /// Absorbing a block after the first.
+    pub(super) fn absorb() -> usize {
+        RATE_LANES * normalize(2)
+    }
+
+    /// The message lanes of a `len`-byte message: one row per message byte, two for
+    /// the padding constants of a lane, if any.
+    pub(super) fn message<F: PrimeFieldBits>(len: usize) -> usize {
+        let message = vec![Value::unknown(); len];
+        message_lanes(len)
+            .map(|bytes| pack::<F>(&bytes, &message).0.len() + 1)
+            .sum()
+    }
+}
+
+/// Columns and rows used by [`KeccakCircuit`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeccakCost {
+    pub advice_columns: usize,
+    /// Fixed columns, lookup table columns and selectors included.
+    pub fixed_columns: usize,
+    pub lookups: usize,
+    pub table_rows: usize,
+    pub rows_per_permutation: usize,
+    /// Rows of a message of `len` bytes, the zero capacity included.
+    pub rows: usize,
+}
+
+impl KeccakCost {
+    pub fn measure<F: PrimeFieldBits>(len: usize) -> Self {
+        let mut meta = ConstraintSystem::<F>::default();
+        let config = KeccakChip::configure(&mut meta);
+        let blocks = padding(len).len() / (8 * RATE_LANES);
+
+        Self {
+            advice_columns: meta.num_advice_columns(),
+            fixed_columns: meta.num_fixed_columns() + meta.num_selectors(),
+            lookups: meta.lookups().len(),
+            table_rows: config.spread.table.num_rows(),
+            rows_per_permutation: rows::permutation(),
+            rows: rows::message::<F>(len)
+                + (25 - RATE_LANES)
+                + (blocks - 1) * rows::absorb()
+                + blocks * rows::permutation(),
+        }
+    }
+}
+
+/// Hashes a private message of public length.
+///
+/// Instance layout: `[l0, .., l3]`, the digest as little-endian lanes.
+#[derive(Clone, Debug)]
+pub struct KeccakCircuit {
+    pub message: Vec<Value<u8>>,
+}
+
+impl KeccakCircuit {
+    pub fn new(message: &[u8]) -> Self {
+        Self {
+            message: message.iter().copied().map(Value::known).collect(),
+        }
+    }
+
+    /// The public inputs matching `message`.
+    pub fn instances<F: PrimeField>(message: &[u8]) -> Vec<Vec<F>> {
+        vec![super::digest_lanes(message)
+            .iter()
+            .map(|lane| F::from(*lane))
+            .collect()]
+    }
+}
+
+impl<F: PrimeFieldBits> Circuit<F> for KeccakCircuit {
+    type Config = KeccakConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            message: vec![Value::unknown(); self.message.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        KeccakChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = KeccakChip::construct(config);
+        chip.load_table(&mut layouter)?;
+
+        let digest = chip.digest(layouter.namespace(|| "keccak256"), &self.message)?;
+        for (row, lane) in digest.iter().enumerate() {
+            chip.expose_public(layouter.namespace(|| "digest"), &lane.dense, row)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keccak::VECTORS;
+    use halo2_proofs::dev::MockProver;
+    use halo2curves::bn256::Fr;
+    use halo2curves::pasta::Fp;
+
+    /// One permutation takes 47832 rows.
+    const K: u32 = 16;
+
+    #[test]
+    fn keccak_chip_vectors() {
+        for (message, _) in VECTORS {
+            let circuit = KeccakCircuit::new(message);
+            let instances = KeccakCircuit::instances::<Fp>(message);
+
+            let prover = MockProver::run(K, &circuit, instances).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn keccak_chip_bn256() {
+        let message = b"abc";
+        let circuit = KeccakCircuit::new(message);
+        let prover = MockProver::run(K, &circuit, KeccakCircuit::instances::<Fr>(message)).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    #[ignore = "takes a while"]
+    fn keccak_chip_two_blocks() {
+        let message = [0x5a; 136];
+        let circuit = KeccakCircuit::new(&message);
+        let prover =
+            MockProver::run(K + 1, &circuit, KeccakCircuit::instances::<Fp>(&message)).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn keccak_chip_rejects() {
+        let message = b"abc";
+        let circuit = KeccakCircuit::new(message);
+
+        // A wrong digest lane.
+        let mut wrong = KeccakCircuit::instances::<Fp>(message);
+        wrong[0][3] += Fp::from(1);
+        let prover = MockProver::run(K, &circuit, wrong).unwrap();
+        assert!(prover.verify().is_err());
+
+        // The digest of another message of the same length.
+        let circuit = KeccakCircuit::new(b"abd");
+        let instances = KeccakCircuit::instances::<Fp>(message);
+        let prover = MockProver::run(K, &circuit, instances).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn keccak_chip_cost() {
+        let cost = KeccakCost::measure::<Fp>(0);
+
+        assert_eq!(cost.advice_columns, 4);
+        // Four fixed columns and two selectors in the spread chip, three in its table.
+        assert_eq!(cost.fixed_columns, 4 + 3 + 2);
+        assert_eq!(cost.lookups, 1);
+        assert_eq!(cost.table_rows, 511);
+        assert_eq!(cost.rows_per_permutation, 47832);
+        // Lanes with padding constants, zero lanes, and the zero capacity.
+        assert_eq!(cost.rows, 2 * 3 + 15 + 8 + 47832);
+        assert!(cost.rows < 1 << K);
+
+        let cost = KeccakCost::measure::<Fp>(136);
+        assert_eq!(cost.rows, 17 * 9 + 2 * 3 + 15 + 8 + 17 * 19 + 2 * 47832);
+        assert!(cost.rows < 1 << (K + 1));
+    }
+}
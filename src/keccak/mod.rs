@@ -0,0 +1,257 @@
+//! Keccak-256 over the spread lookup table.
+//!
+//! [`KeccakChip`] applies Keccak-f[1600] to a state of 25 lanes of 64 bits, absorbing
+//! the padded message 136 bytes at a time, and [`KeccakCircuit`] exposes the digest of
+//! a private message as four public little-endian lanes. As for
+//! [`sha256`](crate::sha256), the message length is fixed at synthesis, so the
+//! [`padding`] is made of constants and only the message bytes are private.
+//!
+//! This is the original Keccak padding used by Ethereum, not the SHA-3 one: the
+//! digest of the empty message is `c5d24601...`.
+//!
+//! Lanes are kept with their spread (see [`spread_lane`]). XOR is the even bits of a
+//! sum of up to three spreads, `!x & y` the odd bits of `spread(!x) + spread(y)`, and
+//! rotations reweigh the spreads of chunks of the lane.
+
+use crate::range_check::spread::PaddedByte;
+use crate::range_check::table::spread;
+
+mod chip;
+
+pub use chip::{KeccakChip, KeccakCircuit, KeccakConfig, KeccakCost, Lane, SpreadLane};
+
+/// Bytes absorbed per permutation.
+pub const RATE: usize = 136;
+pub const RATE_LANES: usize = RATE / 8;
+pub const ROUNDS: usize = 24;
+pub const DIGEST_LANES: usize = 4;
+
+/// Round constants of the iota step.
+pub const RC: [u64; ROUNDS] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+/// Left rotations of the rho step, by `[x][y]`.
+pub const ROTATIONS: [[u32; 5]; 5] = [
+    [0, 36, 3, 41, 18],
+    [1, 44, 10, 45, 2],
+    [62, 6, 43, 15, 61],
+    [28, 55, 25, 21, 56],
+    [27, 20, 39, 8, 14],
+];
+
+/// The state, by `[x][y]`. Lane `i` of a block is `[i % 5][i / 5]`.
+pub type State = [[u64; 5]; 5];
+
+/// The message of `len` bytes followed by a one bit, zeros and a final one bit, up
+/// to a multiple of the rate, byte by byte.
+pub fn padding(len: usize) -> Vec<PaddedByte> {
+    let mut bytes: Vec<_> = (0..len).map(PaddedByte::Message).collect();
+    let start = bytes.len();
+    bytes.push(PaddedByte::Padding(0x01));
+    while bytes.len() % RATE != 0 {
+        bytes.push(PaddedByte::Padding(0));
+    }
+    // A single padding byte carries both bits.
+    let last = bytes.len() - 1;
+    let first = if last == start { 0x01 } else { 0 };
+    bytes[last] = PaddedByte::Padding(first | 0x80);
+    bytes
+}
+
+/// Pads `message` and splits it into blocks of little-endian lanes.
+pub fn pad(message: &[u8]) -> Vec<[u64; RATE_LANES]> {
+    let bytes: Vec<u8> = padding(message.len())
+        .into_iter()
+        .map(|byte| match byte {
+            PaddedByte::Message(index) => message[index],
+            PaddedByte::Padding(byte) => byte,
+        })
+        .collect();
+
+    bytes
+        .chunks(RATE)
+        .map(|block| {
+            let mut lanes = [0; RATE_LANES];
+            for (lane, chunk) in lanes.iter_mut().zip(block.chunks(8)) {
+                *lane = u64::from_le_bytes(chunk.try_into().unwrap());
+            }
+            lanes
+        })
+        .collect()
+}
+
+/// Native reference: Keccak-f[1600].
+pub fn keccak_f(state: &mut State) {
+    for rc in RC {
+        // Theta.
+        let c: Vec<u64> = state
+            .iter()
+            .map(|column| column.iter().fold(0, |acc, lane| acc ^ lane))
+            .collect();
+        for (x, column) in state.iter_mut().enumerate() {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for lane in column.iter_mut() {
+                *lane ^= d;
+            }
+        }
+
+        // Rho and pi.
+        let mut b = [[0; 5]; 5];
+        for (x, column) in state.iter().enumerate() {
+            for (y, lane) in column.iter().enumerate() {
+                b[y][(2 * x + 3 * y) % 5] = lane.rotate_left(ROTATIONS[x][y]);
+            }
+        }
+
+        // Chi.
+        for (x, column) in state.iter_mut().enumerate() {
+            for (y, lane) in column.iter_mut().enumerate() {
+                *lane = b[x][y] ^ (!b[(x + 1) % 5][y] & b[(x + 2) % 5][y]);
+            }
+        }
+
+        // Iota.
+        state[0][0] ^= rc;
+    }
+}
+
+/// Native reference: the digest of `message` as little-endian lanes.
+pub fn digest_lanes(message: &[u8]) -> [u64; DIGEST_LANES] {
+    let mut state = [[0; 5]; 5];
+    for block in pad(message) {
+        for (i, lane) in block.iter().enumerate() {
+            state[i % 5][i / 5] ^= lane;
+        }
+        keccak_f(&mut state);
+    }
+    [state[0][0], state[1][0], state[2][0], state[3][0]]
+}
+
+/// Native reference: the digest of `message`.
+pub fn keccak256(message: &[u8]) -> [u8; 32] {
+    let mut digest = [0; 32];
+    for (bytes, lane) in digest.chunks_mut(8).zip(digest_lanes(message)) {
+        bytes.copy_from_slice(&lane.to_le_bytes());
+    }
+    digest
+}
+
+/// Spreads a lane, putting bit `i` at bit `2i`.
+pub fn spread_lane(lane: u64) -> u128 {
+    spread(lane & 0xffff_ffff) as u128 | (spread(lane >> 32) as u128) << 64
+}
+
+/// Splits a sum of spread lanes into the lanes of its even and odd bits.
+pub(crate) fn split_spread_lane(total: u128) -> (u64, u64) {
+    let (mut even, mut odd) = (0, 0);
+    for i in 0..64 {
+        even |= (((total >> (2 * i)) & 1) as u64) << i;
+        odd |= (((total >> (2 * i + 1)) & 1) as u64) << i;
+    }
+    debug_assert_eq!(spread_lane(even) + 2 * spread_lane(odd), total);
+    (even, odd)
+}
+
+/// Digests of the empty message and of two short ones.
+#[cfg(test)]
+pub(crate) const VECTORS: [(&[u8], &str); 3] = [
+    (
+        b"",
+        "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+    ),
+    (
+        b"abc",
+        "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45",
+    ),
+    (
+        b"The quick brown fox jumps over the lazy dog",
+        "4d741b6f1eb29cb2a9b9911c82f56fa8d73b04959d3d9d222895df6c0b28aa15",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    #[test]
+    fn keccak256_vectors() {
+        for (message, expected) in VECTORS {
+            assert_eq!(hex(&keccak256(message)), expected);
+        }
+
+        // 135 bytes take one block, 136 two.
+        assert_eq!(
+            hex(&keccak256(&[0; 135])),
+            "29e3704feeca7fb9ba229f0fa04d9b36449cf3ad6e1d85d9cfff3a10df9abc3e"
+        );
+        assert_eq!(
+            hex(&keccak256(&[0; 136])),
+            "3a5912a7c5faa06ee4fe906253e339467a9ce87d533c65be3c15cb231cdb25f9"
+        );
+    }
+
+    #[test]
+    fn keccak_f_zero_state() {
+        // Keccak team reference: Keccak-f[1600] of the zero state.
+        let mut state = [[0; 5]; 5];
+        keccak_f(&mut state);
+        assert_eq!(state[0][0], 0xf1258f7940e1dde7);
+        assert_eq!(state[1][0], 0x84d5ccf933c0478a);
+        assert_eq!(state[4][4], 0xeaf1ff7b5ceca249);
+    }
+
+    #[test]
+    fn keccak_padding() {
+        assert_eq!(pad(b"").len(), 1);
+        assert_eq!(pad(&[0; 135]).len(), 1);
+        assert_eq!(pad(&[0; 136]).len(), 2);
+
+        // Both padding bits in the same byte.
+        assert_eq!(pad(&[0; 135])[0][16], 0x81 << 56);
+
+        let blocks = pad(b"abc");
+        assert_eq!(blocks[0][0], 0x01636261);
+        assert_eq!(blocks[0][16], 0x80 << 56);
+    }
+
+    #[test]
+    fn keccak_split_spread_lane() {
+        let (a, b, c) = (
+            0xdeadbeef01234567u64,
+            0x0123456789abcdefu64,
+            0xffff0000ffff0000u64,
+        );
+        let total = [a, b, c].iter().map(|x| spread_lane(*x)).sum();
+        let (xor, maj) = split_spread_lane(total);
+        assert_eq!(xor, a ^ b ^ c);
+        assert_eq!(maj, (a & b) ^ (a & c) ^ (b & c));
+    }
+}
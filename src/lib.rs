@@ -1,5 +1,18 @@
 pub mod add1;
+pub mod bits;
+pub mod bitwise;
+pub mod circuits;
+pub mod division;
 pub mod fibonacci;
+pub mod fixed_point;
+pub mod fuzz;
+pub mod hash;
 pub mod is_zero;
+pub mod keccak;
+pub mod merkle;
+pub mod model;
 pub mod proof;
 pub mod range_check;
+pub mod sha256;
+pub mod trace;
+pub mod uint64;
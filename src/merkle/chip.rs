@@ -0,0 +1,348 @@
+use ff::PrimeField;
+use halo2_gadgets::poseidon::primitives::Spec;
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+use super::MerklePath;
+use crate::hash::{PoseidonChip, PoseidonConfig, RATE, WIDTH};
+use crate::range_check::example1::bool_check;
+
+// Table setup
+//
+// One swap row per level, followed by the Poseidon hash of `left, right`, whose
+// digest is copied into `node` of the next level.
+//
+//   node  | sibling |  bit  |  left  |  right  |  q_swap  |
+// -----------------------------------------------------------
+//    n    |    s    |   b   | b?s:n  |  b?n:s  |     1    |
+
+#[derive(Clone, Debug)]
+pub struct MerkleConfig<F: PrimeField> {
+    pub node: Column<Advice>,
+    pub sibling: Column<Advice>,
+    pub bit: Column<Advice>,
+    pub left: Column<Advice>,
+    pub right: Column<Advice>,
+    pub q_swap: Selector,
+    pub instance: Column<Instance>,
+    pub poseidon: PoseidonConfig<F>,
+}
+
+/// Recomputes a Merkle root from a leaf and its path, hashing with the spec `S`.
+#[derive(Clone, Debug)]
+pub struct MerkleChip<F: PrimeField, S: Spec<F, WIDTH, RATE>> {
+    config: MerkleConfig<F>,
+    _spec: PhantomData<S>,
+}
+
+impl<F: PrimeField, S: Spec<F, WIDTH, RATE>> MerkleChip<F, S> {
+    pub fn construct(config: MerkleConfig<F>) -> Self {
+        Self {
+            config,
+            _spec: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> MerkleConfig<F> {
+        let node = meta.advice_column();
+        let sibling = meta.advice_column();
+        let bit = meta.advice_column();
+        let left = meta.advice_column();
+        let right = meta.advice_column();
+        let q_swap = meta.selector();
+        let instance = meta.instance_column();
+
+        for column in [node, left, right] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+
+        meta.create_gate("merkle swap", |meta| {
+            let q_swap = meta.query_selector(q_swap);
+            let node = meta.query_advice(node, Rotation::cur());
+            let sibling = meta.query_advice(sibling, Rotation::cur());
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let left = meta.query_advice(left, Rotation::cur());
+            let right = meta.query_advice(right, Rotation::cur());
+
+            Constraints::with_selector(
+                q_swap,
+                [
+                    ("bit is boolean", bool_check(bit.clone())),
+                    (
+                        "left",
+                        left - (node.clone() + bit.clone() * (sibling.clone() - node.clone())),
+                    ),
+                    ("right", right - (sibling.clone() + bit * (node - sibling))),
+                ],
+            )
+        });
+
+        let poseidon = PoseidonChip::<F, S>::configure(meta);
+
+        MerkleConfig {
+            node,
+            sibling,
+            bit,
+            left,
+            right,
+            q_swap,
+            instance,
+            poseidon,
+        }
+    }
+
+    /// Orders `node` and `sibling` by `bit` in one swap row.
+    fn swap(
+        &self,
+        mut layouter: impl Layouter<F>,
+        node: Node<'_, F>,
+        sibling: Value<F>,
+        bit: Value<F>,
+    ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        layouter.assign_region(
+            || "merkle swap",
+            |mut region| {
+                self.config.q_swap.enable(&mut region, 0)?;
+
+                let node = match node {
+                    Node::Leaf(value) => {
+                        region.assign_advice(|| "leaf", self.config.node, 0, || value)?
+                    }
+                    Node::Digest(cell) => {
+                        cell.copy_advice(|| "node", &mut region, self.config.node, 0)?
+                    }
+                };
+                region.assign_advice(|| "sibling", self.config.sibling, 0, || sibling)?;
+                region.assign_advice(|| "bit", self.config.bit, 0, || bit)?;
+
+                let swapped = node
+                    .value()
+                    .zip(sibling)
+                    .zip(bit)
+                    .map(|((node, sibling), bit)| {
+                        if bit == F::ZERO {
+                            (*node, sibling)
+                        } else {
+                            (sibling, *node)
+                        }
+                    });
+                let (left, right) = swapped.unzip();
+
+                let left = region.assign_advice(|| "left", self.config.left, 0, || left)?;
+                let right = region.assign_advice(|| "right", self.config.right, 0, || right)?;
+                Ok((left, right))
+            },
+        )
+    }
+
+    /// Computes the root above a private `leaf` given its `siblings` and the `bits` of
+    /// its index, bottom level first.
+    pub fn root(
+        &self,
+        mut layouter: impl Layouter<F>,
+        leaf: Value<F>,
+        siblings: &[Value<F>],
+        bits: &[Value<F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert_eq!(siblings.len(), bits.len(), "one bit per sibling");
+
+        let poseidon = PoseidonChip::<F, S>::construct(self.config.poseidon.clone());
+
+        let mut digest: Option<AssignedCell<F, F>> = None;
+        for (level, (sibling, bit)) in siblings.iter().zip(bits.iter()).enumerate() {
+            let node = match &digest {
+                Some(cell) => Node::Digest(cell),
+                None => Node::Leaf(leaf),
+            };
+            let (left, right) = self.swap(
+                layouter.namespace(|| format!("swap {}", level)),
+                node,
+                *sibling,
+                *bit,
+            )?;
+            digest = Some(poseidon.hash_constant(
+                layouter.namespace(|| format!("hash {}", level)),
+                &[left, right],
+            )?);
+        }
+
+        match digest {
+            Some(root) => Ok(root),
+            // A tree of depth zero is its own leaf.
+            None => layouter.assign_region(
+                || "leaf",
+                |mut region| region.assign_advice(|| "leaf", self.config.node, 0, || leaf),
+            ),
+        }
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+/// The node entering a swap row: the private leaf, or the digest of the level below.
+#[derive(Clone, Copy)]
+enum Node<'a, F: PrimeField> {
+    Leaf(Value<F>),
+    Digest(&'a AssignedCell<F, F>),
+}
+
+/// Proves that a private leaf belongs to the tree with a public root. The depth is the
+/// number of siblings.
+///
+/// Instance layout: `[root]`.
+#[derive(Debug)]
+pub struct MerkleCircuit<F: PrimeField, S: Spec<F, WIDTH, RATE>> {
+    pub leaf: Value<F>,
+    pub siblings: Vec<Value<F>>,
+    /// Index bits, bottom level first. Only 0 and 1 satisfy the circuit.
+    pub bits: Vec<Value<F>>,
+    pub _spec: PhantomData<S>,
+}
+
+impl<F: PrimeField, S: Spec<F, WIDTH, RATE>> MerkleCircuit<F, S> {
+    pub fn new(leaf: F, path: &MerklePath<F>) -> Self {
+        Self {
+            leaf: Value::known(leaf),
+            siblings: path.siblings.iter().copied().map(Value::known).collect(),
+            bits: (0..path.depth())
+                .map(|level| Value::known(F::from(path.bit(level) as u64)))
+                .collect(),
+            _spec: PhantomData,
+        }
+    }
+}
+
+// Manual impl: a derive would require `S: Clone`, which the gadget specs don't have.
+impl<F: PrimeField, S: Spec<F, WIDTH, RATE>> Clone for MerkleCircuit<F, S> {
+    fn clone(&self) -> Self {
+        Self {
+            leaf: self.leaf,
+            siblings: self.siblings.clone(),
+            bits: self.bits.clone(),
+            _spec: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, S: Spec<F, WIDTH, RATE>> Circuit<F> for MerkleCircuit<F, S> {
+    type Config = MerkleConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            leaf: Value::unknown(),
+            siblings: vec![Value::unknown(); self.siblings.len()],
+            bits: vec![Value::unknown(); self.bits.len()],
+            _spec: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        MerkleChip::<F, S>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = MerkleChip::<F, S>::construct(config);
+
+        let root = chip.root(
+            layouter.namespace(|| "path"),
+            self.leaf,
+            &self.siblings,
+            &self.bits,
+        )?;
+        chip.expose_public(layouter.namespace(|| "root"), &root, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::{P128Pow5T3, P128Pow5T3Bn256};
+    use crate::merkle::MerkleTree;
+    use halo2_proofs::dev::{metadata, MockProver, VerifyFailure};
+    use halo2curves::bn256::Fr;
+    use halo2curves::pasta::Fp;
+
+    const K: u32 = 10;
+
+    #[test]
+    fn merkle_chip_membership() {
+        let leaves: Vec<Fp> = (10..15).map(Fp::from).collect();
+        let tree = MerkleTree::<Fp, P128Pow5T3>::new(3, &leaves);
+
+        for index in 0..8 {
+            let circuit = MerkleCircuit::<Fp, P128Pow5T3>::new(tree.leaf(index), &tree.path(index));
+
+            let prover = MockProver::run(K, &circuit, vec![vec![tree.root()]]).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn merkle_chip_bn256() {
+        let leaves: Vec<Fr> = (10..15).map(Fr::from).collect();
+        let tree = MerkleTree::<Fr, P128Pow5T3Bn256>::new(3, &leaves);
+
+        let circuit = MerkleCircuit::<Fr, P128Pow5T3Bn256>::new(tree.leaf(2), &tree.path(2));
+        let prover = MockProver::run(K, &circuit, vec![vec![tree.root()]]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn merkle_chip_rejects() {
+        let leaves: Vec<Fp> = (10..15).map(Fp::from).collect();
+        let tree = MerkleTree::<Fp, P128Pow5T3>::new(3, &leaves);
+        let path = tree.path(3);
+
+        // A leaf that is not in the tree.
+        let circuit = MerkleCircuit::<Fp, P128Pow5T3>::new(Fp::from(42), &path);
+        let prover = MockProver::run(K, &circuit, vec![vec![tree.root()]]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // The right leaf under the wrong root.
+        let circuit = MerkleCircuit::<Fp, P128Pow5T3>::new(tree.leaf(3), &path);
+        let wrong_root = tree.root() + Fp::from(1);
+        let prover = MockProver::run(K, &circuit, vec![vec![wrong_root]]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // A path bit of 2 next to an equal sibling: `left` and `right` both equal the
+        // leaf, which satisfies the swap, so only the boolean check fails.
+        let leaves: Vec<Fp> = [10u64, 11, 12, 12, 14].map(Fp::from).to_vec();
+        let tree = MerkleTree::<Fp, P128Pow5T3>::new(3, &leaves);
+        let mut circuit = MerkleCircuit::<Fp, P128Pow5T3>::new(tree.leaf(3), &tree.path(3));
+        circuit.bits[0] = Value::known(Fp::from(2));
+        let prover = MockProver::run(K, &circuit, vec![vec![tree.root()]]).unwrap();
+        let failures = prover.verify().unwrap_err();
+        assert_eq!(failures.len(), 1, "{:?}", failures);
+        match &failures[0] {
+            VerifyFailure::ConstraintNotSatisfied { constraint, .. } => assert_eq!(
+                *constraint,
+                metadata::Constraint::from(((0, "merkle swap").into(), 0, "bit is boolean"))
+            ),
+            failure => panic!("unexpected failure: {:?}", failure),
+        }
+    }
+
+    #[test]
+    fn merkle_chip_depth_zero() {
+        let leaf = Fp::from(7);
+        let tree = MerkleTree::<Fp, P128Pow5T3>::new(0, &[leaf]);
+        let circuit = MerkleCircuit::<Fp, P128Pow5T3>::new(leaf, &tree.path(0));
+
+        let prover = MockProver::run(K, &circuit, vec![vec![tree.root()]]).unwrap();
+        prover.assert_satisfied();
+    }
+}
@@ -0,0 +1,170 @@
+//! Merkle tree membership over Poseidon.
+//!
+//! [`MerkleTree`] builds a tree natively and hands out the [`MerklePath`] witness of a
+//! leaf. [`MerkleChip`] recomputes the root from a private leaf and path in circuit, and
+//! [`MerkleCircuit`] exposes that root as its only public input.
+//!
+//! Nodes are hashed in the `ConstantLength<2>` domain, one permutation per level.
+
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use halo2_gadgets::poseidon::primitives::Spec;
+
+use crate::hash::{poseidon_constant, RATE, WIDTH};
+
+mod chip;
+
+pub use chip::{MerkleChip, MerkleCircuit, MerkleConfig};
+
+pub mod sparse;
+
+/// Native reference: the parent of two nodes, as hashed by the chips.
+pub fn hash_pair<F: PrimeField, S: Spec<F, WIDTH, RATE>>(left: F, right: F) -> F {
+    poseidon_constant::<F, S, 2>([left, right])
+}
+
+/// The siblings of a leaf from the bottom of the tree up, and its index, whose bit `i`
+/// tells whether the node at level `i` is a right child.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerklePath<F> {
+    pub index: usize,
+    pub siblings: Vec<F>,
+}
+
+impl<F: PrimeField> MerklePath<F> {
+    pub fn depth(&self) -> usize {
+        self.siblings.len()
+    }
+
+    /// Bit `i` of the index, as used by the chip at level `i`.
+    pub fn bit(&self, level: usize) -> bool {
+        (self.index >> level) & 1 == 1
+    }
+
+    /// Native reference: the root reached from `leaf` along this path.
+    pub fn root<S: Spec<F, WIDTH, RATE>>(&self, leaf: F) -> F {
+        self.siblings
+            .iter()
+            .enumerate()
+            .fold(leaf, |node, (level, sibling)| {
+                if self.bit(level) {
+                    hash_pair::<F, S>(*sibling, node)
+                } else {
+                    hash_pair::<F, S>(node, *sibling)
+                }
+            })
+    }
+}
+
+/// A complete binary tree of `2^depth` leaves, missing leaves being zero.
+#[derive(Clone, Debug)]
+pub struct MerkleTree<F: PrimeField, S: Spec<F, WIDTH, RATE>> {
+    /// `levels[0]` holds the leaves and the last level holds the root.
+    levels: Vec<Vec<F>>,
+    _spec: PhantomData<S>,
+}
+
+impl<F: PrimeField, S: Spec<F, WIDTH, RATE>> MerkleTree<F, S> {
+    pub fn new(depth: usize, leaves: &[F]) -> Self {
+        assert!(
+            leaves.len() <= 1 << depth,
+            "{} leaves do not fit in a tree of depth {}",
+            leaves.len(),
+            depth
+        );
+
+        let mut level = leaves.to_vec();
+        level.resize(1 << depth, F::ZERO);
+
+        let mut levels = vec![level];
+        for _ in 0..depth {
+            let parents = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| hash_pair::<F, S>(pair[0], pair[1]))
+                .collect();
+            levels.push(parents);
+        }
+
+        Self {
+            levels,
+            _spec: PhantomData,
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    pub fn root(&self) -> F {
+        self.levels[self.depth()][0]
+    }
+
+    pub fn leaf(&self, index: usize) -> F {
+        self.levels[0][index]
+    }
+
+    /// The path of the leaf at `index`, to witness its membership.
+    pub fn path(&self, index: usize) -> MerklePath<F> {
+        assert!(index < self.levels[0].len(), "leaf index out of range");
+
+        let siblings = self.levels[..self.depth()]
+            .iter()
+            .enumerate()
+            .map(|(level, nodes)| nodes[(index >> level) ^ 1])
+            .collect();
+
+        MerklePath { index, siblings }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::P128Pow5T3;
+    use halo2curves::pasta::Fp;
+
+    #[test]
+    fn merkle_tree_paths() {
+        let leaves: Vec<Fp> = (1..=6).map(Fp::from).collect();
+        let tree = MerkleTree::<Fp, P128Pow5T3>::new(3, &leaves);
+        assert_eq!(tree.depth(), 3);
+
+        for index in 0..8 {
+            let path = tree.path(index);
+            assert_eq!(path.depth(), 3);
+            assert_eq!(path.root::<P128Pow5T3>(tree.leaf(index)), tree.root());
+            assert_ne!(
+                path.root::<P128Pow5T3>(tree.leaf(index) + Fp::from(1)),
+                tree.root()
+            );
+        }
+
+        // Padding leaves are zero, so appending one changes nothing.
+        let mut padded = leaves.clone();
+        padded.push(Fp::from(0));
+        assert_eq!(
+            MerkleTree::<Fp, P128Pow5T3>::new(3, &padded).root(),
+            tree.root()
+        );
+    }
+
+    #[test]
+    fn merkle_tree_root() {
+        let (a, b) = (Fp::from(1), Fp::from(2));
+        let tree = MerkleTree::<Fp, P128Pow5T3>::new(1, &[a, b]);
+        assert_eq!(tree.root(), hash_pair::<Fp, P128Pow5T3>(a, b));
+
+        let tree = MerkleTree::<Fp, P128Pow5T3>::new(0, &[a]);
+        assert_eq!(tree.root(), a);
+        assert_eq!(tree.path(0).siblings, vec![]);
+    }
+
+    #[test]
+    #[should_panic(expected = "do not fit")]
+    fn merkle_tree_too_many_leaves() {
+        MerkleTree::<Fp, P128Pow5T3>::new(1, &[Fp::from(1); 3]);
+    }
+}
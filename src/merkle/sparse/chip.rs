@@ -0,0 +1,496 @@
+use ff::PrimeField;
+use halo2_gadgets::poseidon::primitives::Spec;
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+use super::{SmtOp, SmtUpdate};
+use crate::hash::{PoseidonChip, PoseidonConfig, RATE, WIDTH};
+use crate::range_check::example1::bool_check;
+
+// Table setup
+//
+// The leaf row holds the old and new leaf values, with their inverses when the
+// operation requires them to be nonzero. Then, for each level from the bottom, one
+// row orders the old and new nodes against their shared sibling by the key bit,
+// followed by the two Poseidon hashes whose digests are the next level's nodes.
+//
+// `key` accumulates the key bits from the top: `key = bit + 2 * key_above`, with
+// `key_above` copied into `key` of the level above and zero above the root, so the
+// bottom `key` is the public key and has at most `depth` bits.
+//
+//   old  |  new  | old_inv | new_inv | q_old_nonzero | q_new_nonzero |
+// ---------------------------------------------------------------------
+//   v    |   v'  |  1 / v  |  1 / v' |      0/1      |      0/1      |
+//
+//   old  |  new  | sibling | bit | old_left | old_right | new_left | new_right | key | key_above | q_level |
+// ------------------------------------------------------------------------------------------------------------
+//   n    |   n'  |    s    |  b  |  b?s:n   |   b?n:s   |  b?s:n'  |   b?n':s  |  k  |  k >> 1   |    1    |
+
+#[derive(Clone, Debug)]
+pub struct SmtConfig<F: PrimeField> {
+    pub old: Column<Advice>,
+    pub new: Column<Advice>,
+    pub old_inv: Column<Advice>,
+    pub new_inv: Column<Advice>,
+    pub sibling: Column<Advice>,
+    pub bit: Column<Advice>,
+    pub old_left: Column<Advice>,
+    pub old_right: Column<Advice>,
+    pub new_left: Column<Advice>,
+    pub new_right: Column<Advice>,
+    pub key: Column<Advice>,
+    pub key_above: Column<Advice>,
+    pub q_old_nonzero: Selector,
+    pub q_new_nonzero: Selector,
+    pub q_level: Selector,
+    pub instance: Column<Instance>,
+    pub poseidon: PoseidonConfig<F>,
+}
+
+/// The public cells of an update: `[old_root, new_root, key, new_value]`.
+#[derive(Clone, Debug)]
+pub struct SmtUpdateCells<F: PrimeField> {
+    pub old_root: AssignedCell<F, F>,
+    pub new_root: AssignedCell<F, F>,
+    pub key: AssignedCell<F, F>,
+    pub new_value: AssignedCell<F, F>,
+}
+
+/// Proves a change of one leaf of a sparse Merkle tree, hashing with the spec `S`.
+#[derive(Clone, Debug)]
+pub struct SmtChip<F: PrimeField, S: Spec<F, WIDTH, RATE>> {
+    config: SmtConfig<F>,
+    _spec: PhantomData<S>,
+}
+
+impl<F: PrimeField, S: Spec<F, WIDTH, RATE>> SmtChip<F, S> {
+    pub fn construct(config: SmtConfig<F>) -> Self {
+        Self {
+            config,
+            _spec: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> SmtConfig<F> {
+        let old = meta.advice_column();
+        let new = meta.advice_column();
+        let old_inv = meta.advice_column();
+        let new_inv = meta.advice_column();
+        let sibling = meta.advice_column();
+        let bit = meta.advice_column();
+        let old_left = meta.advice_column();
+        let old_right = meta.advice_column();
+        let new_left = meta.advice_column();
+        let new_right = meta.advice_column();
+        let key = meta.advice_column();
+        let key_above = meta.advice_column();
+        let q_old_nonzero = meta.selector();
+        let q_new_nonzero = meta.selector();
+        let q_level = meta.selector();
+        let instance = meta.instance_column();
+
+        for column in [
+            old, new, old_left, old_right, new_left, new_right, key, key_above,
+        ] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+
+        meta.create_gate("smt leaf", |meta| {
+            let q_old_nonzero = meta.query_selector(q_old_nonzero);
+            let q_new_nonzero = meta.query_selector(q_new_nonzero);
+            let old = meta.query_advice(old, Rotation::cur());
+            let new = meta.query_advice(new, Rotation::cur());
+            let old_inv = meta.query_advice(old_inv, Rotation::cur());
+            let new_inv = meta.query_advice(new_inv, Rotation::cur());
+            let one = Expression::Constant(F::ONE);
+
+            vec![
+                (
+                    "old is nonzero",
+                    q_old_nonzero * (old * old_inv - one.clone()),
+                ),
+                ("new is nonzero", q_new_nonzero * (new * new_inv - one)),
+            ]
+        });
+
+        meta.create_gate("smt level", |meta| {
+            let q_level = meta.query_selector(q_level);
+            let old = meta.query_advice(old, Rotation::cur());
+            let new = meta.query_advice(new, Rotation::cur());
+            let sibling = meta.query_advice(sibling, Rotation::cur());
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let old_left = meta.query_advice(old_left, Rotation::cur());
+            let old_right = meta.query_advice(old_right, Rotation::cur());
+            let new_left = meta.query_advice(new_left, Rotation::cur());
+            let new_right = meta.query_advice(new_right, Rotation::cur());
+            let key = meta.query_advice(key, Rotation::cur());
+            let key_above = meta.query_advice(key_above, Rotation::cur());
+
+            let left = |node: Expression<F>| node.clone() + bit.clone() * (sibling.clone() - node);
+            let right =
+                |node: Expression<F>| sibling.clone() + bit.clone() * (node - sibling.clone());
+
+            Constraints::with_selector(
+                q_level,
+                [
+                    ("bit is boolean", bool_check(bit.clone())),
+                    ("old left", old_left - left(old.clone())),
+                    ("old right", old_right - right(old)),
+                    ("new left", new_left - left(new.clone())),
+                    ("new right", new_right - right(new)),
+                    (
+                        "key bit",
+                        key - (bit.clone() + key_above * Expression::Constant(F::from(2))),
+                    ),
+                ],
+            )
+        });
+
+        let poseidon = PoseidonChip::<F, S>::configure(meta);
+
+        SmtConfig {
+            old,
+            new,
+            old_inv,
+            new_inv,
+            sibling,
+            bit,
+            old_left,
+            old_right,
+            new_left,
+            new_right,
+            key,
+            key_above,
+            q_old_nonzero,
+            q_new_nonzero,
+            q_level,
+            instance,
+            poseidon,
+        }
+    }
+
+    /// Assigns the old and new leaves. A leaf that `op` requires to be absent is the
+    /// constant zero; any other leaf is witnessed along with its inverse.
+    fn assign_leaves(
+        &self,
+        mut layouter: impl Layouter<F>,
+        op: SmtOp,
+        old_value: Value<F>,
+        new_value: Value<F>,
+    ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        let inverse = |value: Value<F>| value.map(|value| value.invert().unwrap_or(F::ZERO));
+
+        layouter.assign_region(
+            || "smt leaf",
+            |mut region| {
+                let old = if op == SmtOp::Insert {
+                    region.assign_advice_from_constant(|| "old", self.config.old, 0, F::ZERO)?
+                } else {
+                    self.config.q_old_nonzero.enable(&mut region, 0)?;
+                    let inv = inverse(old_value);
+                    region.assign_advice(|| "old inv", self.config.old_inv, 0, || inv)?;
+                    region.assign_advice(|| "old", self.config.old, 0, || old_value)?
+                };
+
+                let new = if op == SmtOp::Delete {
+                    region.assign_advice_from_constant(|| "new", self.config.new, 0, F::ZERO)?
+                } else {
+                    self.config.q_new_nonzero.enable(&mut region, 0)?;
+                    let inv = inverse(new_value);
+                    region.assign_advice(|| "new inv", self.config.new_inv, 0, || inv)?;
+                    region.assign_advice(|| "new", self.config.new, 0, || new_value)?
+                };
+
+                Ok((old, new))
+            },
+        )
+    }
+
+    /// Proves that the leaf at a key went from `old_value` to `new_value` under the
+    /// same `siblings`. `bits` are the key bits, bottom level first; `op` fixes which
+    /// of the two leaves must be absent.
+    pub fn update(
+        &self,
+        mut layouter: impl Layouter<F>,
+        op: SmtOp,
+        old_value: Value<F>,
+        new_value: Value<F>,
+        siblings: &[Value<F>],
+        bits: &[Value<F>],
+    ) -> Result<SmtUpdateCells<F>, Error> {
+        assert_eq!(siblings.len(), bits.len(), "one bit per sibling");
+        assert!(!siblings.is_empty(), "the tree needs at least one level");
+
+        let poseidon = PoseidonChip::<F, S>::construct(self.config.poseidon.clone());
+
+        let (mut old, mut new) =
+            self.assign_leaves(layouter.namespace(|| "leaves"), op, old_value, new_value)?;
+        let value = new.clone();
+
+        // The key bits above each level, as an integer: `keys[level]` is the key
+        // shifted right by `level`.
+        let mut keys = vec![Value::known(F::ZERO); bits.len() + 1];
+        for level in (0..bits.len()).rev() {
+            keys[level] = bits[level] + keys[level + 1] * Value::known(F::from(2));
+        }
+
+        let mut key: Option<AssignedCell<F, F>> = None;
+        let mut key_above: Option<AssignedCell<F, F>> = None;
+        for (level, (sibling, bit)) in siblings.iter().zip(bits.iter()).enumerate() {
+            let row = layouter.assign_region(
+                || format!("smt level {}", level),
+                |mut region| {
+                    self.config.q_level.enable(&mut region, 0)?;
+
+                    let old = old.copy_advice(|| "old", &mut region, self.config.old, 0)?;
+                    let new = new.copy_advice(|| "new", &mut region, self.config.new, 0)?;
+                    region.assign_advice(|| "sibling", self.config.sibling, 0, || *sibling)?;
+                    region.assign_advice(|| "bit", self.config.bit, 0, || *bit)?;
+
+                    let key = match &key_above {
+                        Some(cell) => {
+                            cell.copy_advice(|| "key", &mut region, self.config.key, 0)?
+                        }
+                        None => {
+                            region.assign_advice(|| "key", self.config.key, 0, || keys[level])?
+                        }
+                    };
+                    let key_above = region.assign_advice(
+                        || "key above",
+                        self.config.key_above,
+                        0,
+                        || keys[level + 1],
+                    )?;
+                    // Nothing is left of the key above the root.
+                    if level + 1 == bits.len() {
+                        region.constrain_constant(key_above.cell(), F::ZERO)?;
+                    }
+
+                    let ordered = |node: &AssignedCell<F, F>| {
+                        node.value()
+                            .zip(*sibling)
+                            .zip(*bit)
+                            .map(|((node, sibling), bit)| {
+                                if bit == F::ZERO {
+                                    (*node, sibling)
+                                } else {
+                                    (sibling, *node)
+                                }
+                            })
+                            .unzip()
+                    };
+                    let (old_left, old_right) = ordered(&old);
+                    let (new_left, new_right) = ordered(&new);
+
+                    let cells = [
+                        (self.config.old_left, old_left),
+                        (self.config.old_right, old_right),
+                        (self.config.new_left, new_left),
+                        (self.config.new_right, new_right),
+                    ]
+                    .map(|(column, value)| {
+                        region.assign_advice(|| "ordered node", column, 0, || value)
+                    });
+                    let [old_left, old_right, new_left, new_right] = cells;
+
+                    Ok((
+                        key,
+                        key_above,
+                        [old_left?, old_right?],
+                        [new_left?, new_right?],
+                    ))
+                },
+            )?;
+            let (row_key, row_key_above, old_pair, new_pair) = row;
+
+            old = poseidon.hash_constant(
+                layouter.namespace(|| format!("old hash {}", level)),
+                &old_pair,
+            )?;
+            new = poseidon.hash_constant(
+                layouter.namespace(|| format!("new hash {}", level)),
+                &new_pair,
+            )?;
+
+            if key.is_none() {
+                key = Some(row_key);
+            }
+            key_above = Some(row_key_above);
+        }
+
+        Ok(SmtUpdateCells {
+            old_root: old,
+            new_root: new,
+            key: key.expect("at least one level"),
+            new_value: value,
+        })
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+/// Proves one insert, update or delete in a sparse Merkle tree. The depth is the
+/// number of siblings and `op` is fixed at keygen.
+///
+/// Instance layout: `[old_root, new_root, key, new_value]`.
+#[derive(Debug)]
+pub struct SmtUpdateCircuit<F: PrimeField, S: Spec<F, WIDTH, RATE>> {
+    pub op: SmtOp,
+    pub old_value: Value<F>,
+    pub new_value: Value<F>,
+    pub siblings: Vec<Value<F>>,
+    /// Key bits, bottom level first.
+    pub bits: Vec<Value<F>>,
+    pub _spec: PhantomData<S>,
+}
+
+impl<F: PrimeField, S: Spec<F, WIDTH, RATE>> SmtUpdateCircuit<F, S> {
+    pub fn new(update: &SmtUpdate<F>) -> Self {
+        let path = &update.path;
+        Self {
+            op: update.op,
+            old_value: Value::known(update.old_value),
+            new_value: Value::known(update.new_value),
+            siblings: path.siblings.iter().copied().map(Value::known).collect(),
+            bits: (0..path.depth())
+                .map(|level| Value::known(F::from(path.bit(level) as u64)))
+                .collect(),
+            _spec: PhantomData,
+        }
+    }
+}
+
+// Manual impl: a derive would require `S: Clone`, which the gadget specs don't have.
+impl<F: PrimeField, S: Spec<F, WIDTH, RATE>> Clone for SmtUpdateCircuit<F, S> {
+    fn clone(&self) -> Self {
+        Self {
+            op: self.op,
+            old_value: self.old_value,
+            new_value: self.new_value,
+            siblings: self.siblings.clone(),
+            bits: self.bits.clone(),
+            _spec: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, S: Spec<F, WIDTH, RATE>> Circuit<F> for SmtUpdateCircuit<F, S> {
+    type Config = SmtConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            op: self.op,
+            old_value: Value::unknown(),
+            new_value: Value::unknown(),
+            siblings: vec![Value::unknown(); self.siblings.len()],
+            bits: vec![Value::unknown(); self.bits.len()],
+            _spec: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        SmtChip::<F, S>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = SmtChip::<F, S>::construct(config);
+
+        let cells = chip.update(
+            layouter.namespace(|| "update"),
+            self.op,
+            self.old_value,
+            self.new_value,
+            &self.siblings,
+            &self.bits,
+        )?;
+
+        chip.expose_public(layouter.namespace(|| "old root"), &cells.old_root, 0)?;
+        chip.expose_public(layouter.namespace(|| "new root"), &cells.new_root, 1)?;
+        chip.expose_public(layouter.namespace(|| "key"), &cells.key, 2)?;
+        chip.expose_public(layouter.namespace(|| "new value"), &cells.new_value, 3)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::{P128Pow5T3, P128Pow5T3Bn256};
+    use crate::merkle::sparse::SparseMerkleTree;
+    use halo2_proofs::dev::MockProver;
+    use halo2curves::bn256::Fr;
+    use halo2curves::pasta::Fp;
+
+    const K: u32 = 10;
+    const DEPTH: usize = 4;
+
+    fn prove(update: &SmtUpdate<Fp>) -> MockProver<Fp> {
+        let circuit = SmtUpdateCircuit::<Fp, P128Pow5T3>::new(update);
+        MockProver::run(K, &circuit, update.instances()).unwrap()
+    }
+
+    #[test]
+    fn smt_chip_operations() {
+        let mut smt = SparseMerkleTree::<Fp, P128Pow5T3>::new(DEPTH);
+        smt.insert(3, Fp::from(30)).unwrap();
+
+        let updates = [
+            smt.insert(12, Fp::from(120)).unwrap(),
+            smt.update(3, Fp::from(31)).unwrap(),
+            smt.delete(12).unwrap(),
+        ];
+        for update in updates.iter() {
+            prove(update).assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn smt_chip_bn256() {
+        let mut smt = SparseMerkleTree::<Fr, P128Pow5T3Bn256>::new(DEPTH);
+        let update = smt.insert(5, Fr::from(1)).unwrap();
+
+        let circuit = SmtUpdateCircuit::<Fr, P128Pow5T3Bn256>::new(&update);
+        let prover = MockProver::run(K, &circuit, update.instances()).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn smt_chip_rejects() {
+        let mut smt = SparseMerkleTree::<Fp, P128Pow5T3>::new(DEPTH);
+        smt.insert(3, Fp::from(30)).unwrap();
+        let insert = smt.insert(12, Fp::from(120)).unwrap();
+
+        // Wrong public new root, key or value.
+        for row in 1..4 {
+            let mut instances = insert.instances();
+            instances[0][row] += Fp::from(1);
+            let circuit = SmtUpdateCircuit::<Fp, P128Pow5T3>::new(&insert);
+            let prover = MockProver::run(K, &circuit, instances).unwrap();
+            assert!(prover.verify().is_err());
+        }
+
+        // An insert over a present key: the old leaf is not the empty one.
+        let mut update = smt.update(3, Fp::from(31)).unwrap();
+        update.op = SmtOp::Insert;
+        assert!(prove(&update).verify().is_err());
+
+        // Inserting zero would leave the tree unchanged.
+        let mut zero = insert.clone();
+        zero.new_value = Fp::from(0);
+        zero.new_root = zero.old_root;
+        assert!(prove(&zero).verify().is_err());
+    }
+}
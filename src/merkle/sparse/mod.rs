@@ -0,0 +1,305 @@
+//! Sparse Merkle trees keyed by the leaf index.
+//!
+//! A [`SparseMerkleTree`] of depth `d` has a leaf for every key below `2^d`. Absent
+//! keys hold zero, so every subtree with no keys in it hashes to a precomputed
+//! default and only the nodes above present keys are stored. Each operation returns
+//! the [`SmtUpdate`] witness that [`SmtUpdateCircuit`] proves.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use halo2_gadgets::poseidon::primitives::Spec;
+
+use super::{hash_pair, MerklePath};
+use crate::hash::{RATE, WIDTH};
+
+mod chip;
+
+pub use chip::{SmtChip, SmtConfig, SmtUpdateCells, SmtUpdateCircuit};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SmtError {
+    /// The key has more bits than the tree is deep.
+    KeyOutOfRange(u64),
+    /// Insert of a key that is already present.
+    KeyPresent(u64),
+    /// Update or delete of a key that is absent.
+    KeyAbsent(u64),
+    /// Zero is the value of absent keys, so it cannot be stored.
+    ZeroValue,
+}
+
+impl fmt::Display for SmtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SmtError::KeyOutOfRange(key) => write!(f, "key {} is out of range", key),
+            SmtError::KeyPresent(key) => write!(f, "key {} is already present", key),
+            SmtError::KeyAbsent(key) => write!(f, "key {} is absent", key),
+            SmtError::ZeroValue => write!(f, "zero is reserved for absent keys"),
+        }
+    }
+}
+
+impl std::error::Error for SmtError {}
+
+/// The kind of change to a leaf, which fixes the constraints on its old and new
+/// values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SmtOp {
+    /// Absent before, nonzero after.
+    Insert,
+    /// Nonzero before and after.
+    Update,
+    /// Nonzero before, absent after.
+    Delete,
+}
+
+/// Witness of one operation: the leaf at `key` went from `old_value` to `new_value`,
+/// taking the root from `old_root` to `new_root`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SmtUpdate<F> {
+    pub op: SmtOp,
+    pub key: u64,
+    pub old_value: F,
+    pub new_value: F,
+    pub old_root: F,
+    pub new_root: F,
+    /// Siblings of the leaf, which the operation does not change.
+    pub path: MerklePath<F>,
+}
+
+impl<F: PrimeField> SmtUpdate<F> {
+    /// `[old_root, new_root, key, new_value]`, the public inputs of
+    /// [`SmtUpdateCircuit`].
+    pub fn instances(&self) -> Vec<Vec<F>> {
+        vec![vec![
+            self.old_root,
+            self.new_root,
+            F::from(self.key),
+            self.new_value,
+        ]]
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SparseMerkleTree<F: PrimeField, S: Spec<F, WIDTH, RATE>> {
+    /// `defaults[level]` is the hash of an empty subtree whose root is at `level`.
+    defaults: Vec<F>,
+    /// Nodes that differ from the default of their level, by `(level, index)`.
+    nodes: BTreeMap<(usize, u64), F>,
+    _spec: PhantomData<S>,
+}
+
+impl<F: PrimeField, S: Spec<F, WIDTH, RATE>> SparseMerkleTree<F, S> {
+    pub fn new(depth: usize) -> Self {
+        assert!(depth <= 64, "keys are at most 64 bits");
+
+        let mut defaults = vec![F::ZERO];
+        for level in 0..depth {
+            let child = defaults[level];
+            defaults.push(hash_pair::<F, S>(child, child));
+        }
+
+        Self {
+            defaults,
+            nodes: BTreeMap::new(),
+            _spec: PhantomData,
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.defaults.len() - 1
+    }
+
+    fn node(&self, level: usize, index: u64) -> F {
+        self.nodes
+            .get(&(level, index))
+            .copied()
+            .unwrap_or(self.defaults[level])
+    }
+
+    pub fn root(&self) -> F {
+        self.node(self.depth(), 0)
+    }
+
+    /// The value at `key`, zero if absent.
+    pub fn get(&self, key: u64) -> F {
+        self.node(0, key)
+    }
+
+    pub fn contains(&self, key: u64) -> bool {
+        self.get(key) != F::ZERO
+    }
+
+    fn check_key(&self, key: u64) -> Result<(), SmtError> {
+        if self.depth() < 64 && key >> self.depth() != 0 {
+            Err(SmtError::KeyOutOfRange(key))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// The path of `key`, whether or not it is present.
+    pub fn path(&self, key: u64) -> Result<MerklePath<F>, SmtError> {
+        self.check_key(key)?;
+
+        let siblings = (0..self.depth())
+            .map(|level| self.node(level, (key >> level) ^ 1))
+            .collect();
+        Ok(MerklePath {
+            index: key as usize,
+            siblings,
+        })
+    }
+
+    pub fn insert(&mut self, key: u64, value: F) -> Result<SmtUpdate<F>, SmtError> {
+        self.check_key(key)?;
+        if self.contains(key) {
+            return Err(SmtError::KeyPresent(key));
+        }
+        if value == F::ZERO {
+            return Err(SmtError::ZeroValue);
+        }
+        Ok(self.set(SmtOp::Insert, key, value))
+    }
+
+    pub fn update(&mut self, key: u64, value: F) -> Result<SmtUpdate<F>, SmtError> {
+        self.check_key(key)?;
+        if !self.contains(key) {
+            return Err(SmtError::KeyAbsent(key));
+        }
+        if value == F::ZERO {
+            return Err(SmtError::ZeroValue);
+        }
+        Ok(self.set(SmtOp::Update, key, value))
+    }
+
+    pub fn delete(&mut self, key: u64) -> Result<SmtUpdate<F>, SmtError> {
+        self.check_key(key)?;
+        if !self.contains(key) {
+            return Err(SmtError::KeyAbsent(key));
+        }
+        Ok(self.set(SmtOp::Delete, key, F::ZERO))
+    }
+
+    /// Writes `value` at `key` and rehashes its path, dropping nodes that are back to
+    /// their default.
+    fn set(&mut self, op: SmtOp, key: u64, value: F) -> SmtUpdate<F> {
+        let path = self.path(key).expect("key checked by the caller");
+        let old_value = self.get(key);
+        let old_root = self.root();
+
+        let mut node = value;
+        for level in 0..=self.depth() {
+            let index = key.checked_shr(level as u32).unwrap_or(0);
+            if node == self.defaults[level] {
+                self.nodes.remove(&(level, index));
+            } else {
+                self.nodes.insert((level, index), node);
+            }
+
+            if level < self.depth() {
+                let sibling = path.siblings[level];
+                node = if path.bit(level) {
+                    hash_pair::<F, S>(sibling, node)
+                } else {
+                    hash_pair::<F, S>(node, sibling)
+                };
+            }
+        }
+
+        SmtUpdate {
+            op,
+            key,
+            old_value,
+            new_value: value,
+            old_root,
+            new_root: self.root(),
+            path,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::P128Pow5T3;
+    use crate::merkle::MerkleTree;
+    use halo2curves::pasta::Fp;
+
+    type Smt = SparseMerkleTree<Fp, P128Pow5T3>;
+
+    #[test]
+    fn smt_matches_dense_tree() {
+        let mut smt = Smt::new(3);
+        let mut leaves = vec![Fp::from(0); 8];
+        assert_eq!(
+            smt.root(),
+            MerkleTree::<Fp, P128Pow5T3>::new(3, &leaves).root()
+        );
+
+        for (key, value) in [(5, 50), (0, 10), (7, 70)] {
+            smt.insert(key, Fp::from(value)).unwrap();
+            leaves[key as usize] = Fp::from(value);
+            assert_eq!(
+                smt.root(),
+                MerkleTree::<Fp, P128Pow5T3>::new(3, &leaves).root()
+            );
+        }
+
+        let tree = MerkleTree::<Fp, P128Pow5T3>::new(3, &leaves);
+        for key in 0..8 {
+            assert_eq!(smt.path(key).unwrap(), tree.path(key as usize));
+        }
+    }
+
+    #[test]
+    fn smt_operations() {
+        let mut smt = Smt::new(4);
+        let empty = smt.root();
+
+        let insert = smt.insert(9, Fp::from(1)).unwrap();
+        assert_eq!(insert.op, SmtOp::Insert);
+        assert_eq!(insert.old_root, empty);
+        assert_eq!(insert.old_value, Fp::from(0));
+        assert_eq!(insert.path.root::<P128Pow5T3>(insert.old_value), empty);
+        assert_eq!(insert.path.root::<P128Pow5T3>(insert.new_value), smt.root());
+
+        let update = smt.update(9, Fp::from(2)).unwrap();
+        assert_eq!(update.old_root, insert.new_root);
+        assert_eq!(update.old_value, Fp::from(1));
+        assert_eq!(smt.get(9), Fp::from(2));
+
+        let delete = smt.delete(9).unwrap();
+        assert_eq!(delete.new_root, empty);
+        assert!(!smt.contains(9));
+        // Deleting the only key leaves no stored nodes behind.
+        assert!(smt.nodes.is_empty());
+    }
+
+    #[test]
+    fn smt_errors() {
+        let mut smt = Smt::new(4);
+        assert_eq!(
+            smt.insert(16, Fp::from(1)),
+            Err(SmtError::KeyOutOfRange(16))
+        );
+        assert_eq!(smt.insert(3, Fp::from(0)), Err(SmtError::ZeroValue));
+        assert_eq!(smt.update(3, Fp::from(1)), Err(SmtError::KeyAbsent(3)));
+        assert_eq!(smt.delete(3), Err(SmtError::KeyAbsent(3)));
+
+        smt.insert(3, Fp::from(1)).unwrap();
+        assert_eq!(smt.insert(3, Fp::from(2)), Err(SmtError::KeyPresent(3)));
+        assert_eq!(smt.update(3, Fp::from(0)), Err(SmtError::ZeroValue));
+    }
+
+    #[test]
+    fn smt_deep() {
+        let mut smt = SparseMerkleTree::<Fp, P128Pow5T3>::new(64);
+        let update = smt.insert(u64::MAX, Fp::from(1)).unwrap();
+        assert_eq!(update.path.depth(), 64);
+        assert_eq!(update.path.root::<P128Pow5T3>(Fp::from(1)), smt.root());
+    }
+}
@@ -0,0 +1,137 @@
+//! Property-based checks of chips against native reference models.
+//!
+//! A [`Model`] pairs a circuit with the native function it is meant to enforce. [`check`]
+//! runs the `MockProver` on one input and fails if the circuit and the reference
+//! disagree, in either direction: a circuit that rejects a valid input is as wrong as
+//! one that accepts an invalid one.
+
+use std::fmt::Debug;
+
+use ff::{FromUniformBytes, PrimeField};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::plonk::Circuit;
+
+/// A circuit together with a native model of the inputs it should accept.
+pub trait Model<F: PrimeField> {
+    type Input: Debug;
+    type Circuit: Circuit<F>;
+
+    fn k(input: &Self::Input) -> u32;
+
+    /// The circuit and instances for `input`.
+    fn circuit(input: &Self::Input) -> (Self::Circuit, Vec<Vec<F>>);
+
+    /// Whether the circuit must accept `input`, computed natively.
+    fn accepts(input: &Self::Input) -> bool;
+}
+
+/// Whether the `MockProver` accepts `circuit`. Synthesis errors count as rejection.
+pub fn circuit_accepts<F: FromUniformBytes<64> + Ord, C: Circuit<F>>(
+    k: u32,
+    circuit: &C,
+    instances: Vec<Vec<F>>,
+) -> bool {
+    MockProver::run(k, circuit, instances)
+        .map(|prover| prover.verify().is_ok())
+        .unwrap_or(false)
+}
+
+/// Checks that the circuit of `M` accepts `input` exactly when the reference does.
+pub fn check<F, M>(input: &M::Input) -> Result<(), String>
+where
+    F: PrimeField + FromUniformBytes<64> + Ord,
+    M: Model<F>,
+{
+    let (circuit, instances) = M::circuit(input);
+    let circuit = circuit_accepts(M::k(input), &circuit, instances);
+    let reference = M::accepts(input);
+
+    if circuit == reference {
+        Ok(())
+    } else {
+        Err(format!(
+            "{:?}: reference accepts = {}, circuit accepts = {}",
+            input, reference, circuit
+        ))
+    }
+}
+
+/// Field elements that tend to break chips: zero, one, the top of the field, the
+/// 64-bit boundary and the middle of the field.
+pub fn edge_cases<F: PrimeField>() -> Vec<F> {
+    let two_64 = F::from(u64::MAX) + F::ONE;
+    let half = F::TWO_INV;
+
+    vec![
+        F::ZERO,
+        F::ONE,
+        F::from(2),
+        -F::ONE,
+        -F::from(2),
+        F::from(u64::MAX),
+        two_64,
+        half,
+        -half,
+    ]
+}
+
+/// The value of a field element as an integer, if it fits in 64 bits.
+pub fn to_u64<F: PrimeField>(value: F) -> Option<u64> {
+    let repr = value.to_repr();
+    let (low, high) = repr.as_ref().split_at(8);
+    if high.iter().any(|byte| *byte != 0) {
+        return None;
+    }
+
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(low);
+    Some(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+pub(crate) mod strategy {
+    use super::edge_cases;
+    use ff::FromUniformBytes;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use proptest::sample::select;
+
+    /// Any field element, biased towards [`edge_cases`] and 64-bit values.
+    pub fn field<F: FromUniformBytes<64>>() -> impl Strategy<Value = F> {
+        prop_oneof![
+            select(edge_cases::<F>()),
+            any::<u64>().prop_map(F::from),
+            vec(any::<u8>(), 64).prop_map(|bytes| {
+                F::from_uniform_bytes(&bytes.try_into().expect("64 bytes"))
+            }),
+        ]
+    }
+
+    /// Field elements around `bound`, mixed with arbitrary ones, for range checks.
+    pub fn near<F: FromUniformBytes<64>>(bound: u64) -> impl Strategy<Value = F> {
+        prop_oneof![
+            3 => (0..bound.saturating_mul(2).max(2)).prop_map(F::from),
+            1 => field::<F>(),
+        ]
+    }
+
+    /// `None` for the correct public output, or an arbitrary claimed output.
+    pub fn claim<F: FromUniformBytes<64>>() -> impl Strategy<Value = Option<F>> {
+        prop_oneof![Just(None), field::<F>().prop_map(Some)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ff::Field;
+    use halo2curves::bn256::Fr;
+
+    #[test]
+    fn model_to_u64() {
+        assert_eq!(to_u64(Fr::ZERO), Some(0));
+        assert_eq!(to_u64(Fr::from(u64::MAX)), Some(u64::MAX));
+        assert_eq!(to_u64(Fr::from(u64::MAX) + Fr::ONE), None);
+        assert_eq!(to_u64(-Fr::ONE), None);
+    }
+}
@@ -1,12 +1,12 @@
 use ff::Field;
-use halo2_proofs::dev::MockProver;
+use halo2_proofs::dev::{MockProver, VerifyFailure};
 use halo2_proofs::poly::commitment::{CommitmentScheme, ParamsProver, Prover, Verifier};
 use halo2_proofs::poly::VerificationStrategy;
 
 use ff::{FromUniformBytes, WithSmallOrderMulGroup};
 use halo2_proofs::plonk::{
     create_proof as create_proof_plonk, keygen_pk, keygen_vk, verify_proof as verify_proof_plonk,
-    Circuit, ProvingKey, VerifyingKey,
+    Circuit, Error, ProvingKey, VerifyingKey,
 };
 use halo2_proofs::transcript::{
     Blake2bRead, Blake2bWrite, Challenge255, EncodedChallenge, Transcript, TranscriptRead,
@@ -41,6 +41,8 @@ pub fn common<Scheme: CommitmentScheme>() -> (
     (scalar, value)
     // common!(Scheme)
 }
+/// Verifies a proof of a batch of circuits, given the instances of each circuit of
+/// the batch in order.
 pub fn verify_proof<
     'a,
     'params,
@@ -53,30 +55,21 @@ pub fn verify_proof<
     params_verifier: &'params Scheme::ParamsVerifier,
     vk: &VerifyingKey<Scheme::Curve>,
     proof: &'a [u8],
-    nrows: u64,
+    instances: &[&[&[Scheme::Scalar]]],
 ) where
     Scheme::Scalar: Ord + WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
 {
-    // let (scalar, value) = common::<Scheme>();
-    let out = Scheme::Scalar::from(nrows);
-
-    let pubinputs = vec![out];
-
     let mut transcript = T::init(proof);
 
     let strategy = Strategy::new(params_verifier);
-    let strategy = verify_proof_plonk(
-        params_verifier,
-        vk,
-        strategy,
-        &[&[&pubinputs[..]], &[&pubinputs[..]]],
-        &mut transcript,
-    )
-    .unwrap();
+    let strategy =
+        verify_proof_plonk(params_verifier, vk, strategy, instances, &mut transcript).unwrap();
 
     assert!(strategy.finalize());
 }
 
+/// Proves a batch of circuits sharing `pk`, with `instances[i]` the instance columns
+/// of `circuits[i]`.
 pub fn create_proof<
     'params,
     Scheme: CommitmentScheme,
@@ -88,33 +81,101 @@ pub fn create_proof<
     rng: R,
     params: &'params Scheme::ParamsProver,
     pk: &ProvingKey<Scheme::Curve>,
-    circuit: impl Circuit<Scheme::Scalar> + Clone,
-    nrows: u64,
-    k: u32,
+    circuits: &[impl Circuit<Scheme::Scalar>],
+    instances: &[&[&[Scheme::Scalar]]],
 ) -> Vec<u8>
 where
     Scheme::Scalar: Ord + WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
 {
-    let out = Scheme::Scalar::from(nrows);
-
+    assert_eq!(
+        circuits.len(),
+        instances.len(),
+        "one instance set per circuit"
+    );
     let mut transcript = T::init(vec![]);
 
     create_proof_plonk::<Scheme, P, _, _, _, _>(
         params,
         pk,
-        &[circuit.clone(), circuit.clone()],
-        &[&[&[out]], &[&[out]]],
+        circuits,
+        instances,
         rng,
         &mut transcript,
     )
     .expect("proof generation should not fail");
 
-    // Check this circuit is satisfied.
-    let prover = match MockProver::run(k, &circuit, vec![vec![out]]) {
-        Ok(prover) => prover,
-        Err(e) => panic!("{:?}", e),
-    };
-    assert_eq!(prover.verify(), Ok(()));
-
     transcript.finalize()
 }
+
+/// Why a circuit in a batch failed the pre-flight check.
+#[derive(Debug)]
+pub enum PreflightFailure {
+    /// Synthesis failed before any constraint could be checked.
+    Synthesis(Error),
+    /// The circuit was synthesized but is not satisfied.
+    Unsatisfied(Vec<VerifyFailure>),
+}
+
+/// Runs the `MockProver` on every circuit of a batch, returning the index and failure
+/// of each circuit that is not satisfied.
+///
+/// This is much slower than proving at large `k`, so it is opt-in: call it directly or
+/// use [`create_proof_checked`].
+pub fn preflight<F: Field + Ord + FromUniformBytes<64>, C: Circuit<F>>(
+    k: u32,
+    circuits: &[C],
+    instances: &[&[&[F]]],
+) -> Result<(), Vec<(usize, PreflightFailure)>> {
+    assert_eq!(
+        circuits.len(),
+        instances.len(),
+        "one instance set per circuit"
+    );
+
+    let failures: Vec<_> = circuits
+        .iter()
+        .zip(instances.iter())
+        .enumerate()
+        .filter_map(|(i, (circuit, instances))| {
+            let instances = instances.iter().map(|column| column.to_vec()).collect();
+            let failure = match MockProver::run(k, circuit, instances) {
+                Ok(prover) => prover.verify().err().map(PreflightFailure::Unsatisfied),
+                Err(e) => Some(PreflightFailure::Synthesis(e)),
+            };
+            failure.map(|failure| (i, failure))
+        })
+        .collect();
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures)
+    }
+}
+
+/// [`create_proof`] with a [`preflight`] check of the batch first. No proof is
+/// created if any circuit fails, and failures are indexed into `circuits`.
+pub fn create_proof_checked<
+    'params,
+    Scheme: CommitmentScheme,
+    P: Prover<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    R: RngCore,
+    T: TranscriptWriterBuffer<Vec<u8>, Scheme::Curve, E>,
+>(
+    rng: R,
+    params: &'params Scheme::ParamsProver,
+    pk: &ProvingKey<Scheme::Curve>,
+    circuits: &[impl Circuit<Scheme::Scalar>],
+    instances: &[&[&[Scheme::Scalar]]],
+    k: u32,
+) -> Result<Vec<u8>, Vec<(usize, PreflightFailure)>>
+where
+    Scheme::Scalar: Ord + WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
+{
+    preflight(k, circuits, instances)?;
+
+    Ok(create_proof::<Scheme, P, E, R, T>(
+        rng, params, pk, circuits, instances,
+    ))
+}
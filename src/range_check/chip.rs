@@ -0,0 +1,549 @@
+use ff::{Field, PrimeFieldBits};
+use halo2_gadgets::utilities::FieldValue;
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+use super::table::{RangeCheckTable, TableRegistry};
+
+// Table setup
+//
+//     value   |  q_lookup  |  table_value  |
+// ---------------------------------------------
+//        v    |      1     |       0       |
+//             |            |      ...      |
+//             |            | 2^num_bits - 1|
+
+/// A type representing a range-constrained field element.
+///
+/// Values of this type can only be produced by [`RangeCheckChip`], so holding one
+/// means the inner value has been constrained to `[0, 2^num_bits)`.
+#[derive(Clone, Debug)]
+pub struct RangeConstrained<F: Field, T: FieldValue<F>> {
+    inner: T,
+    num_bits: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: Field, T: FieldValue<F>> RangeConstrained<F, T> {
+    /// Returns the range-constrained inner type.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns the number of bits to which this cell is constrained.
+    pub fn num_bits(&self) -> usize {
+        self.num_bits
+    }
+}
+
+impl<F: Field> RangeConstrained<F, AssignedCell<F, F>> {
+    /// Extracts the range-constrained value from this range-constrained cell.
+    pub fn value(&self) -> RangeConstrained<F, Value<F>> {
+        RangeConstrained {
+            inner: self.inner.value().copied(),
+            num_bits: self.num_bits,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// Only built by [`RangeCheckChip::configure`] and
+/// [`RangeCheckChip::configure_with_registry`], so that `num_bits` always matches the
+/// table its selector looks up into.
+#[derive(Clone, Debug)]
+pub struct RangeCheckConfig<F: PrimeFieldBits> {
+    q_lookup: Selector,
+    advice: Column<Advice>,
+    table: RangeCheckTable<F>,
+    num_bits: usize,
+}
+
+impl<F: PrimeFieldBits> RangeCheckConfig<F> {
+    /// The number of bits values are constrained to.
+    pub fn num_bits(&self) -> usize {
+        self.num_bits
+    }
+}
+
+/// Range checks values to `[0, 2^num_bits)` with a lookup into a table of all
+/// `num_bits`-bit values.
+#[derive(Clone, Debug)]
+pub struct RangeCheckChip<F: PrimeFieldBits> {
+    config: RangeCheckConfig<F>,
+}
+
+impl<F: PrimeFieldBits> RangeCheckChip<F> {
+    pub fn construct(config: RangeCheckConfig<F>) -> Self {
+        Self { config }
+    }
+
+    /// Configures the chip with its own `num_bits`-bit table.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: Column<Advice>,
+        num_bits: usize,
+    ) -> RangeCheckConfig<F> {
+        assert!(num_bits > 0 && num_bits < 32, "unsupported table size");
+
+        let table = RangeCheckTable::configure(meta, 1 << num_bits);
+        Self::configure_with_table(meta, advice, table, num_bits)
+    }
+
+    /// Configures the chip against the `num_bits`-bit table in `registry`, shared with
+    /// every other chip configured from the same registry.
+    ///
+    /// The table must then be loaded through [`TableRegistry::load`] rather than
+    /// [`RangeCheckChip::load_table`].
+    pub fn configure_with_registry(
+        meta: &mut ConstraintSystem<F>,
+        advice: Column<Advice>,
+        registry: &mut TableRegistry<F>,
+        num_bits: usize,
+    ) -> RangeCheckConfig<F> {
+        let table = registry.range_table(meta, num_bits);
+        Self::configure_with_table(meta, advice, table, num_bits)
+    }
+
+    fn configure_with_table(
+        meta: &mut ConstraintSystem<F>,
+        advice: Column<Advice>,
+        table: RangeCheckTable<F>,
+        num_bits: usize,
+    ) -> RangeCheckConfig<F> {
+        let q_lookup = meta.complex_selector();
+
+        meta.enable_equality(advice);
+
+        meta.lookup("range check chip", |meta| {
+            let q_lookup = meta.query_selector(q_lookup);
+            let advice = meta.query_advice(advice, Rotation::cur());
+
+            vec![(q_lookup * advice, table.value)]
+        });
+
+        RangeCheckConfig {
+            q_lookup,
+            advice,
+            table,
+            num_bits,
+        }
+    }
+
+    pub fn config(&self) -> &RangeCheckConfig<F> {
+        &self.config
+    }
+
+    /// Loads the lookup table. Must be called once per circuit, and not at all if the
+    /// chip was configured from a [`TableRegistry`].
+    pub fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.config.table.load(layouter)
+    }
+
+    /// Witnesses `value` and constrains it to `[0, 2^num_bits)`.
+    pub fn witness_check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<RangeConstrained<F, AssignedCell<F, F>>, Error> {
+        layouter
+            .assign_region(
+                || "witness range check",
+                |mut region| {
+                    self.config.q_lookup.enable(&mut region, 0)?;
+                    region.assign_advice(|| "value", self.config.advice, 0, || value)
+                },
+            )
+            .map(|cell| self.constrained(cell))
+    }
+
+    /// Copies an existing cell into the chip and constrains it to `[0, 2^num_bits)`.
+    pub fn copy_check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+    ) -> Result<RangeConstrained<F, AssignedCell<F, F>>, Error> {
+        layouter
+            .assign_region(
+                || "copy range check",
+                |mut region| {
+                    self.config.q_lookup.enable(&mut region, 0)?;
+                    cell.copy_advice(|| "value", &mut region, self.config.advice, 0)
+                },
+            )
+            .map(|cell| self.constrained(cell))
+    }
+
+    /// Witnesses `values` in consecutive rows of a single region and constrains each
+    /// of them to `[0, 2^num_bits)`.
+    pub fn assign_many(
+        &self,
+        mut layouter: impl Layouter<F>,
+        values: &[Value<F>],
+    ) -> Result<Vec<RangeConstrained<F, AssignedCell<F, F>>>, Error> {
+        layouter.assign_region(
+            || "witness range check many",
+            |mut region| {
+                values
+                    .iter()
+                    .enumerate()
+                    .map(|(offset, value)| {
+                        self.config.q_lookup.enable(&mut region, offset)?;
+                        region
+                            .assign_advice(
+                                || format!("value {}", offset),
+                                self.config.advice,
+                                offset,
+                                || *value,
+                            )
+                            .map(|cell| self.constrained(cell))
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    fn constrained(&self, cell: AssignedCell<F, F>) -> RangeConstrained<F, AssignedCell<F, F>> {
+        RangeConstrained {
+            inner: cell,
+            num_bits: self.config.num_bits,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// Range checks `values` to `num_bits` bits in a single region, with `num_bits` chosen
+/// at runtime. Has no instance columns.
+#[derive(Default, Clone, Debug)]
+pub struct RangeCheckManyCircuit<F: PrimeFieldBits> {
+    pub values: Vec<Value<F>>,
+    pub num_bits: usize,
+}
+
+impl<F: PrimeFieldBits> Circuit<F> for RangeCheckManyCircuit<F> {
+    type Config = RangeCheckConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = usize;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            values: vec![Value::unknown(); self.values.len()],
+            num_bits: self.num_bits,
+        }
+    }
+
+    fn params(&self) -> Self::Params {
+        self.num_bits
+    }
+
+    fn configure_with_params(meta: &mut ConstraintSystem<F>, num_bits: usize) -> Self::Config {
+        let advice = meta.advice_column();
+        RangeCheckChip::configure(meta, advice, num_bits)
+    }
+
+    fn configure(_meta: &mut ConstraintSystem<F>) -> Self::Config {
+        unreachable!("RangeCheckManyCircuit is configured through configure_with_params")
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = RangeCheckChip::construct(config);
+        chip.load_table(&mut layouter)?;
+        chip.assign_many(layouter.namespace(|| "values"), &self.values)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{check, strategy, to_u64, Model};
+    use halo2_proofs::dev::{FailureLocation, MockProver, VerifyFailure};
+    use pasta_curves::pallas;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    const NUM_BITS: usize = 8;
+
+    #[derive(Default)]
+    struct MyCircuit {
+        value: Value<pallas::Base>,
+        copied: Value<pallas::Base>,
+    }
+
+    impl Circuit<pallas::Base> for MyCircuit {
+        type Config = (RangeCheckConfig<pallas::Base>, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+        type Params = ();
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            let advice = meta.advice_column();
+            let other = meta.advice_column();
+            meta.enable_equality(other);
+
+            (RangeCheckChip::configure(meta, advice, NUM_BITS), other)
+        }
+
+        fn synthesize(
+            &self,
+            (config, other): Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> Result<(), Error> {
+            let chip = RangeCheckChip::construct(config);
+            chip.load_table(&mut layouter)?;
+
+            let checked = chip.witness_check(layouter.namespace(|| "witness"), self.value)?;
+            assert_eq!(checked.num_bits(), NUM_BITS);
+
+            let cell = layouter.assign_region(
+                || "other",
+                |mut region| region.assign_advice(|| "copied", other, 0, || self.copied),
+            )?;
+            chip.copy_check(layouter.namespace(|| "copy"), &cell)?;
+
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct ManyCircuit {
+        values: Vec<Value<pallas::Base>>,
+    }
+
+    impl Circuit<pallas::Base> for ManyCircuit {
+        type Config = RangeCheckConfig<pallas::Base>;
+        type FloorPlanner = SimpleFloorPlanner;
+        type Params = ();
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                values: vec![Value::unknown(); self.values.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            let advice = meta.advice_column();
+            RangeCheckChip::configure(meta, advice, NUM_BITS)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> Result<(), Error> {
+            let chip = RangeCheckChip::construct(config);
+            chip.load_table(&mut layouter)?;
+
+            let checked = chip.assign_many(layouter.namespace(|| "many"), &self.values)?;
+            assert_eq!(checked.len(), self.values.len());
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_range_check_chip_many() {
+        let k = 9;
+
+        let mut values: Vec<_> = (0..300u64)
+            .map(|i| Value::known(pallas::Base::from(i % 256)))
+            .collect();
+        let prover = MockProver::run(
+            k,
+            &ManyCircuit {
+                values: values.clone(),
+            },
+            vec![],
+        )
+        .unwrap();
+        prover.assert_satisfied();
+
+        values[42] = Value::known(pallas::Base::from(256u64));
+        let prover = MockProver::run(k, &ManyCircuit { values }, vec![]).unwrap();
+        assert_eq!(
+            prover.verify(),
+            Err(vec![VerifyFailure::Lookup {
+                name: "range check chip".to_string(),
+                lookup_index: 0,
+                location: FailureLocation::InRegion {
+                    region: (1, "witness range check many").into(),
+                    offset: 42
+                }
+            }])
+        );
+    }
+
+    #[derive(Default)]
+    struct SharedCircuit {
+        value: Value<pallas::Base>,
+        load_per_chip: bool,
+    }
+
+    impl Circuit<pallas::Base> for SharedCircuit {
+        type Config = (
+            RangeCheckConfig<pallas::Base>,
+            RangeCheckConfig<pallas::Base>,
+            TableRegistry<pallas::Base>,
+        );
+        type FloorPlanner = SimpleFloorPlanner;
+        type Params = ();
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                value: Value::unknown(),
+                load_per_chip: self.load_per_chip,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            let mut registry = TableRegistry::new();
+            let a = meta.advice_column();
+            let b = meta.advice_column();
+
+            let a = RangeCheckChip::configure_with_registry(meta, a, &mut registry, NUM_BITS);
+            let b = RangeCheckChip::configure_with_registry(meta, b, &mut registry, NUM_BITS);
+
+            (a, b, registry)
+        }
+
+        fn synthesize(
+            &self,
+            (a, b, registry): Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> Result<(), Error> {
+            let a = RangeCheckChip::construct(a);
+            let b = RangeCheckChip::construct(b);
+
+            if self.load_per_chip {
+                a.load_table(&mut layouter)?;
+                b.load_table(&mut layouter)?;
+            } else {
+                registry.load(&mut layouter)?;
+            }
+
+            let cell = a.witness_check(layouter.namespace(|| "a"), self.value)?;
+            b.copy_check(layouter.namespace(|| "b"), cell.inner())?;
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_range_check_shared_table() {
+        let k = 9;
+
+        let mut cs = ConstraintSystem::<pallas::Base>::default();
+        let (a, b, registry) = SharedCircuit::configure(&mut cs);
+        assert_eq!(a.table.value, b.table.value);
+        assert_eq!(registry.num_bits().collect::<Vec<_>>(), vec![NUM_BITS]);
+
+        let circuit = SharedCircuit {
+            value: Value::known(pallas::Base::from(200u64)),
+            load_per_chip: false,
+        };
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+
+        // Both chips loading the shared table is rejected at synthesis time.
+        let circuit = SharedCircuit {
+            value: Value::known(pallas::Base::from(200u64)),
+            load_per_chip: true,
+        };
+        assert!(matches!(
+            MockProver::run(k, &circuit, vec![]),
+            Err(Error::TableError(TableError::UsedColumn(_)))
+        ));
+    }
+
+    #[test]
+    fn test_range_check_chip() {
+        let k = 9;
+
+        for (value, copied) in [(0u64, 0u64), (1, 255), (255, 17)] {
+            let circuit = MyCircuit {
+                value: Value::known(pallas::Base::from(value)),
+                copied: Value::known(pallas::Base::from(copied)),
+            };
+            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            prover.assert_satisfied();
+        }
+
+        let circuit = MyCircuit {
+            value: Value::known(pallas::Base::from(256u64)),
+            copied: Value::known(-pallas::Base::ONE),
+        };
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        assert_eq!(
+            prover.verify(),
+            Err(vec![
+                VerifyFailure::Lookup {
+                    name: "range check chip".to_string(),
+                    lookup_index: 0,
+                    location: FailureLocation::InRegion {
+                        region: (1, "witness range check").into(),
+                        offset: 0
+                    }
+                },
+                VerifyFailure::Lookup {
+                    name: "range check chip".to_string(),
+                    lookup_index: 0,
+                    location: FailureLocation::InRegion {
+                        region: (3, "copy range check").into(),
+                        offset: 0
+                    }
+                },
+            ])
+        );
+    }
+
+    struct RangeCheckManyModel;
+
+    impl Model<pallas::Base> for RangeCheckManyModel {
+        /// `(num_bits, values)`.
+        type Input = (usize, Vec<pallas::Base>);
+        type Circuit = RangeCheckManyCircuit<pallas::Base>;
+
+        fn k(_: &Self::Input) -> u32 {
+            9
+        }
+
+        fn circuit((num_bits, values): &Self::Input) -> (Self::Circuit, Vec<Vec<pallas::Base>>) {
+            let circuit = RangeCheckManyCircuit {
+                values: values.iter().copied().map(Value::known).collect(),
+                num_bits: *num_bits,
+            };
+            (circuit, vec![])
+        }
+
+        fn accepts((num_bits, values): &Self::Input) -> bool {
+            values
+                .iter()
+                .all(|value| matches!(to_u64(*value), Some(v) if v < 1 << num_bits))
+        }
+    }
+
+    fn range_check_many_input() -> impl Strategy<Value = (usize, Vec<pallas::Base>)> {
+        (1usize..=NUM_BITS).prop_flat_map(|num_bits| {
+            (
+                Just(num_bits),
+                vec(strategy::near::<pallas::Base>(1 << num_bits), 0..8),
+            )
+        })
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn range_check_many_model(input in range_check_many_input()) {
+            check::<_, RangeCheckManyModel>(&input).map_err(TestCaseError::fail)?;
+        }
+    }
+}
@@ -1,8 +1,7 @@
-use ff::{Field, PrimeField};
-use halo2_gadgets::utilities::{FieldValue, UtilitiesInstructions};
+use ff::PrimeField;
+use halo2_gadgets::utilities::UtilitiesInstructions;
 use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
 use pasta_curves::pallas;
-use std::marker::PhantomData;
 
 // Table setup
 //
@@ -11,48 +10,7 @@ use std::marker::PhantomData;
 // ------------------------------------
 //        v    |        1
 
-/// A type representing a range-constrained field element.
-#[derive(Clone, Debug)]
-pub struct RangeConstrained<F: Field, T: FieldValue<F>> {
-    inner: T,
-    num_bits: usize,
-    _phantom: PhantomData<F>,
-}
-
-impl<F: Field, T: FieldValue<F>> RangeConstrained<F, T> {
-    /// Returns the range-constrained inner type.
-    pub fn inner(&self) -> &T {
-        &self.inner
-    }
-
-    /// Returns the number of bits to which this cell is constrained.
-    pub fn num_bits(&self) -> usize {
-        self.num_bits
-    }
-}
-impl<F: Field> RangeConstrained<F, AssignedCell<F, F>> {
-    /// Constructs a `RangeConstrained<AssignedCell<F, F>>` without verifying that the
-    /// cell is correctly range constrained.
-    ///
-    /// This API only exists to ease with integrating this type into existing circuits,
-    /// and will likely be removed in future.
-    pub fn unsound_unchecked(cell: AssignedCell<F, F>, num_bits: usize) -> Self {
-        Self {
-            inner: cell,
-            num_bits,
-            _phantom: PhantomData::default(),
-        }
-    }
-
-    /// Extracts the range-constrained value from this range-constrained cell.
-    pub fn value(&self) -> RangeConstrained<F, Value<F>> {
-        RangeConstrained {
-            inner: self.inner.value().copied(),
-            num_bits: self.num_bits,
-            _phantom: PhantomData::default(),
-        }
-    }
-}
+pub use super::RangeConstrained;
 
 /// Checks that an expression is either 1 or 0.
 pub fn bool_check<F: PrimeField>(value: Expression<F>) -> Expression<F> {
@@ -67,27 +25,35 @@ pub fn range_check<F: PrimeField>(word: Expression<F>, range: usize) -> Expressi
     })
 }
 
-struct MyCircuit<const RANGE: usize>(u8);
+/// Range [`RangeCheckCircuit`] is configured for when no params are given, as in
+/// [`RangeCheckParams::default`](super::example2::RangeCheckParams).
+pub const DEFAULT_RANGE: usize = 8;
+
+/// A range check circuit whose range is chosen at configure time through
+/// `Circuit::Params`, e.g. when the bound is read from a config file.
+#[derive(Clone, Debug, Default)]
+pub struct RangeCheckCircuit {
+    pub value: u8,
+    pub range: usize,
+}
+
+/// Const-generic wrapper around [`RangeCheckCircuit`] with `range = RANGE`.
+pub struct MyCircuit<const RANGE: usize>(pub u8);
 
 impl<const RANGE: usize> UtilitiesInstructions<pallas::Base> for MyCircuit<RANGE> {
     type Var = AssignedCell<pallas::Base, pallas::Base>;
 }
 
-#[derive(Clone)]
-struct Config {
+#[derive(Clone, Debug)]
+pub struct Config {
     selector: Selector,
     advice: Column<Advice>,
 }
 
-impl<const RANGE: usize> Circuit<pallas::Base> for MyCircuit<RANGE> {
-    type Config = Config;
-    type FloorPlanner = SimpleFloorPlanner;
+impl Config {
+    fn configure(meta: &mut ConstraintSystem<pallas::Base>, range: usize) -> Self {
+        assert!(range > 0, "range must be non-empty");
 
-    fn without_witnesses(&self) -> Self {
-        MyCircuit(self.0)
-    }
-
-    fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
         let selector = meta.selector();
         let advice = meta.advice_column();
 
@@ -95,26 +61,22 @@ impl<const RANGE: usize> Circuit<pallas::Base> for MyCircuit<RANGE> {
             let selector = meta.query_selector(selector);
             let advice = meta.query_advice(advice, Rotation::cur());
 
-            Constraints::with_selector(selector, Some(range_check(advice, RANGE)))
+            Constraints::with_selector(selector, Some(range_check(advice, range)))
         });
 
         Config { selector, advice }
     }
 
-    fn synthesize(
-        &self,
-        config: Self::Config,
-        mut layouter: impl Layouter<pallas::Base>,
-    ) -> Result<(), Error> {
+    fn assign(&self, mut layouter: impl Layouter<pallas::Base>, value: u8) -> Result<(), Error> {
         layouter.assign_region(
             || "range constrain",
             |mut region| {
-                config.selector.enable(&mut region, 0)?;
+                self.selector.enable(&mut region, 0)?;
                 region.assign_advice(
-                    || format!("witness {}", self.0),
-                    config.advice,
+                    || format!("witness {}", value),
+                    self.advice,
                     0,
-                    || Value::known(pallas::Base::from(self.0 as u64)),
+                    || Value::known(pallas::Base::from(value as u64)),
                 )?;
                 Ok(())
             },
@@ -122,14 +84,74 @@ impl<const RANGE: usize> Circuit<pallas::Base> for MyCircuit<RANGE> {
     }
 }
 
+impl Circuit<pallas::Base> for RangeCheckCircuit {
+    type Config = Config;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = usize;
+
+    fn without_witnesses(&self) -> Self {
+        RangeCheckCircuit {
+            value: 0,
+            range: self.range,
+        }
+    }
+
+    fn params(&self) -> Self::Params {
+        self.range
+    }
+
+    fn configure_with_params(
+        meta: &mut ConstraintSystem<pallas::Base>,
+        range: Self::Params,
+    ) -> Self::Config {
+        Config::configure(meta, range)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+        Self::configure_with_params(meta, DEFAULT_RANGE)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        layouter: impl Layouter<pallas::Base>,
+    ) -> Result<(), Error> {
+        config.assign(layouter, self.value)
+    }
+}
+
+impl<const RANGE: usize> Circuit<pallas::Base> for MyCircuit<RANGE> {
+    type Config = Config;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        MyCircuit(self.0)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+        RangeCheckCircuit::configure_with_params(meta, RANGE)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        layouter: impl Layouter<pallas::Base>,
+    ) -> Result<(), Error> {
+        config.assign(layouter, self.0)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::model::{check, Model};
     use halo2_proofs::{
         dev::{FailureLocation, MockProver, VerifyFailure},
         plonk::Any,
     };
     use pasta_curves::pallas;
+    use proptest::prelude::*;
 
     #[test]
     fn test_range_check() {
@@ -159,4 +181,57 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_range_check_runtime() {
+        for range in [2, 8, 16] {
+            for value in 0..range {
+                let circuit = RangeCheckCircuit {
+                    value: value as u8,
+                    range,
+                };
+                let prover = MockProver::<pallas::Base>::run(3, &circuit, vec![]).unwrap();
+                assert_eq!(prover.verify(), Ok(()));
+            }
+
+            let circuit = RangeCheckCircuit {
+                value: range as u8,
+                range,
+            };
+            let prover = MockProver::<pallas::Base>::run(3, &circuit, vec![]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    struct RangeCheckModel;
+
+    impl Model<pallas::Base> for RangeCheckModel {
+        /// `(value, range)`.
+        type Input = (u8, usize);
+        type Circuit = RangeCheckCircuit;
+
+        fn k(_: &Self::Input) -> u32 {
+            3
+        }
+
+        fn circuit(&(value, range): &Self::Input) -> (Self::Circuit, Vec<Vec<pallas::Base>>) {
+            (RangeCheckCircuit { value, range }, vec![])
+        }
+
+        fn accepts(&(value, range): &Self::Input) -> bool {
+            (value as usize) < range
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn range_check_model(
+            value in prop_oneof![3 => 0u8..32, 1 => any::<u8>()],
+            range in 1usize..=16,
+        ) {
+            check::<_, RangeCheckModel>(&(value, range)).map_err(TestCaseError::fail)?;
+        }
+    }
 }
@@ -1,4 +1,4 @@
-use ff::{Field, PrimeFieldBits};
+use ff::PrimeFieldBits;
 // use halo2_gadgets::utilities::UtilitiesInstructions;
 use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
 // use pasta_curves::pallas;
@@ -14,26 +14,39 @@ use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
 //        v    |        1          |      0     |       0       |
 //        v'   |        0          |      1     |       1       |
 
-mod table;
-use table::RangeCheckTable;
-// use super::example1::{RangeConstrained, bool_check};
-
-#[derive(Debug, Clone)]
-/// A range-constrained value in the circuit produced by the RangeCheckConfig.
-struct RangeConstrained<F: Field, const RANGE: usize>(AssignedCell<Assigned<F>, F>);
-/// A type representing a range-constrained field element.
-#[derive(Clone)]
-struct RangeCheckConfig<const RANGE: usize, const LOOKUP_RANGE: usize, F: PrimeFieldBits> {
-    q_range_check: Selector,
-    q_lookup: Selector,
-    advice: Column<Advice>,
-    table: RangeCheckTable<LOOKUP_RANGE, F>,
+use super::table::RangeCheckTable;
+
+/// Sizes of the small (gate) and big (lookup) range checks, chosen at configure time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RangeCheckParams {
+    pub range: usize,
+    pub lookup_range: usize,
 }
 
-impl<const RANGE: usize, const LOOKUP_RANGE: usize, F: PrimeFieldBits>
-    RangeCheckConfig<RANGE, LOOKUP_RANGE, F>
-{
-    fn configure(meta: &mut ConstraintSystem<F>, advice: Column<Advice>) -> Self {
+impl Default for RangeCheckParams {
+    fn default() -> Self {
+        Self {
+            range: 8,
+            lookup_range: 256,
+        }
+    }
+}
+
+/// A range check config whose sizes are read from [`RangeCheckParams`].
+#[derive(Clone, Debug)]
+pub struct RuntimeRangeCheckConfig<F: PrimeFieldBits> {
+    pub q_range_check: Selector,
+    pub q_lookup: Selector,
+    pub advice: Column<Advice>,
+    pub table: RangeCheckTable<F>,
+}
+
+impl<F: PrimeFieldBits> RuntimeRangeCheckConfig<F> {
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: Column<Advice>,
+        params: RangeCheckParams,
+    ) -> Self {
         // Toggles range check constraint
         let q_range_check = meta.selector();
 
@@ -41,7 +54,7 @@ impl<const RANGE: usize, const LOOKUP_RANGE: usize, F: PrimeFieldBits>
         let q_lookup = meta.complex_selector();
 
         // Config lookup table
-        let table = RangeCheckTable::configure(meta);
+        let table = RangeCheckTable::configure(meta, params.lookup_range);
 
         // Small range check
         meta.create_gate("range check", |meta| {
@@ -55,7 +68,10 @@ impl<const RANGE: usize, const LOOKUP_RANGE: usize, F: PrimeFieldBits>
                 })
             };
 
-            Constraints::with_selector(selector, [("range check", range_check(advice, RANGE))])
+            Constraints::with_selector(
+                selector,
+                [("range check", range_check(advice, params.range))],
+            )
         });
 
         // lookup range check
@@ -73,11 +89,12 @@ impl<const RANGE: usize, const LOOKUP_RANGE: usize, F: PrimeFieldBits>
             table,
         }
     }
+
     pub fn assign_simple(
         &self,
         mut layouter: impl Layouter<F>,
         advice: Value<Assigned<F>>,
-    ) -> Result<RangeConstrained<F, RANGE>, Error> {
+    ) -> Result<AssignedCell<Assigned<F>, F>, Error> {
         layouter.assign_region(
             || "Assign value for simple range check",
             |mut region| {
@@ -87,9 +104,7 @@ impl<const RANGE: usize, const LOOKUP_RANGE: usize, F: PrimeFieldBits>
                 self.q_range_check.enable(&mut region, offset)?;
 
                 // Assign value
-                region
-                    .assign_advice(|| "value", self.advice, offset, || advice)
-                    .map(RangeConstrained)
+                region.assign_advice(|| "value", self.advice, offset, || advice)
             },
         )
     }
@@ -98,7 +113,7 @@ impl<const RANGE: usize, const LOOKUP_RANGE: usize, F: PrimeFieldBits>
         &self,
         mut layouter: impl Layouter<F>,
         advice: Value<Assigned<F>>,
-    ) -> Result<RangeConstrained<F, LOOKUP_RANGE>, Error> {
+    ) -> Result<AssignedCell<Assigned<F>, F>, Error> {
         layouter.assign_region(
             || "Assign value for lookup range check",
             |mut region| {
@@ -108,17 +123,54 @@ impl<const RANGE: usize, const LOOKUP_RANGE: usize, F: PrimeFieldBits>
                 self.q_lookup.enable(&mut region, offset)?;
 
                 // Assign value
-                region
-                    .assign_advice(|| "value", self.advice, offset, || advice)
-                    .map(RangeConstrained)
+                region.assign_advice(|| "value", self.advice, offset, || advice)
             },
         )
     }
 }
 
+/// Thin const-generic wrapper around [`RuntimeRangeCheckConfig`].
+#[derive(Clone, Debug)]
+struct RangeCheckConfig<const RANGE: usize, const LOOKUP_RANGE: usize, F: PrimeFieldBits>(
+    RuntimeRangeCheckConfig<F>,
+);
+
+impl<const RANGE: usize, const LOOKUP_RANGE: usize, F: PrimeFieldBits>
+    RangeCheckConfig<RANGE, LOOKUP_RANGE, F>
+{
+    fn configure(meta: &mut ConstraintSystem<F>, advice: Column<Advice>) -> Self {
+        let params = RangeCheckParams {
+            range: RANGE,
+            lookup_range: LOOKUP_RANGE,
+        };
+        Self(RuntimeRangeCheckConfig::configure(meta, advice, params))
+    }
+
+    fn table(&self) -> &RangeCheckTable<F> {
+        &self.0.table
+    }
+
+    pub fn assign_simple(
+        &self,
+        layouter: impl Layouter<F>,
+        advice: Value<Assigned<F>>,
+    ) -> Result<AssignedCell<Assigned<F>, F>, Error> {
+        self.0.assign_simple(layouter, advice)
+    }
+
+    pub fn assign_lookup(
+        &self,
+        layouter: impl Layouter<F>,
+        advice: Value<Assigned<F>>,
+    ) -> Result<AssignedCell<Assigned<F>, F>, Error> {
+        self.0.assign_lookup(layouter, advice)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::model::{check, strategy, to_u64, Model};
     // use group::ff::{Field, FromUniformBytes, PrimeField};
     use halo2_proofs::{
         circuit::{Layouter, SimpleFloorPlanner},
@@ -126,6 +178,7 @@ mod tests {
         plonk::{Any, Circuit, ConstraintSystem, Error},
     };
     use pasta_curves::pallas;
+    use proptest::prelude::*;
 
     #[derive(Default)]
     struct MyCircuit<const RANGE: usize, const LOOKUP_RANGE: usize> {
@@ -138,6 +191,7 @@ mod tests {
     {
         type Config = RangeCheckConfig<RANGE, LOOKUP_RANGE, pallas::Base>;
         type FloorPlanner = SimpleFloorPlanner;
+        type Params = ();
 
         fn without_witnesses(&self) -> Self {
             Self::default()
@@ -148,6 +202,57 @@ mod tests {
             RangeCheckConfig::configure(meta, value)
         }
 
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> Result<(), Error> {
+            config.table().load(&mut layouter)?;
+
+            config.assign_simple(layouter.namespace(|| "Assign simple value"), self.value)?;
+            config.assign_lookup(
+                layouter.namespace(|| "Assign lookup value"),
+                self.lookup_value,
+            )?;
+
+            Ok(())
+        }
+    }
+    #[derive(Default)]
+    struct RuntimeCircuit {
+        value: Value<Assigned<pallas::Base>>,
+        lookup_value: Value<Assigned<pallas::Base>>,
+        params: RangeCheckParams,
+    }
+
+    impl Circuit<pallas::Base> for RuntimeCircuit {
+        type Config = RuntimeRangeCheckConfig<pallas::Base>;
+        type FloorPlanner = SimpleFloorPlanner;
+        type Params = RangeCheckParams;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                params: self.params,
+                ..Self::default()
+            }
+        }
+
+        fn params(&self) -> Self::Params {
+            self.params
+        }
+
+        fn configure_with_params(
+            meta: &mut ConstraintSystem<pallas::Base>,
+            params: Self::Params,
+        ) -> Self::Config {
+            let value = meta.advice_column();
+            RuntimeRangeCheckConfig::configure(meta, value, params)
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            Self::configure_with_params(meta, RangeCheckParams::default())
+        }
+
         fn synthesize(
             &self,
             config: Self::Config,
@@ -164,6 +269,41 @@ mod tests {
             Ok(())
         }
     }
+
+    #[test]
+    fn test_range_check_lookup_runtime() {
+        let k = 11;
+
+        for params in [
+            RangeCheckParams {
+                range: 4,
+                lookup_range: 100,
+            },
+            RangeCheckParams {
+                range: 10,
+                lookup_range: 1000,
+            },
+        ] {
+            for (i, j) in [(0, 0), (params.range - 1, params.lookup_range - 1)] {
+                let circuit = RuntimeCircuit {
+                    value: Value::known(pallas::Base::from(i as u64).into()),
+                    lookup_value: Value::known(pallas::Base::from(j as u64).into()),
+                    params,
+                };
+                let prover = MockProver::<pallas::Base>::run(k, &circuit, vec![]).unwrap();
+                prover.assert_satisfied()
+            }
+
+            let circuit = RuntimeCircuit {
+                value: Value::known(pallas::Base::from(params.range as u64).into()),
+                lookup_value: Value::known(pallas::Base::from(params.lookup_range as u64).into()),
+                params,
+            };
+            let prover = MockProver::<pallas::Base>::run(k, &circuit, vec![]).unwrap();
+            assert_eq!(prover.verify().unwrap_err().len(), 2);
+        }
+    }
+
     #[test]
     fn test_range_check_lookup() {
         let k = 9;
@@ -246,6 +386,56 @@ mod tests {
             .render(9, &circuit, &root)
             .unwrap();
     }
+
+    struct RuntimeModel;
+
+    impl Model<pallas::Base> for RuntimeModel {
+        /// `(value, lookup_value, params)`.
+        type Input = (pallas::Base, pallas::Base, RangeCheckParams);
+        type Circuit = RuntimeCircuit;
+
+        fn k(_: &Self::Input) -> u32 {
+            11
+        }
+
+        fn circuit(
+            &(value, lookup_value, params): &Self::Input,
+        ) -> (Self::Circuit, Vec<Vec<pallas::Base>>) {
+            let circuit = RuntimeCircuit {
+                value: Value::known(value.into()),
+                lookup_value: Value::known(lookup_value.into()),
+                params,
+            };
+            (circuit, vec![])
+        }
+
+        fn accepts(&(value, lookup_value, params): &Self::Input) -> bool {
+            let below = |value, bound: usize| matches!(to_u64(value), Some(v) if v < bound as u64);
+            below(value, params.range) && below(lookup_value, params.lookup_range)
+        }
+    }
+
+    fn runtime_input() -> impl Strategy<Value = (pallas::Base, pallas::Base, RangeCheckParams)> {
+        (1usize..=10, 1usize..=1000).prop_flat_map(|(range, lookup_range)| {
+            (
+                strategy::near::<pallas::Base>(range as u64),
+                strategy::near::<pallas::Base>(lookup_range as u64),
+                Just(RangeCheckParams {
+                    range,
+                    lookup_range,
+                }),
+            )
+        })
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn range_check_runtime_model(input in runtime_input()) {
+            check::<_, RuntimeModel>(&input).map_err(TestCaseError::fail)?;
+        }
+    }
 }
 // ConstraintCaseDebug {
 //     constraint: Constraint {
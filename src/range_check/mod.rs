@@ -1,4 +1,8 @@
-mod example1;
-mod example2;
+mod chip;
+pub mod example1;
+pub mod example2;
 mod example3;
-// mod example3_broken;
+pub mod spread;
+pub mod table;
+
+pub use chip::{RangeCheckChip, RangeCheckConfig, RangeCheckManyCircuit, RangeConstrained};
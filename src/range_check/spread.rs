@@ -0,0 +1,388 @@
+use ff::PrimeFieldBits;
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+
+use super::table::{spread, SpreadTable, TableRegistry};
+
+// Table setup
+//
+// Every operation is one region accumulating two linear combinations from zero, with
+// coefficients in fixed columns:
+//
+//   acc_dense[i + 1]  = acc_dense[i]  + coeff_dense[i]  * dense[i]
+//   acc_spread[i + 1] = acc_spread[i] + coeff_spread[i] * spread[i]
+//
+// Piece rows also look up `(tag, dense, spread)` in the spread table, making `dense`
+// a `tag`-bit chunk and `spread` its spread. Term rows copy in a word or a spread
+// word. Results are read off the accumulators, and checks constrain an accumulator
+// to zero. E.g. `x + y` mod 2^32, with result bytes r0..r3 and carry c:
+//
+//  dense | spread | acc_dense | acc_spread | tag | coeff_dense | coeff_spread | q_piece | q_term |
+// ---------------------------------------------------------------------------------------------------
+//   r0   |  r0'   |     0     |     0      |  8  |      1      |       1      |    1    |    0   |
+//   ...  |        |           |            |     |             |              |         |        |
+//   r3   |  r3'   |           |            |  8  |    2^24     |     2^48     |    1    |    0   |
+//   c    |   c'   |     r     |     r'     |  1  |    2^32     |       0      |    1    |    0   |
+//   x    |   0    |           |     r'     |     |     -1      |       0      |    0    |    1   |
+//   y    |   0    |           |     r'     |     |     -1      |       0      |    0    |    1   |
+//        |        |     0     |     r'     |     |             |              |    0    |    0   |
+
+/// Widest chunk looked up in the spread table.
+pub const PIECE_BITS: usize = 8;
+
+#[derive(Clone, Debug)]
+pub struct SpreadConfig<F: PrimeFieldBits> {
+    pub dense: Column<Advice>,
+    pub spread: Column<Advice>,
+    pub acc_dense: Column<Advice>,
+    pub acc_spread: Column<Advice>,
+    pub tag: Column<Fixed>,
+    pub coeff_dense: Column<Fixed>,
+    pub coeff_spread: Column<Fixed>,
+    pub q_piece: Selector,
+    pub q_term: Selector,
+    pub table: SpreadTable<F>,
+}
+
+/// Where a term row takes its value from.
+pub(crate) enum Input<'a, F: PrimeFieldBits> {
+    Cell(&'a AssignedCell<F, F>),
+    Constant(F),
+}
+
+pub(crate) enum Row<'a, F: PrimeFieldBits> {
+    /// A chunk of `bits` bits, looked up together with its spread.
+    Piece {
+        bits: usize,
+        value: Value<u64>,
+        coeff_dense: F,
+        coeff_spread: F,
+    },
+    /// A dense word, weighted into `acc_dense`.
+    Dense(Input<'a, F>, F),
+    /// A spread word, weighted into `acc_spread`.
+    Spread(Input<'a, F>, F),
+}
+
+/// An accumulator constrained to zero, by row.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Zero {
+    Dense(usize),
+    Spread(usize),
+}
+
+/// `2^exponent` in the field, for exponents up to 127.
+pub(crate) fn pow2<F: PrimeFieldBits>(exponent: u32) -> F {
+    F::from_u128(1 << exponent)
+}
+
+/// The byte pieces of a `num_bytes`-byte `word`, byte `i` weighted by `2^(8i) * dense`
+/// and its spread by `4^(8i) * spread`.
+pub(crate) fn bytes<'a, F: PrimeFieldBits>(
+    word: Value<u64>,
+    num_bytes: usize,
+    dense: F,
+    spread: F,
+) -> Vec<Row<'a, F>> {
+    (0..num_bytes as u32)
+        .map(|i| Row::Piece {
+            bits: PIECE_BITS,
+            value: word.map(|word| (word >> (8 * i)) & 0xff),
+            coeff_dense: dense * pow2::<F>(8 * i),
+            coeff_spread: spread * pow2::<F>(16 * i),
+        })
+        .collect()
+}
+
+/// A byte of a padded message: a private message byte by index, or a padding
+/// constant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaddedByte {
+    Message(usize),
+    Padding(u8),
+}
+
+/// Rows packing `bytes`, least significant first, into one word and its spread, and
+/// the value of that word.
+///
+/// Message bytes are byte pieces, so they are range checked, and the padding goes in
+/// as a single constant, so the prover cannot change it.
+pub(crate) fn pack<'a, F: PrimeFieldBits>(
+    bytes: &[PaddedByte],
+    message: &[Value<u8>],
+) -> (Vec<Row<'a, F>>, Value<u64>) {
+    assert!(bytes.len() <= 8, "words are at most 64 bits");
+
+    let mut rows = vec![];
+    let mut value = Value::known(0);
+    let (mut padding, mut padding_spread) = (0u64, 0u128);
+    for (i, byte) in bytes.iter().enumerate() {
+        let i = i as u32;
+        match byte {
+            PaddedByte::Message(index) => {
+                let byte = message[*index].map(u64::from);
+                rows.push(Row::Piece {
+                    bits: PIECE_BITS,
+                    value: byte,
+                    coeff_dense: pow2(8 * i),
+                    coeff_spread: pow2(16 * i),
+                });
+                value = value + byte.map(|byte| byte << (8 * i));
+            }
+            PaddedByte::Padding(byte) => {
+                padding |= (*byte as u64) << (8 * i);
+                padding_spread |= (spread(*byte as u64) as u128) << (16 * i);
+            }
+        }
+    }
+
+    if padding != 0 {
+        rows.push(Row::Dense(Input::Constant(F::from(padding)), F::ONE));
+        rows.push(Row::Spread(
+            Input::Constant(F::from_u128(padding_spread)),
+            F::ONE,
+        ));
+    }
+    (rows, value.map(|value| value | padding))
+}
+
+/// Lays out linear combinations of spread pieces and words, for chips doing bitwise
+/// arithmetic on spread words.
+#[derive(Clone, Debug)]
+pub struct SpreadChip<F: PrimeFieldBits> {
+    config: SpreadConfig<F>,
+}
+
+impl<F: PrimeFieldBits> SpreadChip<F> {
+    pub fn construct(config: SpreadConfig<F>) -> Self {
+        Self { config }
+    }
+
+    /// Configures the chip with its own spread table.
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> SpreadConfig<F> {
+        let table = SpreadTable::configure(meta, PIECE_BITS);
+        Self::configure_with_table(meta, table)
+    }
+
+    /// Configures the chip against the spread table in `registry`, which must then be
+    /// loaded through [`TableRegistry::load`] rather than [`SpreadChip::load_table`].
+    pub fn configure_with_registry(
+        meta: &mut ConstraintSystem<F>,
+        registry: &mut TableRegistry<F>,
+    ) -> SpreadConfig<F> {
+        let table = registry.spread_table(meta, PIECE_BITS);
+        Self::configure_with_table(meta, table)
+    }
+
+    fn configure_with_table(
+        meta: &mut ConstraintSystem<F>,
+        table: SpreadTable<F>,
+    ) -> SpreadConfig<F> {
+        let dense = meta.advice_column();
+        let spread = meta.advice_column();
+        let acc_dense = meta.advice_column();
+        let acc_spread = meta.advice_column();
+        let tag = meta.fixed_column();
+        let coeff_dense = meta.fixed_column();
+        let coeff_spread = meta.fixed_column();
+        let constants = meta.fixed_column();
+        let q_piece = meta.complex_selector();
+        let q_term = meta.selector();
+
+        for column in [dense, spread, acc_dense, acc_spread] {
+            meta.enable_equality(column);
+        }
+        // Accumulators start from a constant zero, and padding and round constants are
+        // loaded as constants.
+        meta.enable_constant(constants);
+
+        meta.create_gate("linear combination", |meta| {
+            let q = meta.query_selector(q_piece) + meta.query_selector(q_term);
+            let dense = meta.query_advice(dense, Rotation::cur());
+            let spread = meta.query_advice(spread, Rotation::cur());
+            let acc_dense_cur = meta.query_advice(acc_dense, Rotation::cur());
+            let acc_dense_next = meta.query_advice(acc_dense, Rotation::next());
+            let acc_spread_cur = meta.query_advice(acc_spread, Rotation::cur());
+            let acc_spread_next = meta.query_advice(acc_spread, Rotation::next());
+            let coeff_dense = meta.query_fixed(coeff_dense, Rotation::cur());
+            let coeff_spread = meta.query_fixed(coeff_spread, Rotation::cur());
+
+            Constraints::with_selector(
+                q,
+                [
+                    (
+                        "dense",
+                        acc_dense_next - acc_dense_cur - coeff_dense * dense,
+                    ),
+                    (
+                        "spread",
+                        acc_spread_next - acc_spread_cur - coeff_spread * spread,
+                    ),
+                ],
+            )
+        });
+
+        meta.lookup("spread", |meta| {
+            let q_piece = meta.query_selector(q_piece);
+            let tag = meta.query_fixed(tag, Rotation::cur());
+            let dense = meta.query_advice(dense, Rotation::cur());
+            let spread = meta.query_advice(spread, Rotation::cur());
+
+            vec![
+                (q_piece.clone() * tag, table.tag),
+                (q_piece.clone() * dense, table.dense),
+                (q_piece * spread, table.spread),
+            ]
+        });
+
+        SpreadConfig {
+            dense,
+            spread,
+            acc_dense,
+            acc_spread,
+            tag,
+            coeff_dense,
+            coeff_spread,
+            q_piece,
+            q_term,
+            table,
+        }
+    }
+
+    pub fn config(&self) -> &SpreadConfig<F> {
+        &self.config
+    }
+
+    /// Loads the spread table. Must be called once per circuit, and not at all if the
+    /// chip was configured from a [`TableRegistry`].
+    pub fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.config.table.load(layouter)
+    }
+
+    /// Lays out `rows` as one region, constrains the `zeros` accumulators to zero,
+    /// and returns the accumulators before each row and after the last one.
+    pub(crate) fn combine(
+        &self,
+        mut layouter: impl Layouter<F>,
+        rows: &[Row<'_, F>],
+        zeros: &[Zero],
+    ) -> Result<Vec<(AssignedCell<F, F>, AssignedCell<F, F>)>, Error> {
+        let config = &self.config;
+
+        layouter.assign_region(
+            || "combine",
+            |mut region| {
+                let mut acc = (
+                    region.assign_advice_from_constant(
+                        || "acc_dense",
+                        config.acc_dense,
+                        0,
+                        F::ZERO,
+                    )?,
+                    region.assign_advice_from_constant(
+                        || "acc_spread",
+                        config.acc_spread,
+                        0,
+                        F::ZERO,
+                    )?,
+                );
+                let mut accs = Vec::with_capacity(rows.len() + 1);
+
+                for (offset, row) in rows.iter().enumerate() {
+                    let (dense, spread, coeff_dense, coeff_spread) = match row {
+                        Row::Piece {
+                            bits,
+                            value,
+                            coeff_dense,
+                            coeff_spread,
+                        } => {
+                            assert!(*bits <= config.table.max_bits, "piece too wide");
+                            config.q_piece.enable(&mut region, offset)?;
+                            region.assign_fixed(
+                                || "tag",
+                                config.tag,
+                                offset,
+                                || Value::known(F::from(*bits as u64)),
+                            )?;
+                            let dense = value.map(F::from);
+                            let spread = value.map(|value| F::from(spread(value)));
+                            region.assign_advice(|| "dense", config.dense, offset, || dense)?;
+                            region.assign_advice(|| "spread", config.spread, offset, || spread)?;
+                            (dense, spread, *coeff_dense, *coeff_spread)
+                        }
+                        Row::Dense(input, coeff) => {
+                            config.q_term.enable(&mut region, offset)?;
+                            let dense =
+                                Self::assign_input(&mut region, config.dense, offset, input)?;
+                            let spread = Value::known(F::ZERO);
+                            region.assign_advice(|| "spread", config.spread, offset, || spread)?;
+                            (dense, spread, *coeff, F::ZERO)
+                        }
+                        Row::Spread(input, coeff) => {
+                            config.q_term.enable(&mut region, offset)?;
+                            let dense = Value::known(F::ZERO);
+                            region.assign_advice(|| "dense", config.dense, offset, || dense)?;
+                            let spread =
+                                Self::assign_input(&mut region, config.spread, offset, input)?;
+                            (dense, spread, F::ZERO, *coeff)
+                        }
+                    };
+                    region.assign_fixed(
+                        || "coeff_dense",
+                        config.coeff_dense,
+                        offset,
+                        || Value::known(coeff_dense),
+                    )?;
+                    region.assign_fixed(
+                        || "coeff_spread",
+                        config.coeff_spread,
+                        offset,
+                        || Value::known(coeff_spread),
+                    )?;
+
+                    let acc_dense = acc.0.value().copied() + dense * Value::known(coeff_dense);
+                    let acc_spread = acc.1.value().copied() + spread * Value::known(coeff_spread);
+                    let next = (
+                        region.assign_advice(
+                            || "acc_dense",
+                            config.acc_dense,
+                            offset + 1,
+                            || acc_dense,
+                        )?,
+                        region.assign_advice(
+                            || "acc_spread",
+                            config.acc_spread,
+                            offset + 1,
+                            || acc_spread,
+                        )?,
+                    );
+                    accs.push(std::mem::replace(&mut acc, next));
+                }
+                accs.push(acc);
+
+                for zero in zeros {
+                    let cell = match zero {
+                        Zero::Dense(row) => accs[*row].0.cell(),
+                        Zero::Spread(row) => accs[*row].1.cell(),
+                    };
+                    region.constrain_constant(cell, F::ZERO)?;
+                }
+                Ok(accs)
+            },
+        )
+    }
+
+    fn assign_input(
+        region: &mut Region<'_, F>,
+        column: Column<Advice>,
+        offset: usize,
+        input: &Input<'_, F>,
+    ) -> Result<Value<F>, Error> {
+        let cell = match input {
+            Input::Cell(cell) => cell.copy_advice(|| "term", region, column, offset)?,
+            Input::Constant(constant) => {
+                region.assign_advice_from_constant(|| "constant", column, offset, *constant)?
+            }
+        };
+        Ok(cell.value().copied())
+    }
+}
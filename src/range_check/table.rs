@@ -0,0 +1,290 @@
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+
+use ff::PrimeFieldBits;
+use halo2_proofs::{plonk::TableColumn, circuit::{Layouter, Value}, plonk::{Error, ConstraintSystem}};
+
+/// A lookup table for values in [0..range), with `range` chosen at configure time.
+/// e.g. range = 1024, values = [0..1023]
+
+#[derive(Clone, Debug)]
+pub struct RangeCheckTable<F: PrimeFieldBits> {
+    pub(crate) value: TableColumn,
+    pub(crate) range: usize,
+    _marker: PhantomData<F>
+}
+
+impl<F: PrimeFieldBits> RangeCheckTable<F> {
+    /// Crate-private, so that range check configs can only hold tables of the size
+    /// they were configured for.
+    pub(crate) fn configure(meta: &mut ConstraintSystem<F>, range: usize) -> Self {
+        assert!(range > 0, "lookup range must be non-empty");
+        let value = meta.lookup_table_column();
+
+        Self {
+            value,
+            range,
+            _marker: PhantomData
+        }
+    }
+
+    pub fn load(
+        &self,
+        layouter: &mut impl Layouter<F>
+    ) -> Result<(), Error> {
+        layouter.assign_table(
+            || "load range-check table",
+            |mut table| {
+                let mut offset = 0;
+                for value in 0..self.range {
+                    table.assign_cell(
+                        ||"num_bits",
+                        self.value,
+                        offset,
+                        || Value::known(F::from(value as u64))
+                    )?;
+                    offset += 1;
+                }
+                Ok(())
+        })
+    }
+}
+
+/// Interleaves a zero bit above each bit of `value`, so that bit `i` moves to bit `2i`.
+///
+/// The sum of up to three spread words holds, in each 2-bit limb, the number of
+/// inputs with that bit set, so XOR, AND and majority can be read off its even and
+/// odd bits.
+pub fn spread(value: u64) -> u64 {
+    assert!(value < 1 << 32, "only 32-bit values have a 64-bit spread");
+    (0..32).fold(0, |acc, i| acc | (((value >> i) & 1) << (2 * i)))
+}
+
+/// A lookup table of `(tag, dense, spread(dense))` for every `dense < 2^tag` and every
+/// `tag <= max_bits`.
+///
+/// Looking up a fixed tag with a dense value both range checks the value to `tag`
+/// bits and binds the spread value to it. Every tag contains `(tag, 0, 0)`, so rows
+/// with the selector off look up `(0, 0, 0)`.
+#[derive(Clone, Debug)]
+pub struct SpreadTable<F: PrimeFieldBits> {
+    pub(crate) tag: TableColumn,
+    pub(crate) dense: TableColumn,
+    pub(crate) spread: TableColumn,
+    pub(crate) max_bits: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeFieldBits> SpreadTable<F> {
+    pub fn configure(meta: &mut ConstraintSystem<F>, max_bits: usize) -> Self {
+        assert!((1..=16).contains(&max_bits), "unsupported spread table size");
+
+        Self {
+            tag: meta.lookup_table_column(),
+            dense: meta.lookup_table_column(),
+            spread: meta.lookup_table_column(),
+            max_bits,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Rows of the table: `2^(max_bits + 1) - 1`.
+    pub fn num_rows(&self) -> usize {
+        (1 << (self.max_bits + 1)) - 1
+    }
+
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "load spread table",
+            |mut table| {
+                let mut offset = 0;
+                for tag in 0..=self.max_bits {
+                    for dense in 0..1u64 << tag {
+                        let row = [
+                            (self.tag, tag as u64),
+                            (self.dense, dense),
+                            (self.spread, spread(dense)),
+                        ];
+                        for (column, value) in row {
+                            table.assign_cell(
+                                || "spread table",
+                                column,
+                                offset,
+                                || Value::known(F::from(value)),
+                            )?;
+                        }
+                        offset += 1;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+/// A bitwise operation on bytes, looked up in a [`BitwiseTable`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BitwiseOp {
+    And,
+    Or,
+    Xor,
+}
+
+impl BitwiseOp {
+    pub const ALL: [BitwiseOp; 3] = [BitwiseOp::And, BitwiseOp::Or, BitwiseOp::Xor];
+
+    /// Native reference: `a op b`.
+    pub fn apply(self, a: u64, b: u64) -> u64 {
+        match self {
+            BitwiseOp::And => a & b,
+            BitwiseOp::Or => a | b,
+            BitwiseOp::Xor => a ^ b,
+        }
+    }
+}
+
+/// A lookup table of `(a, b, a op b)` for every pair of bytes.
+///
+/// `(0, 0, 0)` is a row for every op, so rows with the selector off look it up.
+#[derive(Clone, Debug)]
+pub struct BitwiseTable<F: PrimeFieldBits> {
+    pub(crate) op: BitwiseOp,
+    pub(crate) a: TableColumn,
+    pub(crate) b: TableColumn,
+    pub(crate) result: TableColumn,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeFieldBits> BitwiseTable<F> {
+    pub fn configure(meta: &mut ConstraintSystem<F>, op: BitwiseOp) -> Self {
+        Self {
+            op,
+            a: meta.lookup_table_column(),
+            b: meta.lookup_table_column(),
+            result: meta.lookup_table_column(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Rows of the table: `2^16`.
+    pub fn num_rows(&self) -> usize {
+        1 << 16
+    }
+
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || format!("load {:?} table", self.op),
+            |mut table| {
+                let mut offset = 0;
+                for a in 0..=u8::MAX as u64 {
+                    for b in 0..=u8::MAX as u64 {
+                        let row = [
+                            (self.a, a),
+                            (self.b, b),
+                            (self.result, self.op.apply(a, b)),
+                        ];
+                        for (column, value) in row {
+                            table.assign_cell(
+                                || "bitwise table",
+                                column,
+                                offset,
+                                || Value::known(F::from(value)),
+                            )?;
+                        }
+                        offset += 1;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+/// Range tables shared between chips, keyed by bit width.
+///
+/// Chips configured through the same registry look up into the same table column,
+/// so e.g. a comparison chip and a byte check chip share one 8-bit table. The
+/// registry owns loading: [`TableRegistry::load`] assigns every table exactly once,
+/// and halo2 rejects a second assignment of the same column during synthesis with
+/// `TableError::UsedColumn`.
+#[derive(Clone, Debug)]
+pub struct TableRegistry<F: PrimeFieldBits> {
+    tables: BTreeMap<usize, RangeCheckTable<F>>,
+    spread: Option<SpreadTable<F>>,
+    bitwise: BTreeMap<BitwiseOp, BitwiseTable<F>>,
+}
+
+impl<F: PrimeFieldBits> Default for TableRegistry<F> {
+    fn default() -> Self {
+        Self {
+            tables: BTreeMap::new(),
+            spread: None,
+            bitwise: BTreeMap::new(),
+        }
+    }
+}
+
+impl<F: PrimeFieldBits> TableRegistry<F> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the `num_bits`-bit range table, configuring it on first use.
+    pub fn range_table(
+        &mut self,
+        meta: &mut ConstraintSystem<F>,
+        num_bits: usize,
+    ) -> RangeCheckTable<F> {
+        assert!(num_bits > 0 && num_bits < 32, "unsupported table size");
+        self.tables
+            .entry(num_bits)
+            .or_insert_with(|| RangeCheckTable::configure(meta, 1 << num_bits))
+            .clone()
+    }
+
+    /// Returns the spread table, configuring it on first use. Chips asking for a
+    /// different `max_bits` than the first caller get a panic rather than a silently
+    /// smaller table.
+    pub fn spread_table(
+        &mut self,
+        meta: &mut ConstraintSystem<F>,
+        max_bits: usize,
+    ) -> SpreadTable<F> {
+        let table = self
+            .spread
+            .get_or_insert_with(|| SpreadTable::configure(meta, max_bits));
+        assert_eq!(table.max_bits, max_bits, "spread table size mismatch");
+        table.clone()
+    }
+
+    /// Returns the byte table of `op`, configuring it on first use.
+    pub fn bitwise_table(
+        &mut self,
+        meta: &mut ConstraintSystem<F>,
+        op: BitwiseOp,
+    ) -> BitwiseTable<F> {
+        self.bitwise
+            .entry(op)
+            .or_insert_with(|| BitwiseTable::configure(meta, op))
+            .clone()
+    }
+
+    /// Bit widths of the registered tables.
+    pub fn num_bits(&self) -> impl Iterator<Item = usize> + '_ {
+        self.tables.keys().copied()
+    }
+
+    /// Loads every registered table. Must be called once per circuit.
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        for table in self.tables.values() {
+            table.load(layouter)?;
+        }
+        if let Some(spread) = &self.spread {
+            spread.load(layouter)?;
+        }
+        for table in self.bitwise.values() {
+            table.load(layouter)?;
+        }
+        Ok(())
+    }
+}
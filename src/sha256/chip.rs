@@ -0,0 +1,706 @@
+use ff::{PrimeField, PrimeFieldBits};
+use halo2_proofs::{circuit::*, plonk::*};
+
+use super::{
+    padding, sigma, split_spread, BIG_SIGMA_0, BIG_SIGMA_1, BLOCK_WORDS, DIGEST_WORDS, IV, K,
+    SMALL_SIGMA_0, SMALL_SIGMA_1,
+};
+use crate::range_check::spread::{
+    bytes, pack, pow2, Input, PaddedByte, Row, SpreadChip, SpreadConfig, Zero,
+};
+use crate::range_check::table::{spread, TableRegistry};
+
+// Table setup
+//
+// Every operation is one region of `SpreadChip`. E.g. `sigma(x)` cuts `x` into
+// pieces at its rotations and at every byte, sums the rotated spreads, and splits
+// that sum back into its even bits `e` and odd bits `o`:
+//
+//  dense | spread | acc_dense | acc_spread | coeff_dense | coeff_spread
+// ----------------------------------------------------------------------
+//   x0   |  x0'   |     0     |     0      |    2^0      |  sum 4^rot(0)
+//   ...  |        |           |            |             |
+//   x    |   0    |     x     |     R      |    -1       |       0
+//   e0   |  e0'   |     0     |     R      |     1       |     -1
+//   ...  |        |           |            |             |
+//   o3   |  o3'   |           |            |     0       |   -2 * 4^24
+//        |        |     e     |     0      |             |
+
+/// A word constrained to 32 bits.
+#[derive(Clone, Debug)]
+pub struct DenseWord<F: PrimeFieldBits> {
+    pub value: Value<u32>,
+    pub cell: AssignedCell<F, F>,
+}
+
+/// A word constrained to 32 bits, together with its spread.
+#[derive(Clone, Debug)]
+pub struct SpreadWord<F: PrimeFieldBits> {
+    pub value: Value<u32>,
+    pub dense: AssignedCell<F, F>,
+    pub spread: AssignedCell<F, F>,
+}
+
+impl<F: PrimeFieldBits> SpreadWord<F> {
+    pub fn to_dense(&self) -> DenseWord<F> {
+        DenseWord {
+            value: self.value,
+            cell: self.dense.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Sha256Config<F: PrimeFieldBits> {
+    pub spread: SpreadConfig<F>,
+    pub instance: Column<Instance>,
+}
+
+/// The byte pieces of a 32-bit word.
+fn word_bytes<'a, F: PrimeFieldBits>(word: Value<u32>, dense: F, spread: F) -> Vec<Row<'a, F>> {
+    bytes(word.map(u64::from), 4, dense, spread)
+}
+
+/// Pieces that subtract `total`, a sum of spread words, as the spreads of its even
+/// and odd bits. Only the dense word of the `odd` half is added to `acc_dense`.
+fn split<'a, F: PrimeFieldBits>(total: Value<u128>, odd: bool) -> Vec<Row<'a, F>> {
+    let (even_bits, odd_bits) = total.map(split_spread).unzip();
+    let keep = |half: bool| if half { F::ONE } else { F::ZERO };
+
+    let mut rows = word_bytes(even_bits, keep(!odd), -F::ONE);
+    rows.extend(word_bytes(odd_bits, keep(odd), -F::from(2)));
+    rows
+}
+
+/// Bits needed for the carry of a sum of `addends` 32-bit words.
+fn carry_bits(addends: usize) -> usize {
+    (usize::BITS - (addends - 1).leading_zeros()) as usize
+}
+
+/// Chunk boundaries of a sigma input: every rotation and shift, and every byte.
+fn boundaries(rotations: &[u32], shift: Option<u32>) -> Vec<u32> {
+    let mut bounds = vec![0, 8, 16, 24, 32];
+    bounds.extend(rotations);
+    bounds.extend(shift);
+    bounds.sort_unstable();
+    bounds.dedup();
+    bounds
+}
+
+/// The padded bytes of each message word, least significant first.
+fn message_words(len: usize) -> impl Iterator<Item = Vec<PaddedByte>> {
+    padding(len)
+        .chunks(4)
+        .map(|word| word.iter().rev().copied().collect::<Vec<_>>())
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+/// Computes SHA-256 with 32-bit words held densely and as spreads, over a spread
+/// table of chunks up to 8 bits.
+#[derive(Clone, Debug)]
+pub struct Sha256Chip<F: PrimeFieldBits> {
+    config: Sha256Config<F>,
+    spread: SpreadChip<F>,
+}
+
+impl<F: PrimeFieldBits> Sha256Chip<F> {
+    pub fn construct(config: Sha256Config<F>) -> Self {
+        let spread = SpreadChip::construct(config.spread.clone());
+        Self { config, spread }
+    }
+
+    /// Configures the chip with its own spread table.
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> Sha256Config<F> {
+        let spread = SpreadChip::configure(meta);
+        Self::configure_with_spread(meta, spread)
+    }
+
+    /// Configures the chip against the spread table in `registry`, which must then be
+    /// loaded through [`TableRegistry::load`] rather than [`Sha256Chip::load_table`].
+    pub fn configure_with_registry(
+        meta: &mut ConstraintSystem<F>,
+        registry: &mut TableRegistry<F>,
+    ) -> Sha256Config<F> {
+        let spread = SpreadChip::configure_with_registry(meta, registry);
+        Self::configure_with_spread(meta, spread)
+    }
+
+    fn configure_with_spread(
+        meta: &mut ConstraintSystem<F>,
+        spread: SpreadConfig<F>,
+    ) -> Sha256Config<F> {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        Sha256Config { spread, instance }
+    }
+
+    /// Loads the spread table. Must be called once per circuit, and not at all if the
+    /// chip was configured from a [`TableRegistry`].
+    pub fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.spread.load_table(layouter)
+    }
+
+    /// Loads a message word from its padded `bytes`, least significant first.
+    fn message_word(
+        &self,
+        layouter: impl Layouter<F>,
+        bytes: &[PaddedByte],
+        message: &[Value<u8>],
+    ) -> Result<SpreadWord<F>, Error> {
+        let (rows, value) = pack(bytes, message);
+        let accs = self.spread.combine(layouter, &rows, &[])?;
+        let (dense, spread) = accs[rows.len()].clone();
+        Ok(SpreadWord {
+            value: value.map(|value| value as u32),
+            dense,
+            spread,
+        })
+    }
+
+    /// Loads the initial hash value as constants.
+    fn iv(&self, mut layouter: impl Layouter<F>) -> Result<[SpreadWord<F>; DIGEST_WORDS], Error> {
+        let config = self.spread.config();
+        let words = layouter.assign_region(
+            || "iv",
+            |mut region| {
+                IV.iter()
+                    .enumerate()
+                    .map(|(offset, word)| {
+                        let dense = F::from(*word as u64);
+                        let spread = F::from(spread(*word as u64));
+                        Ok(SpreadWord {
+                            value: Value::known(*word),
+                            dense: region.assign_advice_from_constant(
+                                || "iv",
+                                config.dense,
+                                offset,
+                                dense,
+                            )?,
+                            spread: region.assign_advice_from_constant(
+                                || "iv spread",
+                                config.spread,
+                                offset,
+                                spread,
+                            )?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, Error>>()
+            },
+        )?;
+        Ok(words.try_into().unwrap())
+    }
+
+    /// The sum of `words` and `constant` mod 2^32, with its spread.
+    pub fn add(
+        &self,
+        layouter: impl Layouter<F>,
+        words: &[&DenseWord<F>],
+        constant: Option<u32>,
+    ) -> Result<SpreadWord<F>, Error> {
+        let addends = words.len() + constant.is_some() as usize;
+        let total = words
+            .iter()
+            .fold(Value::known(constant.unwrap_or(0) as u64), |total, word| {
+                total + word.value.map(u64::from)
+            });
+        let value = total.map(|total| total as u32);
+
+        let mut rows = word_bytes(value, F::ONE, F::ONE);
+        let result = rows.len();
+        let bits = carry_bits(addends);
+        if bits > 0 {
+            rows.push(Row::Piece {
+                bits,
+                value: total.map(|total| total >> 32),
+                coeff_dense: pow2(32),
+                coeff_spread: F::ZERO,
+            });
+        }
+        rows.extend(
+            words
+                .iter()
+                .map(|word| Row::Dense(Input::Cell(&word.cell), -F::ONE)),
+        );
+        rows.extend(
+            constant.map(|constant| Row::Dense(Input::Constant(F::from(constant as u64)), -F::ONE)),
+        );
+
+        let accs = self
+            .spread
+            .combine(layouter, &rows, &[Zero::Dense(rows.len())])?;
+        let (dense, spread) = accs[result].clone();
+        Ok(SpreadWord {
+            value,
+            dense,
+            spread,
+        })
+    }
+
+    /// The XOR of `x` rotated right by each of `rotations`, and shifted right by
+    /// `shift` if any.
+    ///
+    /// `x` is cut into chunks at every rotation, so that each chunk lands whole in
+    /// each rotated word, and the spreads of the rotated words are summed with one
+    /// coefficient per chunk. Their XOR is the even bits of that sum.
+    pub fn sigma(
+        &self,
+        layouter: impl Layouter<F>,
+        x: &DenseWord<F>,
+        rotations: &[u32],
+        shift: Option<u32>,
+    ) -> Result<DenseWord<F>, Error> {
+        let mut rows: Vec<_> = boundaries(rotations, shift)
+            .windows(2)
+            .map(|bounds| {
+                let (offset, bits) = (bounds[0], bounds[1] - bounds[0]);
+                let rotated: F = rotations
+                    .iter()
+                    .map(|rotation| F::from(1 << (2 * ((offset + 32 - rotation) % 32))))
+                    .sum();
+                let shifted = match shift {
+                    Some(shift) if offset >= shift => F::from(1 << (2 * (offset - shift))),
+                    _ => F::ZERO,
+                };
+                Row::Piece {
+                    bits: bits as usize,
+                    value: x.value.map(|x| ((x >> offset) & ((1 << bits) - 1)) as u64),
+                    coeff_dense: F::from(1 << offset),
+                    coeff_spread: rotated + shifted,
+                }
+            })
+            .collect();
+        rows.push(Row::Dense(Input::Cell(&x.cell), -F::ONE));
+        let decomposed = rows.len();
+
+        let total = x.value.map(|x| {
+            let rotated: u128 = rotations
+                .iter()
+                .map(|rotation| spread(x.rotate_right(*rotation) as u64) as u128)
+                .sum();
+            rotated + shift.map_or(0, |shift| spread((x >> shift) as u64) as u128)
+        });
+        rows.extend(split(total, false));
+
+        let zeros = [Zero::Dense(decomposed), Zero::Spread(rows.len())];
+        let accs = self.spread.combine(layouter, &rows, &zeros)?;
+        Ok(DenseWord {
+            value: x.value.map(|x| sigma(x, rotations, shift)),
+            cell: accs[rows.len()].0.clone(),
+        })
+    }
+
+    /// Splits the sum of the weighted spread `terms`, which must equal `total`, and
+    /// returns the word of its odd bits.
+    fn odd_bits(
+        &self,
+        layouter: impl Layouter<F>,
+        mut rows: Vec<Row<'_, F>>,
+        total: Value<u128>,
+    ) -> Result<DenseWord<F>, Error> {
+        rows.extend(split(total, true));
+        let accs = self
+            .spread
+            .combine(layouter, &rows, &[Zero::Spread(rows.len())])?;
+        Ok(DenseWord {
+            value: total.map(|total| split_spread(total).1),
+            cell: accs[rows.len()].0.clone(),
+        })
+    }
+
+    /// `x & y`.
+    pub fn and(
+        &self,
+        layouter: impl Layouter<F>,
+        x: &SpreadWord<F>,
+        y: &SpreadWord<F>,
+    ) -> Result<DenseWord<F>, Error> {
+        let rows = vec![
+            Row::Spread(Input::Cell(&x.spread), F::ONE),
+            Row::Spread(Input::Cell(&y.spread), F::ONE),
+        ];
+        let total = x
+            .value
+            .zip(y.value)
+            .map(|(x, y)| spread(x as u64) as u128 + spread(y as u64) as u128);
+        self.odd_bits(layouter, rows, total)
+    }
+
+    /// `!x & y`, with the spread of `!x` taken as `spread(2^32 - 1) - spread(x)`.
+    pub fn and_not(
+        &self,
+        layouter: impl Layouter<F>,
+        x: &SpreadWord<F>,
+        y: &SpreadWord<F>,
+    ) -> Result<DenseWord<F>, Error> {
+        let ones = spread(u32::MAX as u64);
+        let rows = vec![
+            Row::Spread(Input::Constant(F::from(ones)), F::ONE),
+            Row::Spread(Input::Cell(&x.spread), -F::ONE),
+            Row::Spread(Input::Cell(&y.spread), F::ONE),
+        ];
+        let total = x
+            .value
+            .zip(y.value)
+            .map(|(x, y)| spread(!x as u64) as u128 + spread(y as u64) as u128);
+        self.odd_bits(layouter, rows, total)
+    }
+
+    /// The bitwise majority of `x`, `y` and `z`.
+    pub fn maj(
+        &self,
+        layouter: impl Layouter<F>,
+        x: &SpreadWord<F>,
+        y: &SpreadWord<F>,
+        z: &SpreadWord<F>,
+    ) -> Result<DenseWord<F>, Error> {
+        let rows = vec![
+            Row::Spread(Input::Cell(&x.spread), F::ONE),
+            Row::Spread(Input::Cell(&y.spread), F::ONE),
+            Row::Spread(Input::Cell(&z.spread), F::ONE),
+        ];
+        let total = x.value.zip(y.value).zip(z.value).map(|((x, y), z)| {
+            [x, y, z]
+                .iter()
+                .map(|word| spread(*word as u64) as u128)
+                .sum()
+        });
+        self.odd_bits(layouter, rows, total)
+    }
+
+    /// One application of the compression function to `state`.
+    pub fn compress(
+        &self,
+        mut layouter: impl Layouter<F>,
+        state: &[SpreadWord<F>; DIGEST_WORDS],
+        block: &[SpreadWord<F>; BLOCK_WORDS],
+    ) -> Result<[SpreadWord<F>; DIGEST_WORDS], Error> {
+        let mut w: Vec<DenseWord<F>> = block.iter().map(SpreadWord::to_dense).collect();
+        for t in 16..64 {
+            let s0 = self.sigma(
+                layouter.namespace(|| format!("w{} sigma0", t)),
+                &w[t - 15],
+                &SMALL_SIGMA_0.0,
+                Some(SMALL_SIGMA_0.1),
+            )?;
+            let s1 = self.sigma(
+                layouter.namespace(|| format!("w{} sigma1", t)),
+                &w[t - 2],
+                &SMALL_SIGMA_1.0,
+                Some(SMALL_SIGMA_1.1),
+            )?;
+            let word = self.add(
+                layouter.namespace(|| format!("w{}", t)),
+                &[&s1, &w[t - 7], &s0, &w[t - 16]],
+                None,
+            )?;
+            w.push(word.to_dense());
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state.clone();
+        for (t, (k, w)) in K.iter().zip(&w).enumerate() {
+            let s1 = self.sigma(
+                layouter.namespace(|| format!("round {} sigma1", t)),
+                &e.to_dense(),
+                &BIG_SIGMA_1,
+                None,
+            )?;
+            // The two halves of `ch` have no set bit in common, so their sum is their
+            // XOR.
+            let ch_and = self.and(layouter.namespace(|| format!("round {} ch", t)), &e, &f)?;
+            let ch_and_not =
+                self.and_not(layouter.namespace(|| format!("round {} ch", t)), &e, &g)?;
+            let s0 = self.sigma(
+                layouter.namespace(|| format!("round {} sigma0", t)),
+                &a.to_dense(),
+                &BIG_SIGMA_0,
+                None,
+            )?;
+            let maj = self.maj(
+                layouter.namespace(|| format!("round {} maj", t)),
+                &a,
+                &b,
+                &c,
+            )?;
+
+            let (d_dense, h_dense) = (d.to_dense(), h.to_dense());
+            let t1 = [&h_dense, &s1, &ch_and, &ch_and_not, w];
+            let mut e_addends = vec![&d_dense];
+            e_addends.extend(t1);
+            let mut a_addends = t1.to_vec();
+            a_addends.extend([&s0, &maj]);
+
+            let new_e = self.add(
+                layouter.namespace(|| format!("round {} e", t)),
+                &e_addends,
+                Some(*k),
+            )?;
+            let new_a = self.add(
+                layouter.namespace(|| format!("round {} a", t)),
+                &a_addends,
+                Some(*k),
+            )?;
+
+            h = g;
+            g = f;
+            f = e;
+            e = new_e;
+            d = c;
+            c = b;
+            b = a;
+            a = new_a;
+        }
+
+        let words = [a, b, c, d, e, f, g, h]
+            .iter()
+            .zip(state.iter())
+            .enumerate()
+            .map(|(i, (new, old))| {
+                self.add(
+                    layouter.namespace(|| format!("h{}", i)),
+                    &[&old.to_dense(), &new.to_dense()],
+                    None,
+                )
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(words.try_into().unwrap())
+    }
+
+    /// The digest of `message`, as big-endian words. The message length, and so the
+    /// padding, is fixed at synthesis.
+    pub fn digest(
+        &self,
+        mut layouter: impl Layouter<F>,
+        message: &[Value<u8>],
+    ) -> Result<[DenseWord<F>; DIGEST_WORDS], Error> {
+        let words = message_words(message.len())
+            .enumerate()
+            .map(|(i, bytes)| {
+                self.message_word(layouter.namespace(|| format!("m{}", i)), &bytes, message)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let mut state = self.iv(layouter.namespace(|| "iv"))?;
+        for (i, block) in words.chunks(BLOCK_WORDS).enumerate() {
+            state = self.compress(
+                layouter.namespace(|| format!("block {}", i)),
+                &state,
+                &block.to_vec().try_into().unwrap(),
+            )?;
+        }
+        Ok(state.map(|word| word.to_dense()))
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+/// Rows of the operations laid out by [`Sha256Chip`], each one more than its pieces
+/// and terms for the final accumulators.
+mod rows {
+    use super::*;
+
+    pub(super) fn add(addends: usize) -> usize {
+        4 + (carry_bits(addends) > 0) as usize + addends + 1
+    }
+
+    pub(super) fn sigma(rotations: &[u32], shift: Option<u32>) -> usize {
+        (boundaries(rotations, shift).len() - 1) + 1 + 8 + 1
+    }
+
+    pub(super) fn odd_bits(terms: usize) -> usize {
+        terms + 8 + 1
+    }
+
+    /// One compression, message words excluded.
+    pub(super) fn compression() -> usize {
+        let schedule = (64 - 16)
+            * (sigma(&SMALL_SIGMA_0.0, Some(SMALL_SIGMA_0.1))
+                + sigma(&SMALL_SIGMA_1.0, Some(SMALL_SIGMA_1.1))
+                + add(4));
+        let round = sigma(&BIG_SIGMA_1, None)
+            + sigma(&BIG_SIGMA_0, None)
+            + odd_bits(2)
+            + odd_bits(3)
+            + odd_bits(3)
+            + add(7)
+            + add(8);
+        let feed_forward = DIGEST_WORDS * add(2);
+        schedule + 64 * round + feed_forward
+    }
+
+    /// The message words of a `len`-byte message: one row per message byte, two for
+    /// the padding constants of a word, if any.
+    pub(super) fn message<F: PrimeFieldBits>(len: usize) -> usize {
+        let message = vec![Value::unknown(); len];
+        message_words(len)
+            .map(|bytes| pack::<F>(&bytes, &message).0.len() + 1)
+            .sum()
+    }
+}
+
+/// Columns and rows used by [`Sha256Circuit`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sha256Cost {
+    pub advice_columns: usize,
+    /// Fixed columns, lookup table columns and selectors included.
+    pub fixed_columns: usize,
+    pub lookups: usize,
+    pub table_rows: usize,
+    /// Rows of one compression, loading the message words excluded.
+    pub rows_per_compression: usize,
+    /// Rows of a message of `len` bytes, the initial hash value included.
+    pub rows: usize,
+}
+
+impl Sha256Cost {
+    pub fn measure<F: PrimeFieldBits>(len: usize) -> Self {
+        let mut meta = ConstraintSystem::<F>::default();
+        let config = Sha256Chip::configure(&mut meta);
+        let blocks = padding(len).len() / (4 * BLOCK_WORDS);
+
+        Self {
+            advice_columns: meta.num_advice_columns(),
+            fixed_columns: meta.num_fixed_columns() + meta.num_selectors(),
+            lookups: meta.lookups().len(),
+            table_rows: config.spread.table.num_rows(),
+            rows_per_compression: rows::compression(),
+            rows: rows::message::<F>(len) + DIGEST_WORDS + blocks * rows::compression(),
+        }
+    }
+}
+
+/// Hashes a private message of public length.
+///
+/// Instance layout: `[h0, .., h7]`, the digest as big-endian words.
+#[derive(Clone, Debug)]
+pub struct Sha256Circuit {
+    pub message: Vec<Value<u8>>,
+}
+
+impl Sha256Circuit {
+    pub fn new(message: &[u8]) -> Self {
+        Self {
+            message: message.iter().copied().map(Value::known).collect(),
+        }
+    }
+
+    /// The public inputs matching `message`.
+    pub fn instances<F: PrimeField>(message: &[u8]) -> Vec<Vec<F>> {
+        vec![super::digest_words(message)
+            .iter()
+            .map(|word| F::from(*word as u64))
+            .collect()]
+    }
+}
+
+impl<F: PrimeFieldBits> Circuit<F> for Sha256Circuit {
+    type Config = Sha256Config<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            message: vec![Value::unknown(); self.message.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        Sha256Chip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = Sha256Chip::construct(config);
+        chip.load_table(&mut layouter)?;
+
+        let digest = chip.digest(layouter.namespace(|| "sha256"), &self.message)?;
+        for (row, word) in digest.iter().enumerate() {
+            chip.expose_public(layouter.namespace(|| "digest"), &word.cell, row)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sha256::NIST_VECTORS;
+    use halo2_proofs::dev::MockProver;
+    use halo2curves::bn256::Fr;
+    use halo2curves::pasta::Fp;
+
+    /// Two blocks take 16740 rows.
+    const K: u32 = 15;
+
+    #[test]
+    fn sha256_chip_nist_vectors() {
+        for (message, _) in NIST_VECTORS {
+            let circuit = Sha256Circuit::new(message);
+            let instances = Sha256Circuit::instances::<Fp>(message);
+
+            let prover = MockProver::run(K, &circuit, instances).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn sha256_chip_bn256() {
+        let message = b"abc";
+        let circuit = Sha256Circuit::new(message);
+        let prover = MockProver::run(K, &circuit, Sha256Circuit::instances::<Fr>(message)).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn sha256_chip_rejects() {
+        let message = b"abc";
+        let circuit = Sha256Circuit::new(message);
+
+        // A wrong digest word.
+        let mut wrong = Sha256Circuit::instances::<Fp>(message);
+        wrong[0][7] += Fp::from(1);
+        let prover = MockProver::run(K, &circuit, wrong).unwrap();
+        assert!(prover.verify().is_err());
+
+        // The digest of another message of the same length.
+        let circuit = Sha256Circuit::new(b"abd");
+        let instances = Sha256Circuit::instances::<Fp>(message);
+        let prover = MockProver::run(K, &circuit, instances).unwrap();
+        assert!(prover.verify().is_err());
+
+        // The digest of a prefix, which would need other padding.
+        let circuit = Sha256Circuit::new(b"ab");
+        let instances = Sha256Circuit::instances::<Fp>(message);
+        let prover = MockProver::run(K, &circuit, instances).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn sha256_chip_cost() {
+        let (message, _) = NIST_VECTORS[2];
+        let cost = Sha256Cost::measure::<Fp>(message.len());
+
+        assert_eq!(cost.advice_columns, 4);
+        // The tag, coefficient and constant columns of the spread chip, the three
+        // columns of its table, and its two selectors.
+        assert_eq!(cost.fixed_columns, 4 + 3 + 2);
+        assert_eq!(cost.lookups, 1);
+        assert_eq!(cost.table_rows, 511);
+        assert_eq!(cost.rows_per_compression, 8320);
+        // 14 words of message bytes, 2 words with padding constants (0x80 and the
+        // length), 16 words of zeros, and the initial hash value.
+        assert_eq!(cost.rows, 14 * 5 + 2 * 3 + 16 + 8 + 2 * 8320);
+        assert!(cost.rows < 1 << K);
+    }
+}
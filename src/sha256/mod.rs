@@ -0,0 +1,220 @@
+//! SHA-256 over the spread lookup table.
+//!
+//! [`Sha256Chip`] compresses padded 512-bit blocks in circuit, chaining the state
+//! across blocks, and [`Sha256Circuit`] exposes the digest of a private message as
+//! eight public 32-bit words. The message length is fixed at synthesis, so the
+//! [`padding`] is made of constants and only the message bytes are private.
+//!
+//! The bitwise functions never decompose words into bits. Every word is kept with its
+//! spread (see [`spread`]), chunks of at most 8 bits are looked up in a
+//! [`SpreadTable`](crate::range_check::table::SpreadTable) by a
+//! [`SpreadChip`](crate::range_check::spread::SpreadChip), and sums of spreads are
+//! split back into their even and odd bits.
+
+use crate::range_check::spread::PaddedByte;
+use crate::range_check::table::spread;
+
+mod chip;
+
+pub use chip::{DenseWord, Sha256Chip, Sha256Circuit, Sha256Config, Sha256Cost, SpreadWord};
+
+/// Round constants.
+pub const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Initial hash value.
+pub const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+pub const BLOCK_WORDS: usize = 16;
+pub const DIGEST_WORDS: usize = 8;
+
+/// Rotations of the big sigmas and of the rotating part of the small sigmas.
+pub(crate) const BIG_SIGMA_0: [u32; 3] = [2, 13, 22];
+pub(crate) const BIG_SIGMA_1: [u32; 3] = [6, 11, 25];
+pub(crate) const SMALL_SIGMA_0: ([u32; 2], u32) = ([7, 18], 3);
+pub(crate) const SMALL_SIGMA_1: ([u32; 2], u32) = ([17, 19], 10);
+
+/// The message of `len` bytes followed by a one bit, zeros and its bit length,
+/// byte by byte.
+pub fn padding(len: usize) -> Vec<PaddedByte> {
+    let mut bytes: Vec<_> = (0..len).map(PaddedByte::Message).collect();
+    bytes.push(PaddedByte::Padding(0x80));
+    while bytes.len() % 64 != 56 {
+        bytes.push(PaddedByte::Padding(0));
+    }
+    bytes.extend(((len as u64) * 8).to_be_bytes().map(PaddedByte::Padding));
+    bytes
+}
+
+/// Pads `message` and splits it into blocks of big-endian words.
+pub fn pad(message: &[u8]) -> Vec<[u32; BLOCK_WORDS]> {
+    let bytes: Vec<u8> = padding(message.len())
+        .into_iter()
+        .map(|byte| match byte {
+            PaddedByte::Message(index) => message[index],
+            PaddedByte::Padding(byte) => byte,
+        })
+        .collect();
+
+    bytes
+        .chunks(64)
+        .map(|block| {
+            let mut words = [0; BLOCK_WORDS];
+            for (word, chunk) in words.iter_mut().zip(block.chunks(4)) {
+                *word = u32::from_be_bytes(chunk.try_into().unwrap());
+            }
+            words
+        })
+        .collect()
+}
+
+/// Native reference: the XOR of `x` rotated right by each of `rotations`, and shifted
+/// right by `shift` if any.
+pub(crate) fn sigma(x: u32, rotations: &[u32], shift: Option<u32>) -> u32 {
+    let rotated = rotations
+        .iter()
+        .fold(0, |acc, rotation| acc ^ x.rotate_right(*rotation));
+    shift.map_or(rotated, |shift| rotated ^ (x >> shift))
+}
+
+/// Native reference: one application of the compression function.
+pub fn compress(state: &mut [u32; DIGEST_WORDS], block: &[u32; BLOCK_WORDS]) {
+    let mut w = block.to_vec();
+    for t in 16..64 {
+        let s0 = sigma(w[t - 15], &SMALL_SIGMA_0.0, Some(SMALL_SIGMA_0.1));
+        let s1 = sigma(w[t - 2], &SMALL_SIGMA_1.0, Some(SMALL_SIGMA_1.1));
+        w.push(
+            s1.wrapping_add(w[t - 7])
+                .wrapping_add(s0)
+                .wrapping_add(w[t - 16]),
+        );
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for (k, w) in K.iter().zip(&w) {
+        let ch = (e & f) ^ (!e & g);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t1 = h
+            .wrapping_add(sigma(e, &BIG_SIGMA_1, None))
+            .wrapping_add(ch)
+            .wrapping_add(*k)
+            .wrapping_add(*w);
+        let t2 = sigma(a, &BIG_SIGMA_0, None).wrapping_add(maj);
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+    }
+
+    for (word, new) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *word = word.wrapping_add(new);
+    }
+}
+
+/// Native reference: the digest of `message` as big-endian words.
+pub fn digest_words(message: &[u8]) -> [u32; DIGEST_WORDS] {
+    let mut state = IV;
+    for block in pad(message) {
+        compress(&mut state, &block);
+    }
+    state
+}
+
+/// Native reference: the digest of `message`.
+pub fn sha256(message: &[u8]) -> [u8; 32] {
+    let mut digest = [0; 32];
+    for (bytes, word) in digest.chunks_mut(4).zip(digest_words(message)) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+/// Splits a sum of spread words into the dense words of its even and odd bits.
+pub(crate) fn split_spread(total: u128) -> (u32, u32) {
+    assert!(total < 1 << 64, "not a sum of two spread words");
+    let (mut even, mut odd) = (0, 0);
+    for i in 0..32 {
+        even |= (((total >> (2 * i)) & 1) as u32) << i;
+        odd |= (((total >> (2 * i + 1)) & 1) as u32) << i;
+    }
+    debug_assert_eq!(
+        spread(even as u64) as u128 + 2 * spread(odd as u64) as u128,
+        total
+    );
+    (even, odd)
+}
+
+/// NIST FIPS 180-2 examples: the empty message, and one- and two-block messages.
+#[cfg(test)]
+pub(crate) const NIST_VECTORS: [(&[u8], &str); 3] = [
+    (
+        b"",
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+    ),
+    (
+        b"abc",
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+    ),
+    (
+        b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+        "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    #[test]
+    fn sha256_nist_vectors() {
+        for (message, expected) in NIST_VECTORS {
+            assert_eq!(hex(&sha256(message)), expected);
+        }
+
+        let million = vec![b'a'; 1_000_000];
+        assert_eq!(
+            hex(&sha256(&million)),
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+        );
+    }
+
+    #[test]
+    fn sha256_padding() {
+        assert_eq!(pad(b"").len(), 1);
+        // 55 bytes leave room for the one bit and the length, 56 do not.
+        assert_eq!(pad(&[0; 55]).len(), 1);
+        assert_eq!(pad(&[0; 56]).len(), 2);
+        assert_eq!(pad(&[0; 64]).len(), 2);
+
+        let blocks = pad(b"abc");
+        assert_eq!(blocks[0][0], 0x61626380);
+        assert_eq!(blocks[0][15], 24);
+    }
+
+    #[test]
+    fn sha256_split_spread() {
+        let (a, b, c) = (0xdeadbeefu64, 0x01234567u64, 0xffff0000u64);
+        let total = [a, b, c].iter().map(|x| spread(*x) as u128).sum();
+        let (xor, maj) = split_spread(total);
+        assert_eq!(xor as u64, a ^ b ^ c);
+        assert_eq!(maj as u64, (a & b) ^ (a & c) ^ (b & c));
+    }
+}
@@ -0,0 +1,877 @@
+//! Witness trace export and import.
+//!
+//! [`Trace::record`] runs a circuit's floor planner against a recording backend and
+//! keeps every assigned advice, fixed and instance cell, every enabled selector and
+//! every copy constraint, together with the region each was assigned in. A trace can
+//! be written to and read from JSON or CSV, edited cell by cell, and checked again
+//! with [`Trace::mock_prove`] to reproduce a soundness bug from a file.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use ff::{FromUniformBytes, PrimeField};
+use halo2_proofs::circuit::{Cell, Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::plonk::{
+    Advice, Any, Assigned, Assignment, Challenge, Circuit, Column, ConstraintSystem, Error, Fixed,
+    FloorPlanner, Instance, Selector,
+};
+use serde_json::json;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ColumnKind {
+    Advice,
+    Fixed,
+    Instance,
+}
+
+impl ColumnKind {
+    fn of(column: &Column<Any>) -> Self {
+        match column.column_type() {
+            Any::Advice(_) => ColumnKind::Advice,
+            Any::Fixed => ColumnKind::Fixed,
+            Any::Instance => ColumnKind::Instance,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            ColumnKind::Advice => "advice",
+            ColumnKind::Fixed => "fixed",
+            ColumnKind::Instance => "instance",
+        }
+    }
+
+    fn parse(name: &str) -> Result<Self, TraceError> {
+        match name {
+            "advice" => Ok(ColumnKind::Advice),
+            "fixed" => Ok(ColumnKind::Fixed),
+            "instance" => Ok(ColumnKind::Instance),
+            _ => Err(TraceError::Parse(format!("unknown column kind `{}`", name))),
+        }
+    }
+}
+
+/// Absolute position of a cell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CellRef {
+    pub kind: ColumnKind,
+    pub column: usize,
+    pub row: usize,
+}
+
+impl CellRef {
+    pub fn advice(column: usize, row: usize) -> Self {
+        CellRef {
+            kind: ColumnKind::Advice,
+            column,
+            row,
+        }
+    }
+
+    fn of(column: Column<Any>, row: usize) -> Self {
+        CellRef {
+            kind: ColumnKind::of(&column),
+            column: column.index(),
+            row,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceCell<F> {
+    /// `None` if the cell was assigned an unknown value (e.g. during keygen).
+    pub value: Option<F>,
+    /// Index into [`Trace::regions`], `None` for instance cells.
+    pub region: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SelectorRef {
+    /// Selectors are numbered in the order the circuit first enables them.
+    pub selector: usize,
+    pub row: usize,
+    pub region: Option<usize>,
+}
+
+#[derive(Debug)]
+pub enum TraceError {
+    /// The circuit could not be synthesized.
+    Synthesis(Error),
+    /// A trace file could not be parsed.
+    Parse(String),
+}
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceError::Synthesis(e) => write!(f, "synthesis failed: {:?}", e),
+            TraceError::Parse(e) => write!(f, "invalid trace: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for TraceError {}
+
+impl From<Error> for TraceError {
+    fn from(e: Error) -> Self {
+        TraceError::Synthesis(e)
+    }
+}
+
+/// The full assigned witness of a circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Trace<F> {
+    pub k: u32,
+    pub regions: Vec<String>,
+    pub cells: BTreeMap<CellRef, TraceCell<F>>,
+    pub selectors: Vec<SelectorRef>,
+    pub copies: Vec<(CellRef, CellRef)>,
+}
+
+/// Column and selector handles seen while recording, needed to replay a trace
+/// against the same constraint system.
+struct Layout {
+    columns: BTreeMap<(ColumnKind, usize), Column<Any>>,
+    selectors: Vec<Selector>,
+}
+
+impl<F: PrimeField> Trace<F> {
+    /// Synthesizes `circuit` with `instances` and records everything it assigns.
+    pub fn record<C: Circuit<F>>(
+        k: u32,
+        circuit: &C,
+        instances: Vec<Vec<F>>,
+    ) -> Result<Self, TraceError> {
+        Ok(Self::record_layout(k, circuit, instances)?.0)
+    }
+
+    fn record_layout<C: Circuit<F>>(
+        k: u32,
+        circuit: &C,
+        instances: Vec<Vec<F>>,
+    ) -> Result<(Self, Layout), Error> {
+        let mut cs = ConstraintSystem::default();
+        let config = C::configure_with_params(&mut cs, circuit.params());
+        if instances.len() != cs.num_instance_columns() {
+            return Err(Error::InvalidInstances);
+        }
+
+        let n = 1usize << k;
+        let mut recorder = Recorder {
+            trace: Trace {
+                k,
+                regions: vec![],
+                cells: BTreeMap::new(),
+                selectors: vec![],
+                copies: vec![],
+            },
+            layout: Layout {
+                columns: BTreeMap::new(),
+                selectors: vec![],
+            },
+            instances: &instances,
+            usable_rows: n - (cs.blinding_factors() + 1),
+            k,
+            region: None,
+        };
+
+        for (column, values) in instances.iter().enumerate() {
+            for (row, value) in values.iter().enumerate() {
+                recorder.trace.cells.insert(
+                    CellRef {
+                        kind: ColumnKind::Instance,
+                        column,
+                        row,
+                    },
+                    TraceCell {
+                        value: Some(*value),
+                        region: None,
+                    },
+                );
+            }
+        }
+
+        C::FloorPlanner::synthesize(&mut recorder, circuit, config, cs.constants().clone())?;
+
+        Ok((recorder.trace, recorder.layout))
+    }
+
+    pub fn get(&self, at: CellRef) -> Option<F> {
+        self.cells.get(&at).and_then(|cell| cell.value)
+    }
+
+    /// Overwrites the value of an assigned cell, returning the old value.
+    pub fn set(&mut self, at: CellRef, value: F) -> Option<F> {
+        let cell = self.cells.get_mut(&at)?;
+        cell.value.replace(value)
+    }
+
+    /// Instance values, one vector per instance column.
+    pub fn instances(&self) -> Vec<Vec<F>> {
+        let mut instances: Vec<Vec<F>> = vec![];
+        for (at, cell) in self.cells.range(
+            CellRef {
+                kind: ColumnKind::Instance,
+                column: 0,
+                row: 0,
+            }..,
+        ) {
+            if instances.len() <= at.column {
+                instances.resize(at.column + 1, vec![]);
+            }
+            let column = &mut instances[at.column];
+            if column.len() <= at.row {
+                column.resize(at.row + 1, F::ZERO);
+            }
+            column[at.row] = cell.value.unwrap_or(F::ZERO);
+        }
+        instances
+    }
+
+    /// Runs the `MockProver` on the values of this trace.
+    ///
+    /// Selectors and copy constraints are taken from recording `circuit` again rather
+    /// than from the trace, so an edited trace is always checked against the real
+    /// circuit.
+    pub fn mock_prove<C: Circuit<F>>(&self, circuit: &C) -> Result<MockProver<F>, TraceError>
+    where
+        F: FromUniformBytes<64> + Ord,
+    {
+        let mut cs = ConstraintSystem::default();
+        C::configure_with_params(&mut cs, circuit.params());
+        let mut instances = self.instances();
+        instances.resize(cs.num_instance_columns(), vec![]);
+
+        let (structure, layout) = Self::record_layout(self.k, circuit, instances.clone())?;
+
+        let replay = Replay {
+            circuit,
+            values: self,
+            structure: &structure,
+            layout: &layout,
+        };
+        Ok(MockProver::run(self.k, &replay, instances)?)
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let cell =
+            |at: &CellRef| json!({ "kind": at.kind.name(), "column": at.column, "row": at.row });
+
+        json!({
+            "k": self.k,
+            "regions": self.regions,
+            "cells": self.cells.iter().map(|(at, cell)| json!({
+                "kind": at.kind.name(),
+                "column": at.column,
+                "row": at.row,
+                "region": cell.region,
+                "value": cell.value.map(to_hex),
+            })).collect::<Vec<_>>(),
+            "selectors": self.selectors.iter().map(|selector| json!({
+                "selector": selector.selector,
+                "row": selector.row,
+                "region": selector.region,
+            })).collect::<Vec<_>>(),
+            "copies": self.copies.iter().map(|(left, right)| json!([cell(left), cell(right)]))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    pub fn from_json(json: &serde_json::Value) -> Result<Self, TraceError> {
+        let array = |value: &serde_json::Value, name: &str| {
+            value
+                .get(name)
+                .and_then(|v| v.as_array())
+                .cloned()
+                .ok_or_else(|| TraceError::Parse(format!("missing `{}`", name)))
+        };
+        let uint = |value: &serde_json::Value, name: &str| {
+            value
+                .get(name)
+                .and_then(|v| v.as_u64())
+                .map(|v| v as usize)
+                .ok_or_else(|| TraceError::Parse(format!("missing `{}`", name)))
+        };
+        let cell_ref = |value: &serde_json::Value| -> Result<CellRef, TraceError> {
+            Ok(CellRef {
+                kind: ColumnKind::parse(value.get("kind").and_then(|v| v.as_str()).unwrap_or(""))?,
+                column: uint(value, "column")?,
+                row: uint(value, "row")?,
+            })
+        };
+        let region = |value: &serde_json::Value| value.get("region").and_then(|v| v.as_u64());
+
+        let k = uint(json, "k")? as u32;
+        let regions = array(json, "regions")?
+            .iter()
+            .map(|name| name.as_str().map(String::from))
+            .collect::<Option<_>>()
+            .ok_or_else(|| TraceError::Parse("region names must be strings".into()))?;
+
+        let mut cells = BTreeMap::new();
+        for value in array(json, "cells")? {
+            let cell = TraceCell {
+                value: match value.get("value").and_then(|v| v.as_str()) {
+                    Some(s) => Some(from_str(s)?),
+                    None => None,
+                },
+                region: region(&value).map(|r| r as usize),
+            };
+            cells.insert(cell_ref(&value)?, cell);
+        }
+
+        let selectors = array(json, "selectors")?
+            .iter()
+            .map(|value| {
+                Ok(SelectorRef {
+                    selector: uint(value, "selector")?,
+                    row: uint(value, "row")?,
+                    region: region(value).map(|r| r as usize),
+                })
+            })
+            .collect::<Result<_, TraceError>>()?;
+
+        let copies = array(json, "copies")?
+            .iter()
+            .map(|value| match value.as_array().map(|pair| &pair[..]) {
+                Some([left, right]) => Ok((cell_ref(left)?, cell_ref(right)?)),
+                _ => Err(TraceError::Parse("copies are pairs of cells".into())),
+            })
+            .collect::<Result<_, TraceError>>()?;
+
+        Ok(Trace {
+            k,
+            regions,
+            cells,
+            selectors,
+            copies,
+        })
+    }
+
+    /// One line per cell and enabled selector:
+    /// `kind,column,row,region,value`, with `kind` one of `advice`, `fixed`,
+    /// `instance` or `selector`. Copy constraints are not included.
+    pub fn to_csv(&self) -> String {
+        let region = |region: Option<usize>| region.map(|r| r.to_string()).unwrap_or_default();
+
+        let mut csv = format!("# k = {}\n", self.k);
+        for (i, name) in self.regions.iter().enumerate() {
+            csv.push_str(&format!("# region {} = {}\n", i, name));
+        }
+        csv.push_str("kind,column,row,region,value\n");
+        for (at, cell) in self.cells.iter() {
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                at.kind.name(),
+                at.column,
+                at.row,
+                region(cell.region),
+                cell.value.map(to_hex).unwrap_or_default()
+            ));
+        }
+        for selector in self.selectors.iter() {
+            csv.push_str(&format!(
+                "selector,{},{},{},1\n",
+                selector.selector,
+                selector.row,
+                region(selector.region)
+            ));
+        }
+        csv
+    }
+
+    pub fn from_csv(csv: &str) -> Result<Self, TraceError> {
+        let parse_usize = |s: &str| {
+            s.parse::<usize>()
+                .map_err(|_| TraceError::Parse(format!("expected a number, got `{}`", s)))
+        };
+        let parse_region = |s: &str| {
+            if s.is_empty() {
+                Ok(None)
+            } else {
+                parse_usize(s).map(Some)
+            }
+        };
+
+        let mut trace = Trace {
+            k: 0,
+            regions: vec![],
+            cells: BTreeMap::new(),
+            selectors: vec![],
+            copies: vec![],
+        };
+
+        for line in csv.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if let Some(comment) = line.strip_prefix('#') {
+                let comment = comment.trim();
+                if let Some(k) = comment.strip_prefix("k = ") {
+                    trace.k = parse_usize(k)? as u32;
+                } else if let Some(region) = comment.strip_prefix("region ") {
+                    let (_, name) = region
+                        .split_once(" = ")
+                        .ok_or_else(|| TraceError::Parse(format!("bad region `{}`", region)))?;
+                    trace.regions.push(name.to_string());
+                }
+                continue;
+            }
+            if line == "kind,column,row,region,value" {
+                continue;
+            }
+
+            let fields: Vec<_> = line.split(',').collect();
+            let [kind, column, row, region, value] = fields[..] else {
+                return Err(TraceError::Parse(format!("expected 5 fields in `{}`", line)));
+            };
+            let (column, row, region) = (
+                parse_usize(column)?,
+                parse_usize(row)?,
+                parse_region(region)?,
+            );
+
+            if kind == "selector" {
+                trace.selectors.push(SelectorRef {
+                    selector: column,
+                    row,
+                    region,
+                });
+            } else {
+                let value = if value.is_empty() {
+                    None
+                } else {
+                    Some(from_str(value)?)
+                };
+                trace.cells.insert(
+                    CellRef {
+                        kind: ColumnKind::parse(kind)?,
+                        column,
+                        row,
+                    },
+                    TraceCell { value, region },
+                );
+            }
+        }
+
+        Ok(trace)
+    }
+}
+
+/// Big-endian hex of the canonical representation, like halo2curves' `Debug`.
+fn to_hex<F: PrimeField>(value: F) -> String {
+    let repr = value.to_repr();
+    let hex: String = repr
+        .as_ref()
+        .iter()
+        .rev()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("0x{}", hex)
+}
+
+/// Parses `0x`-prefixed big-endian hex as written by [`to_hex`], or a decimal string.
+fn from_str<F: PrimeField>(s: &str) -> Result<F, TraceError> {
+    let invalid = || TraceError::Parse(format!("invalid field element `{}`", s));
+
+    let Some(hex) = s.strip_prefix("0x") else {
+        return F::from_str_vartime(s).ok_or_else(invalid);
+    };
+
+    let mut repr = F::Repr::default();
+    let bytes = repr.as_mut();
+    if hex.len() > 2 * bytes.len() || hex.is_empty() {
+        return Err(invalid());
+    }
+    let hex = format!("{:0>width$}", hex, width = 2 * bytes.len());
+    for (i, byte) in bytes.iter_mut().rev().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).map_err(|_| invalid())?;
+    }
+    Option::from(F::from_repr(repr)).ok_or_else(invalid)
+}
+
+fn known<V>(value: Value<V>) -> Option<V> {
+    let mut known = None;
+    value.map(|value| known = Some(value));
+    known
+}
+
+struct Recorder<'a, F: PrimeField> {
+    trace: Trace<F>,
+    layout: Layout,
+    instances: &'a [Vec<F>],
+    usable_rows: usize,
+    k: u32,
+    region: Option<usize>,
+}
+
+impl<F: PrimeField> Recorder<'_, F> {
+    fn check_row(&self, row: usize) -> Result<(), Error> {
+        if row < self.usable_rows {
+            Ok(())
+        } else {
+            Err(Error::NotEnoughRowsAvailable { current_k: self.k })
+        }
+    }
+
+    fn assign(&mut self, column: Column<Any>, row: usize, value: Value<Assigned<F>>) {
+        self.layout
+            .columns
+            .insert((ColumnKind::of(&column), column.index()), column);
+        self.trace.cells.insert(
+            CellRef::of(column, row),
+            TraceCell {
+                value: known(value.evaluate()),
+                region: self.region,
+            },
+        );
+    }
+}
+
+impl<F: PrimeField> Assignment<F> for Recorder<'_, F> {
+    fn enter_region<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.region = Some(self.trace.regions.len());
+        self.trace.regions.push(name_fn().into());
+    }
+
+    fn annotate_column<A, AR>(&mut self, _annotation: A, _column: Column<Any>)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+    }
+
+    fn exit_region(&mut self) {
+        self.region = None;
+    }
+
+    fn enable_selector<A, AR>(
+        &mut self,
+        _annotation: A,
+        selector: &Selector,
+        row: usize,
+    ) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.check_row(row)?;
+
+        let index = match self.layout.selectors.iter().position(|s| s == selector) {
+            Some(index) => index,
+            None => {
+                self.layout.selectors.push(*selector);
+                self.layout.selectors.len() - 1
+            }
+        };
+        self.trace.selectors.push(SelectorRef {
+            selector: index,
+            row,
+            region: self.region,
+        });
+        Ok(())
+    }
+
+    fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Value<F>, Error> {
+        self.check_row(row)?;
+
+        self.instances
+            .get(column.index())
+            .and_then(|column| column.get(row))
+            .map(|value| Value::known(*value))
+            .ok_or(Error::BoundsFailure)
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _annotation: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.check_row(row)?;
+        self.assign(column.into(), row, to().map(|v| v.into()));
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _annotation: A,
+        column: Column<Fixed>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.check_row(row)?;
+        self.assign(column.into(), row, to().map(|v| v.into()));
+        Ok(())
+    }
+
+    fn copy(
+        &mut self,
+        left_column: Column<Any>,
+        left_row: usize,
+        right_column: Column<Any>,
+        right_row: usize,
+    ) -> Result<(), Error> {
+        self.check_row(left_row)?;
+        self.check_row(right_row)?;
+
+        for column in [left_column, right_column] {
+            self.layout
+                .columns
+                .insert((ColumnKind::of(&column), column.index()), column);
+        }
+        self.trace.copies.push((
+            CellRef::of(left_column, left_row),
+            CellRef::of(right_column, right_row),
+        ));
+        Ok(())
+    }
+
+    fn fill_from_row(
+        &mut self,
+        column: Column<Fixed>,
+        from_row: usize,
+        to: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        self.check_row(from_row)?;
+        for row in from_row..self.usable_rows {
+            self.assign(column.into(), row, to);
+        }
+        Ok(())
+    }
+
+    fn get_challenge(&self, _challenge: Challenge) -> Value<F> {
+        Value::unknown()
+    }
+
+    fn push_namespace<NR, N>(&mut self, _name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _gadget_name: Option<String>) {}
+}
+
+/// Re-assigns the values of a trace in a single region, with the selectors and copy
+/// constraints of `structure`, against the constraint system of `C`.
+struct Replay<'a, F: PrimeField, C: Circuit<F>> {
+    circuit: &'a C,
+    values: &'a Trace<F>,
+    structure: &'a Trace<F>,
+    layout: &'a Layout,
+}
+
+impl<F: PrimeField, C: Circuit<F>> Clone for Replay<'_, F, C> {
+    fn clone(&self) -> Self {
+        Replay {
+            circuit: self.circuit,
+            values: self.values,
+            structure: self.structure,
+            layout: self.layout,
+        }
+    }
+}
+
+impl<F: PrimeField, C: Circuit<F>> Circuit<F> for Replay<'_, F, C> {
+    type Config = C::Config;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = C::Params;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn params(&self) -> Self::Params {
+        self.circuit.params()
+    }
+
+    fn configure_with_params(meta: &mut ConstraintSystem<F>, params: Self::Params) -> Self::Config {
+        C::configure_with_params(meta, params)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        C::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        _config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let column = |at: &CellRef| {
+            self.layout
+                .columns
+                .get(&(at.kind, at.column))
+                .copied()
+                .ok_or(Error::Synthesis)
+        };
+
+        let cells: BTreeMap<CellRef, Cell> = layouter.assign_region(
+            || "replay",
+            |mut region| {
+                for selector in self.structure.selectors.iter() {
+                    self.layout.selectors[selector.selector].enable(&mut region, selector.row)?;
+                }
+
+                let mut cells = BTreeMap::new();
+                for (at, cell) in self.values.cells.iter() {
+                    let Some(value) = cell.value else {
+                        continue;
+                    };
+                    let value = Value::known(value);
+                    let assigned = match at.kind {
+                        ColumnKind::Advice => {
+                            let column = Column::<Advice>::try_from(column(at)?)
+                                .map_err(|_| Error::Synthesis)?;
+                            region.assign_advice(|| "replay", column, at.row, || value)?
+                        }
+                        ColumnKind::Fixed => {
+                            let column = Column::<Fixed>::try_from(column(at)?)
+                                .map_err(|_| Error::Synthesis)?;
+                            region.assign_fixed(|| "replay", column, at.row, || value)?
+                        }
+                        ColumnKind::Instance => continue,
+                    };
+                    cells.insert(*at, assigned.cell());
+                }
+
+                for (left, right) in self.structure.copies.iter() {
+                    if left.kind != ColumnKind::Instance && right.kind != ColumnKind::Instance {
+                        let left = cells.get(left).ok_or(Error::Synthesis)?;
+                        let right = cells.get(right).ok_or(Error::Synthesis)?;
+                        region.constrain_equal(*left, *right)?;
+                    }
+                }
+
+                Ok(cells)
+            },
+        )?;
+
+        for (left, right) in self.structure.copies.iter() {
+            let (instance, other) = match (left.kind, right.kind) {
+                (ColumnKind::Instance, ColumnKind::Instance) => return Err(Error::Synthesis),
+                (ColumnKind::Instance, _) => (left, right),
+                (_, ColumnKind::Instance) => (right, left),
+                _ => continue,
+            };
+            let column =
+                Column::<Instance>::try_from(column(instance)?).map_err(|_| Error::Synthesis)?;
+            let cell = cells.get(other).ok_or(Error::Synthesis)?;
+            layouter.constrain_instance(*cell, column, instance.row)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::add1::example1::{expected_output, MyCircuit};
+    use crate::fibonacci::example1::{fibonacci, FibonacciCircuit};
+    use crate::range_check::RangeCheckManyCircuit;
+    use ff::Field;
+    use halo2_proofs::dev::VerifyFailure;
+    use halo2curves::bn256::Fr;
+    use std::marker::PhantomData;
+
+    #[test]
+    fn trace_round_trip() {
+        let k = 5;
+        let (value, scalar, nrows) = (Fr::from(5), Fr::from(3), 10);
+        let circuit = MyCircuit {
+            value,
+            scalar,
+            nrows,
+            _marker: PhantomData,
+        };
+        let out = expected_output(value, scalar, nrows);
+
+        let trace = Trace::record(k, &circuit, vec![vec![out]]).unwrap();
+        assert_eq!(trace.regions, vec!["entire circuit".to_string()]);
+        assert_eq!(trace.get(CellRef::advice(0, nrows - 1)), Some(out));
+        assert_eq!(trace.selectors.len(), nrows - 1);
+        assert_eq!(trace.copies.len(), 1);
+
+        let json = trace.to_json();
+        assert_eq!(Trace::from_json(&json).unwrap(), trace);
+
+        let from_csv = Trace::<Fr>::from_csv(&trace.to_csv()).unwrap();
+        assert_eq!(from_csv.cells, trace.cells);
+        assert_eq!(from_csv.selectors, trace.selectors);
+        assert_eq!(from_csv.regions, trace.regions);
+
+        assert_eq!(trace.mock_prove(&circuit).unwrap().verify(), Ok(()));
+        assert_eq!(from_csv.mock_prove(&circuit).unwrap().verify(), Ok(()));
+    }
+
+    #[test]
+    fn trace_edit_cell() {
+        let k = 5;
+        let (a, b, n) = (Fr::from(1), Fr::from(1), 10);
+        let circuit = FibonacciCircuit {
+            a: Value::known(a),
+            b: Value::known(b),
+            n,
+        };
+
+        let mut trace = Trace::record(k, &circuit, vec![vec![fibonacci(a, b, n)]]).unwrap();
+        assert_eq!(trace.mock_prove(&circuit).unwrap().verify(), Ok(()));
+
+        // F(2) in the `c` column of row 0 no longer satisfies `a + b = c`.
+        let at = CellRef::advice(2, 0);
+        assert_eq!(trace.set(at, Fr::from(3)), Some(Fr::from(2)));
+        let failures = trace.mock_prove(&circuit).unwrap().verify().unwrap_err();
+        assert!(failures
+            .iter()
+            .any(|failure| matches!(failure, VerifyFailure::ConstraintNotSatisfied { .. })));
+
+        // Edits survive a round trip through a file.
+        let reloaded = Trace::<Fr>::from_json(&trace.to_json()).unwrap();
+        assert_eq!(reloaded.get(at), Some(Fr::from(3)));
+        assert!(reloaded.mock_prove(&circuit).unwrap().verify().is_err());
+    }
+
+    #[test]
+    fn trace_lookup_tables() {
+        let k = 9;
+        let circuit = RangeCheckManyCircuit {
+            values: [3u64, 200, 255].map(|v| Value::known(Fr::from(v))).to_vec(),
+            num_bits: 8,
+        };
+
+        let mut trace = Trace::record(k, &circuit, vec![]).unwrap();
+        assert_eq!(trace.mock_prove(&circuit).unwrap().verify(), Ok(()));
+
+        trace.set(CellRef::advice(0, 1), Fr::from(256));
+        let failures = trace.mock_prove(&circuit).unwrap().verify().unwrap_err();
+        assert!(matches!(failures[..], [VerifyFailure::Lookup { .. }]));
+    }
+
+    #[test]
+    fn trace_field_encoding() {
+        for value in [Fr::ZERO, Fr::ONE, Fr::from(u64::MAX), -Fr::ONE] {
+            assert_eq!(from_str::<Fr>(&to_hex(value)).unwrap(), value);
+        }
+        assert_eq!(from_str::<Fr>("0x2a").unwrap(), Fr::from(42));
+        assert_eq!(from_str::<Fr>("42").unwrap(), Fr::from(42));
+        assert!(from_str::<Fr>("0xzz").is_err());
+    }
+}
@@ -0,0 +1,563 @@
+//! `u64` add, sub and mul with Rust's wrapping and checked semantics.
+//!
+//! [`U64Chip`] keeps every word as eight bytes looked up in the byte table of a
+//! [`RuntimeRangeCheckConfig`] from `range_check::example2`. An op witnesses the
+//! wrapped result together with its carry limb: a carry or borrow bit for add and
+//! sub, checked by the small range check of the same config, and the high word of
+//! the product for mul, checked byte by byte like any other word. The overflow flag
+//! is the carry bit, or whether the high word is non-zero.
+
+use ff::PrimeFieldBits;
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+
+use crate::is_zero::{IsZeroChip, IsZeroConfig};
+use crate::range_check::example2::{RangeCheckParams, RuntimeRangeCheckConfig};
+
+// Table setup
+//
+// A word is one region, its bytes least significant first:
+//
+//   a  |  b  | selector |
+// ------------------------
+//   w  | w0  | q_bytes  |  w = sum(w_i * 256^i)
+//      | w1  |          |
+//     ...    |          |
+//      | w7  |          |
+//
+// An op is one row, with `c` the wrapped result:
+//
+//   a  |  b  |  c  | carry |  hi | hi_inv | selector |
+// ------------------------------------------------------
+//   a  |  b  |  c  | carry |     |        |  q_add   |  a + b = c + carry * 2^64
+//   a  |  b  |  c  | borrow|     |        |  q_sub   |  a + borrow * 2^64 = b + c
+//   a  |  b  |  c  | flag  |  hi | 1 / hi |  q_mul   |  a * b = c + hi * 2^64
+//
+// The bytes are copied from lookup range checks, and carries and borrows from small
+// range checks of range 2.
+
+/// Byte lookups, and a small range check of range 2 for carries.
+pub const RANGE_PARAMS: RangeCheckParams = RangeCheckParams {
+    range: 2,
+    lookup_range: 256,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum U64Op {
+    Add,
+    Sub,
+    Mul,
+}
+
+impl U64Op {
+    pub const ALL: [U64Op; 3] = [U64Op::Add, U64Op::Sub, U64Op::Mul];
+
+    /// Native reference: the wrapped result and whether the op overflowed.
+    pub fn overflowing(self, a: u64, b: u64) -> (u64, bool) {
+        match self {
+            U64Op::Add => a.overflowing_add(b),
+            U64Op::Sub => a.overflowing_sub(b),
+            U64Op::Mul => a.overflowing_mul(b),
+        }
+    }
+
+    /// Native reference: `wrapping_add`, `wrapping_sub` or `wrapping_mul`.
+    pub fn wrapping(self, a: u64, b: u64) -> u64 {
+        match self {
+            U64Op::Add => a.wrapping_add(b),
+            U64Op::Sub => a.wrapping_sub(b),
+            U64Op::Mul => a.wrapping_mul(b),
+        }
+    }
+
+    /// Native reference: `checked_add`, `checked_sub` or `checked_mul`.
+    pub fn checked(self, a: u64, b: u64) -> Option<u64> {
+        match self {
+            U64Op::Add => a.checked_add(b),
+            U64Op::Sub => a.checked_sub(b),
+            U64Op::Mul => a.checked_mul(b),
+        }
+    }
+}
+
+/// A word range checked to 64 bits.
+#[derive(Clone, Debug)]
+pub struct Word<F: PrimeFieldBits> {
+    pub value: Value<u64>,
+    pub cell: AssignedCell<F, F>,
+}
+
+/// The result of a checked op: the wrapped result, and one if the op overflowed.
+///
+/// `checked_*` returns `None` exactly when `overflow` is one, and otherwise the
+/// value of `result`.
+#[derive(Clone, Debug)]
+pub struct Checked<F: PrimeFieldBits> {
+    pub result: Word<F>,
+    pub overflow: AssignedCell<F, F>,
+}
+
+#[derive(Clone, Debug)]
+pub struct U64Config<F: PrimeFieldBits> {
+    pub a: Column<Advice>,
+    pub b: Column<Advice>,
+    pub c: Column<Advice>,
+    pub carry: Column<Advice>,
+    pub hi: Column<Advice>,
+    pub q_bytes: Selector,
+    pub q_add: Selector,
+    pub q_sub: Selector,
+    pub q_mul: Selector,
+    pub is_zero: IsZeroConfig<F>,
+    pub range: RuntimeRangeCheckConfig<F>,
+    pub instance: Column<Instance>,
+}
+
+impl<F: PrimeFieldBits> U64Config<F> {
+    fn selector(&self, op: U64Op) -> Selector {
+        match op {
+            U64Op::Add => self.q_add,
+            U64Op::Sub => self.q_sub,
+            U64Op::Mul => self.q_mul,
+        }
+    }
+}
+
+/// `u64` arithmetic over the byte lookups of `range_check::example2`.
+#[derive(Clone, Debug)]
+pub struct U64Chip<F: PrimeFieldBits> {
+    config: U64Config<F>,
+}
+
+impl<F: PrimeFieldBits> U64Chip<F> {
+    pub fn construct(config: U64Config<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> U64Config<F> {
+        // Products of two words must not wrap around the modulus.
+        assert!(F::CAPACITY > 128, "field too small for 64-bit products");
+        let two_64 = Expression::Constant(F::from_u128(1 << 64));
+
+        let [a, b, c, carry, hi, hi_inv] = [(); 6].map(|_| meta.advice_column());
+        let q_bytes = meta.selector();
+        let [q_add, q_sub, q_mul] = U64Op::ALL.map(|_| meta.selector());
+        let instance = meta.instance_column();
+
+        let range_advice = meta.advice_column();
+        let range = RuntimeRangeCheckConfig::configure(meta, range_advice, RANGE_PARAMS);
+
+        for column in [a, b, c, carry, hi, range_advice] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+
+        meta.create_gate("bytes", |meta| {
+            let q_bytes = meta.query_selector(q_bytes);
+            let word = meta.query_advice(a, Rotation::cur());
+            let bytes = (0..8).rev().fold(Expression::Constant(F::ZERO), |acc, i| {
+                acc * Expression::Constant(F::from(256)) + meta.query_advice(b, Rotation(i))
+            });
+
+            Constraints::with_selector(q_bytes, Some(word - bytes))
+        });
+
+        meta.create_gate("add", |meta| {
+            let q_add = meta.query_selector(q_add);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let c = meta.query_advice(c, Rotation::cur());
+            let carry = meta.query_advice(carry, Rotation::cur());
+
+            Constraints::with_selector(q_add, Some(a + b - c - carry * two_64.clone()))
+        });
+
+        meta.create_gate("sub", |meta| {
+            let q_sub = meta.query_selector(q_sub);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let c = meta.query_advice(c, Rotation::cur());
+            let borrow = meta.query_advice(carry, Rotation::cur());
+
+            Constraints::with_selector(q_sub, Some(a + borrow * two_64.clone() - b - c))
+        });
+
+        let is_zero = IsZeroChip::configure(meta, |meta| meta.query_selector(q_mul), hi, hi_inv);
+
+        meta.create_gate("mul", |meta| {
+            let q_mul = meta.query_selector(q_mul);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let c = meta.query_advice(c, Rotation::cur());
+            let flag = meta.query_advice(carry, Rotation::cur());
+            let hi = meta.query_advice(hi, Rotation::cur());
+
+            Constraints::with_selector(
+                q_mul,
+                [
+                    ("a * b = c + hi * 2^64", a * b - c - hi * two_64.clone()),
+                    (
+                        "flag = hi != 0",
+                        flag - (Expression::Constant(F::ONE) - is_zero.expr.clone()),
+                    ),
+                ],
+            )
+        });
+
+        U64Config {
+            a,
+            b,
+            c,
+            carry,
+            hi,
+            q_bytes,
+            q_add,
+            q_sub,
+            q_mul,
+            is_zero,
+            range,
+            instance,
+        }
+    }
+
+    pub fn config(&self) -> &U64Config<F> {
+        &self.config
+    }
+
+    /// Loads the byte table. Must be called once per circuit.
+    pub fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.config.range.table.load(layouter)
+    }
+
+    /// Witnesses a word and range checks it to 64 bits.
+    pub fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<u64>,
+    ) -> Result<Word<F>, Error> {
+        let config = &self.config;
+        let bytes = (0..8)
+            .map(|i| {
+                let byte = value.map(|value| F::from((value >> (8 * i)) & 0xff).into());
+                config
+                    .range
+                    .assign_lookup(layouter.namespace(|| format!("byte {}", i)), byte)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let cell = layouter.assign_region(
+            || "word",
+            |mut region| {
+                config.q_bytes.enable(&mut region, 0)?;
+                for (i, byte) in bytes.iter().enumerate() {
+                    byte.copy_advice(|| "byte", &mut region, config.b, i)?;
+                }
+                region.assign_advice(|| "word", config.a, 0, || value.map(F::from))
+            },
+        )?;
+        Ok(Word { value, cell })
+    }
+
+    /// `op` on `a` and `b`, with its overflow flag.
+    pub fn apply(
+        &self,
+        mut layouter: impl Layouter<F>,
+        op: U64Op,
+        a: &Word<F>,
+        b: &Word<F>,
+    ) -> Result<Checked<F>, Error> {
+        let config = &self.config;
+        let overflowing = a.value.zip(b.value).map(|(a, b)| op.overflowing(a, b));
+        let flag = overflowing.map(|(_, overflow)| F::from(overflow as u64));
+        let result = self.load_private(
+            layouter.namespace(|| "result"),
+            overflowing.map(|(value, _)| value),
+        )?;
+
+        // The carry limb: a bit for add and sub, the high word for mul.
+        let (carry, hi) = match op {
+            U64Op::Add | U64Op::Sub => {
+                let carry = config
+                    .range
+                    .assign_simple(layouter.namespace(|| "carry"), flag.map(Assigned::from))?;
+                (Some(carry), None)
+            }
+            U64Op::Mul => {
+                let hi = a
+                    .value
+                    .zip(b.value)
+                    .map(|(a, b)| ((a as u128 * b as u128) >> 64) as u64);
+                (
+                    None,
+                    Some(self.load_private(layouter.namespace(|| "hi"), hi)?),
+                )
+            }
+        };
+
+        let overflow = layouter.assign_region(
+            || format!("{:?}", op),
+            |mut region| {
+                config.selector(op).enable(&mut region, 0)?;
+                a.cell.copy_advice(|| "a", &mut region, config.a, 0)?;
+                b.cell.copy_advice(|| "b", &mut region, config.b, 0)?;
+                result.cell.copy_advice(|| "c", &mut region, config.c, 0)?;
+
+                let overflow = region.assign_advice(|| "overflow", config.carry, 0, || flag)?;
+                if let Some(carry) = &carry {
+                    region.constrain_equal(carry.cell(), overflow.cell())?;
+                }
+                if let Some(hi) = &hi {
+                    hi.cell.copy_advice(|| "hi", &mut region, config.hi, 0)?;
+                    IsZeroChip::construct(config.is_zero.clone()).assign_in_region(
+                        &mut region,
+                        0,
+                        hi.value.map(F::from),
+                    )?;
+                }
+                Ok(overflow)
+            },
+        )?;
+
+        Ok(Checked { result, overflow })
+    }
+
+    pub fn wrapping_add(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &Word<F>,
+        b: &Word<F>,
+    ) -> Result<Word<F>, Error> {
+        Ok(self.apply(layouter, U64Op::Add, a, b)?.result)
+    }
+
+    pub fn wrapping_sub(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &Word<F>,
+        b: &Word<F>,
+    ) -> Result<Word<F>, Error> {
+        Ok(self.apply(layouter, U64Op::Sub, a, b)?.result)
+    }
+
+    pub fn wrapping_mul(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &Word<F>,
+        b: &Word<F>,
+    ) -> Result<Word<F>, Error> {
+        Ok(self.apply(layouter, U64Op::Mul, a, b)?.result)
+    }
+
+    pub fn checked_add(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &Word<F>,
+        b: &Word<F>,
+    ) -> Result<Checked<F>, Error> {
+        self.apply(layouter, U64Op::Add, a, b)
+    }
+
+    pub fn checked_sub(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &Word<F>,
+        b: &Word<F>,
+    ) -> Result<Checked<F>, Error> {
+        self.apply(layouter, U64Op::Sub, a, b)
+    }
+
+    pub fn checked_mul(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &Word<F>,
+        b: &Word<F>,
+    ) -> Result<Checked<F>, Error> {
+        self.apply(layouter, U64Op::Mul, a, b)
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+/// Applies `op` to two private words.
+///
+/// Instance layout: `[op.wrapping(a, b), op.checked(a, b).is_none()]`.
+#[derive(Clone, Debug)]
+pub struct U64Circuit {
+    pub a: Value<u64>,
+    pub b: Value<u64>,
+    pub op: U64Op,
+}
+
+impl U64Circuit {
+    pub fn new(a: u64, b: u64, op: U64Op) -> Self {
+        Self {
+            a: Value::known(a),
+            b: Value::known(b),
+            op,
+        }
+    }
+
+    /// The public inputs matching `a` and `b`.
+    pub fn instances<F: PrimeFieldBits>(a: u64, b: u64, op: U64Op) -> Vec<Vec<F>> {
+        let (value, overflow) = op.overflowing(a, b);
+        vec![vec![F::from(value), F::from(overflow as u64)]]
+    }
+}
+
+impl<F: PrimeFieldBits> Circuit<F> for U64Circuit {
+    type Config = U64Config<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            a: Value::unknown(),
+            b: Value::unknown(),
+            op: self.op,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        U64Chip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = U64Chip::construct(config);
+        chip.load_table(&mut layouter)?;
+
+        let a = chip.load_private(layouter.namespace(|| "a"), self.a)?;
+        let b = chip.load_private(layouter.namespace(|| "b"), self.b)?;
+        let checked = chip.apply(layouter.namespace(|| "op"), self.op, &a, &b)?;
+
+        chip.expose_public(layouter.namespace(|| "result"), &checked.result.cell, 0)?;
+        chip.expose_public(layouter.namespace(|| "overflow"), &checked.overflow, 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{check, Model};
+    use halo2_proofs::dev::MockProver;
+    use halo2curves::pasta::Fp;
+    use proptest::prelude::*;
+    use proptest::sample::select;
+
+    /// The byte table takes 256 rows.
+    const K: u32 = 9;
+
+    #[test]
+    fn u64_native() {
+        assert_eq!(U64Op::Add.overflowing(u64::MAX, 1), (0, true));
+        assert_eq!(U64Op::Sub.overflowing(0, 1), (u64::MAX, true));
+        assert_eq!(U64Op::Mul.overflowing(1 << 32, 1 << 32), (0, true));
+        assert_eq!(
+            U64Op::Mul.checked(u32::MAX as u64, u32::MAX as u64),
+            Some(0xffff_fffe_0000_0001)
+        );
+    }
+
+    #[test]
+    fn u64_chip() {
+        let pairs = [
+            (0, 0),
+            (1, 2),
+            (u64::MAX, 1),
+            (u64::MAX, u64::MAX),
+            (1 << 32, 1 << 32),
+            (0xdead_beef, 0x1234_5678_9abc_def0),
+        ];
+        for (a, b) in pairs {
+            for op in U64Op::ALL {
+                let circuit = U64Circuit::new(a, b, op);
+                let instances = U64Circuit::instances::<Fp>(a, b, op);
+                let prover = MockProver::run(K, &circuit, instances).unwrap();
+                prover.assert_satisfied();
+            }
+        }
+    }
+
+    #[test]
+    fn u64_chip_rejects() {
+        let run = |a: u64, b: u64, op: U64Op, value: u64, overflow: u64| {
+            let circuit = U64Circuit::new(a, b, op);
+            let instances = vec![vec![Fp::from(value), Fp::from(overflow)]];
+            MockProver::run(K, &circuit, instances).unwrap().verify()
+        };
+
+        // Unwrapped results, or results claimed without their overflow.
+        assert!(run(u64::MAX, 1, U64Op::Add, 0, 0).is_err());
+        assert!(run(0, 1, U64Op::Sub, u64::MAX, 0).is_err());
+        assert!(run(1 << 32, 1 << 32, U64Op::Mul, 0, 0).is_err());
+        // An overflow where there is none.
+        assert!(run(1, 2, U64Op::Add, 3, 1).is_err());
+        assert!(run(2, 3, U64Op::Mul, 6, 1).is_err());
+    }
+
+    struct U64Model;
+
+    impl Model<Fp> for U64Model {
+        /// `(a, b, op, claim)`, with `claim` the claimed `(result, overflow)`, or
+        /// `None` for the honest one.
+        type Input = (u64, u64, U64Op, Option<(u64, bool)>);
+        type Circuit = U64Circuit;
+
+        fn k(_: &Self::Input) -> u32 {
+            K
+        }
+
+        fn circuit(&(a, b, op, claim): &Self::Input) -> (Self::Circuit, Vec<Vec<Fp>>) {
+            let (value, overflow) = claim.unwrap_or_else(|| op.overflowing(a, b));
+            let instances = vec![vec![Fp::from(value), Fp::from(overflow as u64)]];
+            (U64Circuit::new(a, b, op), instances)
+        }
+
+        fn accepts(&(a, b, op, claim): &Self::Input) -> bool {
+            match claim {
+                None => true,
+                Some((value, overflow)) => {
+                    value == op.wrapping(a, b) && overflow == op.checked(a, b).is_none()
+                }
+            }
+        }
+    }
+
+    fn word() -> impl Strategy<Value = u64> {
+        prop_oneof![
+            select(vec![0, 1, u32::MAX as u64, 1 << 32, u64::MAX - 1, u64::MAX]),
+            any::<u32>().prop_map(u64::from),
+            any::<u64>(),
+        ]
+    }
+
+    fn input() -> impl Strategy<Value = (u64, u64, U64Op, Option<(u64, bool)>)> {
+        (word(), word(), select(U64Op::ALL.to_vec())).prop_flat_map(|(a, b, op)| {
+            let (value, overflow) = op.overflowing(a, b);
+            let claim = prop_oneof![
+                2 => Just(None),
+                1 => Just(Some((value, !overflow))),
+                1 => (word(), any::<bool>()).prop_map(Some),
+            ];
+            (Just(a), Just(b), Just(op), claim)
+        })
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn u64_model(input in input()) {
+            check::<_, U64Model>(&input).map_err(TestCaseError::fail)?;
+        }
+    }
+}